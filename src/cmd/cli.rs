@@ -27,6 +27,29 @@ pub enum CliCommand {
         new: bool,
         #[arg(short, long, help = "Pick from a list of multiple saves instead of the last used")]
         pick: bool,
+        #[arg(
+            short,
+            long = "mod",
+            help = "A mod directory to load after those in the manifest. Can be repeated"
+        )]
+        mods: Vec<Utf8PathBuf>,
+        #[arg(short, long, help = "Fix the random seed, for reproducible playthroughs")]
+        seed: Option<u64>,
+        #[arg(short, long, help = "Load and validate the game without running it")]
+        check: bool,
+    },
+    #[command(about = "Export a game's prompt graph in the Graphviz DOT format")]
+    Graph {
+        #[arg(help = "The game directory. Defaults to the current directory")]
+        path: Option<Utf8PathBuf>,
+        #[arg(
+            short,
+            long = "mod",
+            help = "A mod directory to load after those in the manifest. Can be repeated"
+        )]
+        mods: Vec<Utf8PathBuf>,
+        #[arg(short, long, help = "The file to write the graph to. Defaults to standard output")]
+        output: Option<Utf8PathBuf>,
     },
     #[command(about = "Create a new Nagame template")]
     New {
@@ -1,3 +1,5 @@
+use std::cell::RefCell;
+
 use anyhow::{anyhow, Result};
 use clap::Parser;
 
@@ -10,13 +12,13 @@ use crate::{
         prompt::Prompt as PromptUtil,
         resources::Resources,
         state::{
+            achievements::{Achievement, Achievements},
             info::{InfoPages, UnlockedInfoPages},
-            notes::Notes,
         },
         text::display::Translations,
     },
     game::gloop::GameLoopResult,
-    loading::saves::SaveManager,
+    loading::{exports, saves::SaveManager},
 };
 
 #[derive(Parser, Debug, PartialEq)]
@@ -24,20 +26,58 @@ use crate::{
 pub enum RuntimeCommand {
     #[command(about = "Try going back a choice")]
     Back,
+    #[command(about = "Bookmark the current prompt to return to later")]
+    Bookmark,
+    #[command(about = "Return to a bookmark, discarding any progress made since")]
+    Return,
     #[command(about = "Manage the display language")]
     Lang,
     #[command(about = "Display an info page")]
     Info,
     #[command(about = "Display an action log page")]
     Log,
+    #[command(about = "List the unlocked achievements")]
+    Achievements,
     #[command(about = "Manage sound effects and music channels")]
     Sound,
+    #[command(about = "Turn a sound channel on or off")]
+    Channel {
+        /// The name of the channel.
+        name: String,
+        /// Whether to turn the channel on or off. Toggles it if omitted.
+        #[arg(value_parser = ["on", "off"])]
+        state: Option<String>,
+    },
+    #[command(about = "Try connecting to the sound device again")]
+    Reconnect,
+    #[command(about = "Write a transcript of the playthrough so far")]
+    Transcript,
+    #[command(about = "Export the player data to a portable file")]
+    Export,
+    #[command(about = "Replace the player data with an exported file")]
+    Import {
+        /// The export file, either a path or a file name within the exports directory.
+        file: String,
+    },
     #[command(about = "Save the player data")]
     Save,
     #[command(about = "Save and quits the game")]
     Quit,
     #[command(about = "Display debug info about a prompt", hide = true)]
     Prompt,
+    #[command(about = "Jump directly to a prompt", hide = true)]
+    Jump {
+        /// The prompt to jump to, optionally prefixed with its file and a slash.
+        path: Option<String>,
+        /// A tag to jump to a prompt carrying instead, picking one if there are several.
+        #[arg(short, long, conflicts_with = "path")]
+        tag: Option<String>,
+    },
+    #[command(about = "List the prompt tags, or the prompts carrying a tag", hide = true)]
+    Tags {
+        /// The tag to list the prompts of.
+        tag: Option<String>,
+    },
     #[command(about = "List the currently applied notes", hide = true)]
     Notes,
     #[command(about = "List the currently applied variable names and their values", hide = true)]
@@ -62,7 +102,23 @@ impl RuntimeCommand {
     /// Determines if this command is allowed in a default, non-debug environment.
     fn is_normal(&self) -> bool {
         use RuntimeCommand::*;
-        matches!(&self, Back | Lang | Info | Log | Sound | Save | Quit)
+        matches!(
+            &self,
+            Back | Bookmark
+                | Return
+                | Lang
+                | Info
+                | Log
+                | Achievements
+                | Sound
+                | Channel { .. }
+                | Reconnect
+                | Transcript
+                | Export
+                | Import { .. }
+                | Save
+                | Quit
+        )
     }
 
     /// Handles a [`Back`](RuntimeCommand::Back) command.
@@ -74,7 +130,65 @@ impl RuntimeCommand {
         Ok(CommandResult::Submit(GameLoopResult::Continue))
     }
 
+    /// Handles a [`Bookmark`](RuntimeCommand::Bookmark) command.
+    ///
+    /// Bookmarks only last for the session. If the player has as many as the manifest allows, the oldest is dropped.
+    fn bookmark(player: &Player, stc: &StaticContext) -> Result<CommandResult> {
+        let slots = stc.config.settings.history.bookmarks;
+        if slots == 0 {
+            return Err(anyhow!("Bookmarks are turned off"));
+        }
+        let bookmark = player.bookmark()?;
+        let label = bookmark.label();
+        let mut bookmarks = stc.resources.bookmarks.borrow_mut();
+        bookmarks.push(bookmark);
+        if bookmarks.len() > slots {
+            bookmarks.remove(0);
+        }
+        Ok(CommandResult::Output(format!("Bookmarked {label}")))
+    }
+
+    /// Handles a [`Return`](RuntimeCommand::Return) command.
+    ///
+    /// If the player has several bookmarks, one is selected. Returning restores the story state from when the
+    /// [`Bookmark`](crate::core::player::Bookmark) was made and keeps it, so that the player can return again.
+    fn return_to(player: &mut Player, resources: &Resources) -> Result<CommandResult> {
+        let bookmarks = resources.bookmarks.borrow();
+        let bookmark = match bookmarks.as_slice() {
+            [] => return Err(anyhow!("No bookmarks to return to")),
+            [bookmark] => bookmark,
+            _ => {
+                println!();
+                let question = requestty::Question::select("Return to bookmark")
+                    .choices(bookmarks.iter().rev().map(|bookmark| bookmark.label()))
+                    .build();
+                let index = requestty::prompt_one(question)?
+                    .as_list_item()
+                    .unwrap()
+                    .index;
+                &bookmarks[bookmarks.len() - 1 - index]
+            }
+        };
+        player.return_to(bookmark);
+        println!("\nReturned to {}\n", bookmark.label());
+        Ok(CommandResult::Submit(GameLoopResult::Continue))
+    }
+
+    /// Handles an [`Import`](RuntimeCommand::Import) command.
+    ///
+    /// The imported player only [replaces](Resources::replace_player) the current one for the session until it's saved.
+    /// Bookmarks are dropped since they belong to the previous playthrough.
+    fn import(player: &mut Player, file: &str, stc: &StaticContext) -> Result<CommandResult> {
+        let imported = exports::import(file, stc.config, stc.resources)?;
+        stc.resources.replace_player(player, imported, stc.config)?;
+        stc.resources.bookmarks.borrow_mut().clear();
+        println!("\nImported player data from '{file}'\n");
+        Ok(CommandResult::Submit(GameLoopResult::Continue))
+    }
+
     /// Handles a [`Lang`](RuntimeCommand::Lang) command.
+    ///
+    /// Once a language is picked, the current prompt is printed again in it.
     fn lang(player: &mut Player, translations: &Translations) -> Result<CommandResult> {
         if translations.is_empty() {
             return Err(anyhow!("No display languages loaded"));
@@ -88,7 +202,8 @@ impl RuntimeCommand {
         let lang_choice = requestty::prompt_one(lang_question)?;
         player.lang = lang_choice.as_list_item().unwrap().text.clone();
 
-        Ok(CommandResult::retry())
+        // Re-render the current prompt in the new language
+        Ok(CommandResult::Submit(GameLoopResult::Continue))
     }
 
     /// Handles an [`Info`](RuntimeCommand::Info) command.
@@ -138,11 +253,21 @@ impl RuntimeCommand {
         Ok(CommandResult::Output(format!("\n{entries}")))
     }
 
+    /// Handles an [`Achievements`](RuntimeCommand::Achievements) command.
+    fn achievements(player: &Player, achievements: &Achievements) -> Result<CommandResult> {
+        if achievements.is_empty() {
+            return Err(anyhow!("No achievements to display"));
+        }
+        let list = Achievement::list(achievements, &player.achievements);
+        Ok(CommandResult::Output(format!("\n{list}")))
+    }
+
     /// Handles a [`Sound`](RuntimeCommand::Sound) command.
-    fn sound(player: &mut Player, audio_res: &Option<Audio>) -> Result<CommandResult> {
-        let audio = audio_res
+    fn sound(player: &mut Player, audio_res: &Option<RefCell<Audio>>) -> Result<CommandResult> {
+        let mut audio = audio_res
             .as_ref()
-            .ok_or(anyhow!("No sound channels loaded"))?;
+            .ok_or(anyhow!("No sound channels loaded"))?
+            .borrow_mut();
 
         println!();
 
@@ -163,24 +288,57 @@ impl RuntimeCommand {
 
         // Each possible channel will either be selected or not; if so, append to player's
         // enabled channel list if not already present, otherwise remove and stop the channel playback if necessary
-        for channel in audio.players.keys() {
-            if enabled_channels.contains(channel) {
-                player.channels.insert(channel.clone());
-            } else {
-                player.channels.remove(channel);
-                audio.get_player(channel)?.stop();
-            }
+        let channels: Vec<String> = audio.channels.keys().cloned().collect();
+        for name in channels {
+            audio.set_enabled(player, &name, enabled_channels.contains(&name))?;
         }
 
+        let volume_question = requestty::Question::float("Master volume")
+            .default(audio.master_volume() as f64)
+            .validate(|value, _| {
+                if (0.0..=1.0).contains(&value) {
+                    Ok(())
+                } else {
+                    Err("Volume must be between 0.0 and 1.0".to_owned())
+                }
+            })
+            .build();
+        let volume_choice = requestty::prompt_one(volume_question)?;
+        audio.set_master_volume(volume_choice.as_float().unwrap() as f32)?;
+
         Ok(CommandResult::retry())
     }
 
-    /// Handles a [`Prompt`](RuntimeCommand::Prompt) command.
-    fn prompt(
-        notes: &Notes,
-        resources: &Resources,
-        text_context: &TextContext,
+    /// Handles a [`Channel`](RuntimeCommand::Channel) command.
+    ///
+    /// Disabling a channel stops it right away, while enabling one only lets later sounds play on it.
+    fn channel(
+        player: &mut Player,
+        audio_res: &Option<RefCell<Audio>>,
+        name: &str,
+        state: &Option<String>,
     ) -> Result<CommandResult> {
+        let mut audio = audio_res
+            .as_ref()
+            .ok_or(anyhow!("No sound channels loaded"))?
+            .borrow_mut();
+        if !audio.channels.contains_key(name) {
+            return Err(anyhow!("Invalid sound channel '{name}'"));
+        }
+        let enabled = match state.as_deref() {
+            Some(state) => state == "on",
+            None => !audio.is_enabled(player, name),
+        };
+        audio.set_enabled(player, name, enabled)?;
+        let status = match enabled {
+            true => "on",
+            false => "off",
+        };
+        Ok(CommandResult::Output(format!("Turned channel '{name}' {status}")))
+    }
+
+    /// Prompts the player to select a prompt file and then a prompt within it.
+    fn select_prompt(resources: &Resources) -> Result<PathData> {
         println!();
 
         let file_question = requestty::Question::select("Prompt file")
@@ -195,16 +353,96 @@ impl RuntimeCommand {
         let prompt_choice = requestty::prompt_one(prompt_question)?;
         let prompt = &prompt_choice.as_list_item().unwrap().text;
 
-        let lookup: PathData = PathLookup::new(file, prompt).into();
+        Ok(PathLookup::new(file, prompt).into())
+    }
+
+    /// Handles a [`Prompt`](RuntimeCommand::Prompt) command.
+    fn prompt(
+        player: &Player,
+        resources: &Resources,
+        text_context: &TextContext,
+    ) -> Result<CommandResult> {
+        let lookup = Self::select_prompt(resources)?;
         let prompt = PromptUtil::get(&resources.prompts, &lookup)?;
+        let path = lookup.into();
         Ok(CommandResult::Output(prompt.debug_info(
-            &lookup.into(),
+            &path,
             &resources.prompts,
-            notes,
+            &player.notes,
+            &player.used_choices_at(&path),
             text_context,
         )?))
     }
 
+    /// Prompts the user to pick one of the prompts carrying a tag, if there are several.
+    fn select_tagged(resources: &Resources, tag: &str) -> Result<PathData> {
+        let paths = resources
+            .tags
+            .get(tag)
+            .ok_or(anyhow!("No prompts are tagged '{tag}'"))?;
+        if let [path] = paths.as_slice() {
+            return Ok(path.clone());
+        }
+        println!();
+        let question = requestty::Question::select(format!("Prompt tagged '{tag}'"))
+            .choices(paths.iter().map(|path| path.to_string()))
+            .build();
+        let index = requestty::prompt_one(question)?
+            .as_list_item()
+            .unwrap()
+            .index;
+        Ok(paths[index].clone())
+    }
+
+    /// Handles a [`Jump`](RuntimeCommand::Jump) command.
+    ///
+    /// A `path` without a file refers to a prompt in the current file. If a `tag` is given, a prompt carrying it
+    /// is jumped to instead. If neither is given, a prompt is selected.
+    fn jump(
+        player: &mut Player,
+        path: &Option<String>,
+        tag: &Option<String>,
+        stc: &StaticContext,
+    ) -> Result<CommandResult> {
+        let lookup = match path {
+            _ if tag.is_some() => Self::select_tagged(stc.resources, tag.as_deref().unwrap())?,
+            Some(path) => {
+                let current = &player.latest_entry()?.path;
+                match path.rsplit_once('/') {
+                    Some((file, prompt)) => PathLookup::new(file, prompt).into(),
+                    None => PathLookup::new(&current.file, path).into(),
+                }
+            }
+            None => Self::select_prompt(stc.resources)?,
+        };
+        PromptUtil::get(&stc.resources.prompts, &lookup)?;
+        player.jump(&lookup, stc.config);
+        println!("\nJumped to '{lookup}', bypassing choice requirements\n");
+        Ok(CommandResult::Submit(GameLoopResult::Continue))
+    }
+
+    /// Handles a [`Tags`](RuntimeCommand::Tags) command.
+    fn tags(resources: &Resources, tag: &Option<String>) -> Result<CommandResult> {
+        let result = match tag {
+            Some(tag) => resources
+                .tags
+                .get(tag)
+                .ok_or(anyhow!("No prompts are tagged '{tag}'"))?
+                .iter()
+                .map(|path| path.to_string())
+                .collect::<Vec<String>>()
+                .join("\n"),
+            None if resources.tags.is_empty() => return Err(anyhow!("No prompts are tagged")),
+            None => resources
+                .tags
+                .iter()
+                .map(|(tag, paths)| format!("{tag} ({})", paths.len()))
+                .collect::<Vec<String>>()
+                .join(", "),
+        };
+        Ok(CommandResult::Output(result))
+    }
+
     /// Handles a [`Notes`](RuntimeCommand::Notes) command.
     fn notes(player: &Player) -> Result<CommandResult> {
         if player.notes.is_empty() {
@@ -246,16 +484,38 @@ impl RuntimeCommand {
         use RuntimeCommand::*;
         let result = match self {
             Back => Self::back(player)?,
+            Bookmark => Self::bookmark(player, stc)?,
+            Return => Self::return_to(player, stc.resources)?,
             Lang => Self::lang(player, &stc.resources.translations)?,
             Info => Self::info(&player.info_pages, &stc.resources.info_pages)?,
             Log => Self::log(&player)?,
+            Achievements => Self::achievements(player, &stc.config.achievements)?,
             Sound => Self::sound(player, &stc.resources.audio)?,
+            Channel { name, state } => Self::channel(player, &stc.resources.audio, name, state)?,
+            Reconnect => match (&stc.resources.audio, &stc.config.settings.channels) {
+                (Some(_), _) => return Err(anyhow!("Already connected to the sound device")),
+                (None, None) => return Err(anyhow!("No sound channels declared")),
+                (None, Some(_)) => Submit(GameLoopResult::ReconnectAudio),
+            },
+            Transcript => {
+                let file = stc.resources.transcript.export(stc.config)?;
+                Output(format!("Wrote transcript to {file}"))
+            }
+            Export => {
+                stc.resources.record_audio(player);
+                let file = exports::export(player, stc.config)?;
+                Output(format!("Exported player data to {file}"))
+            }
+            Import { file } => Self::import(player, file, stc)?,
             Save => {
+                stc.resources.record_audio(player);
                 saves.write(player)?;
-                Output("Saving... ".to_owned())
+                Output(format!("Saving... {}", player.playtime_display()))
             }
             Quit => Submit(GameLoopResult::Shutdown(false)),
-            Prompt => Self::prompt(&player.notes, stc.resources, text_context)?,
+            Prompt => Self::prompt(player, stc.resources, text_context)?,
+            Jump { path, tag } => Self::jump(player, path, tag, stc)?,
+            Tags { tag } => Self::tags(stc.resources, tag)?,
             Notes => Self::notes(player)?,
             Variables => Self::variables(player)?,
         };
@@ -2,11 +2,15 @@ pub mod audio;
 pub mod choice;
 pub mod context;
 pub mod discord;
+pub mod fast_forward;
 pub mod manifest;
 pub mod path;
+pub mod pause;
 pub mod player;
 pub mod prompt;
+pub mod reachability;
 pub mod resources;
 pub mod scripts;
 pub mod state;
 pub mod text;
+pub mod transcript;
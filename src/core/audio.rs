@@ -1,9 +1,12 @@
 use std::{
-    collections::{BTreeMap, HashMap},
-    time::Duration,
+    collections::{BTreeMap, HashMap, HashSet},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
 use anyhow::{anyhow, Context as _, Result};
+use crossterm::style::Stylize;
+use itertools::Itertools;
 use playback_rs::{Player as AudioPlayer, Song};
 use result::OptionResultExt;
 use rlua::{Context, Table};
@@ -15,12 +18,28 @@ use crate::{
     loading::loader::Loader,
 };
 
-use super::{context::TextContext, manifest::Manifest, player::Player};
+use super::{
+    context::TextContext,
+    manifest::{DuckingSettings, Manifest, SoundDefaults, SoundLoading},
+    player::Player,
+};
+
+use self::sound::{Fade, Layer, Mix, Sound, SoundSource};
 
-/// A map of channel names to audio player instances and whether they are currently enabled.
-pub type AudioPlayers = HashMap<String, AudioPlayer>;
-/// A map of song names to decoded song content.
-pub type Sounds = BTreeMap<String, Song>;
+pub mod sound;
+
+/// A map of channel names to audio channels.
+pub type Channels = HashMap<String, Channel>;
+/// A map of sound names to decoded sound content.
+pub type Sounds = BTreeMap<String, Sound>;
+/// A map of sound names to their undecoded files.
+pub type SoundSources = BTreeMap<String, SoundSource>;
+/// A map of channel names to the sounds looping on them.
+pub type Loops = HashMap<String, Loop>;
+/// A map of channel names to the playlists playing on them.
+pub type Playlists = HashMap<String, Playlist>;
+/// A map of channel names to the sounds playing on them when the game was saved.
+pub type ChannelSounds = HashMap<String, ChannelSound>;
 
 #[derive(Deserialize, Serialize, Display, Debug, Clone, EnumString, EnumIter)]
 #[serde(rename_all = "snake_case")]
@@ -41,6 +60,22 @@ pub enum SoundActionMode {
     Pause,
     /// Un-pauses a channel.
     Play,
+    /// Fades out the sound playing in a channel while fading in another over the same duration.
+    Crossfade,
+    /// Immediately plays a sound on the channel and restarts it from the `seek` position, or the beginning, whenever it ends.
+    Loop,
+    /// Blocks the game until the sound playing in a channel finishes, or until the action's `timeout` elapses.
+    Await,
+    /// Immediately plays the first of the action's `tracks`, or of the manifest playlist named by `name`,
+    /// then plays each next track when the current one ends, starting over after the last.
+    Playlist,
+    /// Moves a channel's playlist onto its next track immediately, crossfading if a `fade` is provided.
+    NextTrack,
+    /// Stops every enabled channel, fading each out if a `fade` is provided. Doesn't use a channel.
+    StopAll,
+    /// Skips the sound named by `name`, or any sound from the sound group it names, on every channel it's playing on,
+    /// fading it out if a `fade` is provided. Doesn't use a channel, and does nothing if the sound isn't playing.
+    StopNamed,
 }
 
 impl Default for SoundActionMode {
@@ -50,10 +85,31 @@ impl Default for SoundActionMode {
 }
 
 impl SoundActionMode {
+    /// Whether this action ends a [`Loop`](SoundActionMode::Loop) playing on its channel.
+    pub fn breaks_loop(&self) -> bool {
+        use SoundActionMode::*;
+        matches!(&self, Queue | Overwrite | Crossfade | Loop | Playlist | Skip | Pause | StopAll)
+    }
+
+    /// Whether this action ends a [`Playlist`](SoundActionMode::Playlist) playing on its channel.
+    ///
+    /// Skipping or pausing a playlist's track doesn't end it; a skipped track is followed by the next one.
+    pub fn ends_playlist(&self) -> bool {
+        use SoundActionMode::*;
+        matches!(&self, Queue | Overwrite | Crossfade | Loop | Playlist | StopAll)
+    }
+
+    /// Whether this action applies to a single channel.
+    pub fn uses_channel(&self) -> bool {
+        !matches!(&self, SoundActionMode::StopAll | SoundActionMode::StopNamed)
+    }
+
     /// Whether this action requires a specific sound file to be present.
+    ///
+    /// [`Playlist`](SoundActionMode::Playlist) actions name a playlist rather than a sound file, so they aren't specific.
     pub fn is_specific(&self) -> bool {
         use SoundActionMode::*;
-        matches!(&self, Queue | Overwrite | Passive)
+        matches!(&self, Queue | Overwrite | Passive | Crossfade | Loop)
     }
 }
 
@@ -63,11 +119,16 @@ impl SoundActionMode {
 /// Essentially a wrapper around [`playback_rs`] functionality.
 pub struct SoundAction {
     #[serde(skip_serializing_if = "Option::is_none")]
-    /// The sound file to submit.
+    /// The sound file to submit, or a sound group to pick a random file from.
     /// Only required for specific [`SoundActionMode`]s.
+    ///
+    /// For [`Playlist`](SoundActionMode::Playlist) actions, this names a playlist from the manifest instead.
     pub name: Option<TemplatableString>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// The channel to modify playback on.
-    pub channel: TemplatableString,
+    /// Required unless the [`SoundActionMode`] doesn't [use a channel](SoundActionMode::uses_channel)
+    /// or the manifest sets a `default_channel`.
+    pub channel: Option<TemplatableString>,
     #[serde(default)]
     /// The method to apply to the sound channel.
     pub mode: TemplatableValue<SoundActionMode>,
@@ -77,29 +138,295 @@ pub struct SoundAction {
     #[serde(skip_serializing_if = "Option::is_none")]
     /// The playback multiplier of the sound.
     pub speed: Option<TemplatableValue<f64>>,
+    #[serde(alias = "preserve pitch")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether a `speed` change keeps the sound's original pitch by [time-stretching](Sound::stretched) it.
+    /// Defaults to `false`, where speeding a sound up also raises its pitch.
+    pub preserve_pitch: Option<TemplatableValue<bool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The volume of the channel, from `0.0` to `1.0`.
+    pub volume: Option<TemplatableValue<f32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The stereo balance of the channel, from `-1.0` (full left) to `1.0` (full right).
+    pub pan: Option<TemplatableValue<f32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// How long, in milliseconds, to fade the sound in or out over.
+    /// Applies when playing a sound immediately, crossfading into a sound, and when skipping, pausing, or un-pausing a channel.
+    pub fade: Option<TemplatableValue<u64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The longest time, in milliseconds, that an [`Await`](SoundActionMode::Await) action blocks for.
+    pub timeout: Option<TemplatableValue<u64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// How long, in milliseconds, after the same `name` last played on the same channel that this action is ignored for,
+    /// so that a sound triggered rapidly doesn't stack up. For sound groups, the group counts as the same sound whichever
+    /// file it picks. Actions that don't end up playing the sound don't start a cooldown.
+    pub cooldown: Option<TemplatableValue<u64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Text describing the sound, announced in its name's place in [accessibility mode](SoundActionData::announcement).
+    pub caption: Option<TemplatableString>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The sound files or sound groups that a [`Playlist`](SoundActionMode::Playlist) action plays in order,
+    /// if it doesn't name a playlist from the manifest.
+    pub tracks: Option<Vec<TemplatableString>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether a [`Playlist`](SoundActionMode::Playlist) action plays its tracks in a random order,
+    /// drawn from the [`Player`]'s generator. Defaults to `false`.
+    pub shuffle: Option<TemplatableValue<bool>>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(untagged)]
+/// The background music a [prompt](crate::core::prompt::Prompt::music) declares, either just the track to loop or its full settings.
+///
+/// Music is only started when it differs from what's playing on its channel,
+/// so moving between prompts that share a track doesn't restart it.
+pub enum Music {
+    Track(TemplatableString),
+    Settings(MusicSettings),
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
+/// The full form of a prompt's [`Music`].
+pub struct MusicSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The sound file to loop, or a sound group to pick a random file from. If omitted, the music is stopped.
+    pub track: Option<TemplatableString>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The channel the music plays on. Required unless the manifest sets a `default_channel`.
+    pub channel: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The volume of the channel, from `0.0` to `1.0`.
+    pub volume: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// How long, in milliseconds, to fade the music in when it starts, or out when it stops.
+    pub fade: Option<u64>,
+}
+
+impl Music {
+    /// The [`SoundAction`] that starts this music, or stops the music on its channel if it has no track.
+    pub fn action(&self) -> SoundAction {
+        let (track, channel, volume, fade) = match self {
+            Music::Track(track) => (Some(track.clone()), None, None, None),
+            Music::Settings(settings) => {
+                (settings.track.clone(), settings.channel.clone(), settings.volume, settings.fade)
+            }
+        };
+        let mode = match track {
+            Some(_) => SoundActionMode::Loop,
+            None => SoundActionMode::Skip,
+        };
+        SoundAction {
+            name: track,
+            channel: channel.map(TemplatableString::from),
+            mode: TemplatableValue::value(mode),
+            seek: None,
+            speed: None,
+            preserve_pitch: None,
+            volume: volume.map(TemplatableValue::value),
+            pan: None,
+            fade: fade.map(TemplatableValue::value),
+            timeout: None,
+            cooldown: None,
+            caption: None,
+            tracks: None,
+            shuffle: None,
+        }
+    }
 }
 
 /// A collection of ordered [`SoundAction`]s to be submitted in order.
 pub type SoundActions = Vec<SoundAction>;
 
+/// A [`SoundAction`] with all of its templatable values filled in.
+///
+/// Sound actions are filled before they reach the [`Audio`] resource so that any scripts
+/// evaluated along the way are free to read the audio state.
+pub struct SoundActionData {
+    pub name: Option<String>,
+    pub channel: Option<String>,
+    pub mode: SoundActionMode,
+    pub seek: Option<Duration>,
+    pub speed: Option<f64>,
+    pub preserve_pitch: bool,
+    pub volume: Option<f32>,
+    pub pan: Option<f32>,
+    pub fade: Option<Duration>,
+    pub timeout: Option<Duration>,
+    pub cooldown: Option<Duration>,
+    pub caption: Option<String>,
+    pub tracks: Option<Vec<String>>,
+    pub shuffle: bool,
+}
+
+impl SoundActionData {
+    /// The text announcing this action's sound in accessibility mode, if it starts one on an enabled channel.
+    ///
+    /// A [`Passive`](SoundActionMode::Passive) action is only announced if its channel has nothing playing,
+    /// since the sound is skipped otherwise.
+    pub fn announcement(&self, audio: &Audio, player: &Player) -> Option<String> {
+        let channel = audio.channel_of(self)?;
+        if !(self.mode.is_specific() || matches!(self.mode, SoundActionMode::Playlist))
+            || !audio.is_declared(channel)
+            || !audio.is_enabled(player, channel)
+        {
+            return None;
+        }
+        if matches!(self.mode, SoundActionMode::Passive)
+            && audio
+                .channel_states()
+                .iter()
+                .any(|(name, state)| *name == channel && state.has_sound)
+        {
+            return None;
+        }
+        let first = self.tracks.as_ref().and_then(|tracks| tracks.first());
+        let caption = self.caption.as_ref().or(self.name.as_ref()).or(first)?;
+        Some(format!("[Sound: {caption}]"))
+    }
+}
+
 impl SoundAction {
+    /// Fills every templatable value of this action into a [`SoundActionData`].
+    pub fn fill(&self, text_context: &TextContext) -> Result<SoundActionData> {
+        let result = SoundActionData {
+            name: self
+                .name
+                .as_ref()
+                .map(|name| name.fill(text_context))
+                .invert()?,
+            channel: self
+                .channel
+                .as_ref()
+                .map(|channel| channel.fill(text_context))
+                .invert()?,
+            mode: self.mode.get_value(text_context)?,
+            seek: self
+                .seek
+                .as_ref()
+                .map(|ms| ms.get_value(text_context).map(Duration::from_millis))
+                .invert()?,
+            speed: self
+                .speed
+                .as_ref()
+                .map(|speed| speed.get_value(text_context).and_then(Self::check_speed))
+                .invert()?,
+            preserve_pitch: self
+                .preserve_pitch
+                .as_ref()
+                .map(|preserve| preserve.get_value(text_context))
+                .invert()?
+                .unwrap_or(false),
+            volume: self
+                .volume
+                .as_ref()
+                .map(|volume| volume.get_value(text_context))
+                .invert()?,
+            pan: self
+                .pan
+                .as_ref()
+                .map(|pan| pan.get_value(text_context))
+                .invert()?,
+            fade: self
+                .fade
+                .as_ref()
+                .map(|ms| ms.get_value(text_context).map(Duration::from_millis))
+                .invert()?,
+            timeout: self
+                .timeout
+                .as_ref()
+                .map(|ms| ms.get_value(text_context).map(Duration::from_millis))
+                .invert()?,
+            cooldown: self
+                .cooldown
+                .as_ref()
+                .map(|ms| ms.get_value(text_context).map(Duration::from_millis))
+                .invert()?,
+            caption: self
+                .caption
+                .as_ref()
+                .map(|caption| caption.fill(text_context))
+                .invert()?,
+            tracks: self
+                .tracks
+                .as_ref()
+                .map(|tracks| {
+                    tracks
+                        .iter()
+                        .map(|track| track.fill(text_context))
+                        .collect::<Result<Vec<String>>>()
+                })
+                .invert()?,
+            shuffle: self
+                .shuffle
+                .as_ref()
+                .map(|shuffle| shuffle.get_value(text_context))
+                .invert()?
+                .unwrap_or(false),
+        };
+        Ok(result)
+    }
+
+    /// Checks that a `speed` is a finite multiplier above zero, since sounds can't be stretched to no length or backwards.
+    fn check_speed(speed: f64) -> Result<f64> {
+        if !speed.is_finite() || speed <= 0.0 {
+            return Err(anyhow!("Sound speed must be above zero, but it's {speed}"));
+        }
+        Ok(speed)
+    }
+
     /// Validates a single [`SoundAction`] against the [`Audio`] resource.
     ///
     /// A sound action is valid if:
-    /// - Its `name` key matches a loaded sound effect
-    /// - Its `channel` key matches a created audio channel
+    /// - Its `name` key matches a loaded sound effect or sound group, or a manifest playlist for [`Playlist`](SoundActionMode::Playlist) actions
+    /// - Each of its `tracks` matches a loaded sound effect or sound group
     /// - The [specificity](SoundActionMode::is_specific) of its `mode` matches whether the sound effect is present
+    /// - Its `channel` key is present if its `mode` [uses a channel](SoundActionMode::uses_channel), unless there's a default channel
+    /// - Its `speed`, if not templated, is [positive](SoundAction::check_speed)
     pub fn validate(&self, audio: &Audio) -> Result<()> {
+        if let Some(speed) = self.speed.as_ref().and_then(|speed| speed.value) {
+            Self::check_speed(speed)?;
+        }
+        let playlist = matches!(self.mode.value, Some(SoundActionMode::Playlist));
         if let Some(name) = &self.name {
             if let Some(sound) = name.content() {
-                let _ = audio.get_sound(sound)?;
+                if playlist {
+                    if !audio.playlists.contains_key(sound) {
+                        return Err(anyhow!("Invalid playlist '{sound}'"));
+                    }
+                } else if !audio.groups.contains_key(sound) {
+                    audio.validate_sound(sound)?;
+                }
             }
         }
-        if let Some(channel) = self.channel.content() {
-            let _ = audio.get_player(channel)?;
+        for track in self
+            .tracks
+            .iter()
+            .flatten()
+            .filter_map(|track| track.content())
+        {
+            if !audio.groups.contains_key(track) {
+                audio.validate_sound(track)?;
+            }
         }
         if let Some(mode) = &self.mode.value {
-            if mode.is_specific() && self.name.is_none() {
+            if playlist {
+                if self.name.is_some() == self.tracks.is_some() {
+                    return Err(anyhow!(
+                        "Sound action 'playlist' requires either a playlist name or a list of tracks"
+                    ));
+                }
+            } else if self.tracks.is_some() {
+                return Err(anyhow!(
+                    "Sound action '{mode}' does not use tracks, but they are provided"
+                ));
+            } else if matches!(mode, SoundActionMode::StopNamed) {
+                if self.name.is_none() {
+                    return Err(anyhow!(
+                        "Sound action '{mode}' requires a sound effect name, but none is provided"
+                    ));
+                }
+            } else if mode.is_specific() && self.name.is_none() {
                 return Err(anyhow!(
                     "Sound action '{mode}' requires a sound effect name, but none is provided"
                 ));
@@ -108,10 +435,56 @@ impl SoundAction {
                     "Sound action '{mode}' does not use a sound effect, but one is provided"
                 ));
             }
+            if self.cooldown.is_some() && !mode.is_specific() {
+                return Err(anyhow!(
+                    "Sound action '{mode}' does not play a sound effect, but a cooldown is provided"
+                ));
+            }
+            if mode.uses_channel() && self.channel.is_none() && audio.default_channel.is_none() {
+                return Err(anyhow!(
+                    "Sound action '{mode}' requires a channel, but none is provided and `settings.default_channel` isn't set"
+                ));
+            }
         }
         Ok(())
     }
 
+    /// Checks the references of this action that can't be fully validated on load, returning a warning for each.
+    ///
+    /// - A static `channel` that isn't declared in the manifest still works, since channels are created on demand, but is often a typo
+    /// - A templated `channel` or `name` can't be checked until the action is submitted
+    pub fn lint(&self, audio: &Audio) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if let Some(channel) = &self.channel {
+            match channel.content() {
+                Some(channel) if !audio.is_declared(channel) => warnings
+                    .push(format!("Sound channel '{channel}' isn't declared in the manifest")),
+                Some(_) => {}
+                None => warnings.push("Sound channel is templated and can't be checked".to_owned()),
+            }
+        }
+        if let Some(name) = &self.name {
+            if name.content().is_none() {
+                warnings.push("Sound name is templated and can't be checked".to_owned());
+            }
+        }
+        warnings
+    }
+
+    /// Checks a list of [`SoundActions`] in order using [`SoundAction::lint`].
+    pub fn lint_all(sounds: &SoundActions, audio: &Audio) -> Vec<String> {
+        sounds
+            .iter()
+            .enumerate()
+            .flat_map(|(index, sound)| {
+                sound
+                    .lint(audio)
+                    .into_iter()
+                    .map(move |warning| format!("Sound action #{}: {warning}", index + 1))
+            })
+            .collect()
+    }
+
     /// Validates a list of [`SoundActions`] in order using [`SoundAction::validate`].
     pub fn validate_all(sounds: &SoundActions, audio: &Audio) -> Result<()> {
         for (index, sound) in sounds.iter().enumerate() {
@@ -123,173 +496,2068 @@ impl SoundAction {
     }
 }
 
-/// A container for [`AudioPlayers`] and [`Sounds`].
-///
-/// A pair of a channel and an audio player corresponds to a single connection to a sound device,
-/// wherein one sound file can be playing at a time. Overlapping sounds requires multiple connections
-/// and playing on different channels.
+/// What a [`Song`] was [rendered](Sound::render) from, so that the render can be reused while it stays the same.
+#[derive(Debug, Clone, PartialEq)]
+struct RenderKey {
+    /// The name of the rendered sound.
+    name: String,
+    gain: f32,
+    fade: Option<Fade>,
+    end: Option<Duration>,
+    /// The start and offset of the crossfade layer, if any.
+    layer: Option<(Duration, Duration)>,
+    pan: f32,
+    tempo: f64,
+}
+
+impl RenderKey {
+    fn new(name: &str, mix: &Mix) -> Self {
+        Self {
+            name: name.to_owned(),
+            gain: mix.gain,
+            fade: mix.fade,
+            end: mix.end,
+            layer: mix.layer.as_ref().map(|layer| (layer.start, layer.offset)),
+            pan: mix.pan,
+            tempo: mix.tempo,
+        }
+    }
+}
+
+/// Renders of a [`Channel`]'s current sound, and of its queued sound if any, running on another thread.
+struct PendingRender {
+    current: RenderKey,
+    next: Option<RenderKey>,
+    handle: JoinHandle<Result<(Song, Option<Song>)>>,
+}
+
+/// An [`AudioPlayer`] along with the playback state tracked on top of it.
 ///
-/// Channels are only created on startup. They are never dynamically loaded and must
-/// be specified in the manifest file prior to runtime.
-pub struct Audio {
-    pub players: AudioPlayers,
-    sounds: Sounds,
+/// The names of the current and queued sounds are tracked so that they can be
+/// [re-rendered](Sound::render) whenever the channel's volume or fade changes.
+pub struct Channel {
+    player: AudioPlayer,
+    /// The volume that sounds are rendered at on this channel.
+    volume: f32,
+    /// The [master volume](Audio::set_master_volume) scaling this channel's volume.
+    master: f32,
+    /// The stereo balance that sounds are rendered with on this channel.
+    pan: f32,
+    /// The factor that sounds are [time-stretched](Sound::stretched) by on this channel.
+    tempo: f64,
+    /// The volume multiplier applied while this channel is being [ducked](DuckingSettings).
+    duck: f32,
+    /// The name of the sound currently playing, if any.
+    current: Option<String>,
+    /// The name of the sound queued to play after the current one, if any.
+    next: Option<String>,
+    /// The fade envelope the current sound was rendered with, if any.
+    fade: Option<Fade>,
+    /// The position at which the current sound was cut off to end a fade-out, if any.
+    end: Option<Duration>,
+    /// The position to resume the current sound from after a fading pause, if any.
+    paused: Option<Duration>,
+    /// The outgoing sound of a crossfade, mixed on top of the current sound, if any.
+    layer: Option<Layer>,
+    /// Whether the channel had a sound as of the last [tick](Audio::tick).
+    sounding: bool,
+    /// Renders started by a [mix change](Channel::apply_mix) that haven't been swapped in yet, if any.
+    pending: Option<PendingRender>,
+    /// The most recent renders, oldest first, so that looping a sound or going back to a recent mix,
+    /// such as when ducking ends, doesn't render it again.
+    renders: Vec<(RenderKey, Song)>,
 }
 
-impl Audio {
-    /// Creates [`AudioPlayers`]s and maps them to the config settings' `channels`.
-    fn load_players(config: &Manifest) -> Option<Result<AudioPlayers>> {
-        config.settings.channels.as_ref().map(|channels| {
-            channels
-                .iter()
-                .map(|(channel, _)| {
-                    AudioPlayer::new(None)
-                        .map(|player| (channel.clone(), player))
-                        .map_err(|err| anyhow!(err))
-                })
-                .try_collect()
-        })
+impl Channel {
+    /// How many [renders](Channel::render) each channel keeps for reuse.
+    const CACHED_RENDERS: usize = 4;
+
+    fn new(player: AudioPlayer, master: f32) -> Self {
+        Self {
+            player,
+            volume: 1.0,
+            master,
+            pan: 0.0,
+            tempo: 1.0,
+            duck: 1.0,
+            current: None,
+            next: None,
+            fade: None,
+            end: None,
+            paused: None,
+            layer: None,
+            sounding: false,
+            pending: None,
+            renders: Vec::new(),
+        }
     }
 
-    /// Loads an [`Audio`] container.
+    /// The volume sounds are actually rendered at, accounting for the master volume and ducking.
+    fn gain(&self) -> f32 {
+        self.volume * self.master * self.duck
+    }
+
+    /// Whether the channel is audibly playing a sound.
+    fn is_sounding(&self) -> bool {
+        self.player.has_current_song() && self.is_playing()
+    }
+
+    /// The [`Mix`] that the current sound is rendered with.
+    fn mix(&self) -> Mix {
+        Mix {
+            gain: self.gain(),
+            fade: self.fade,
+            end: self.end,
+            layer: self.layer.clone(),
+            pan: self.pan,
+            tempo: self.tempo,
+        }
+    }
+
+    /// The [`Mix`] that queued sounds are rendered with.
+    fn queued_mix(&self) -> Mix {
+        Mix::flat(self.gain(), self.pan, self.tempo)
+    }
+
+    /// Renders a sound with a [`Mix`], reusing a recent render of the same sound and mix if there is one.
+    fn render(&mut self, name: &str, sound: &Sound, mix: &Mix) -> Result<Song> {
+        let key = RenderKey::new(name, mix);
+        if let Some(song) = self.cached(&key) {
+            return Ok(song);
+        }
+        let song = sound.render(mix)?;
+        self.cache(key, song.clone());
+        Ok(song)
+    }
+
+    fn cached(&self, key: &RenderKey) -> Option<Song> {
+        self.renders
+            .iter()
+            .find(|(other, _)| other == key)
+            .map(|(_, song)| song.clone())
+    }
+
+    /// Keeps a render for reuse, dropping the oldest one if there are too many.
+    ///
+    /// Renders with a crossfade layer aren't kept, since the layer is only ever mixed in once.
+    fn cache(&mut self, key: RenderKey, song: Song) {
+        if key.layer.is_some() {
+            return;
+        }
+        self.renders.retain(|(other, _)| *other != key);
+        if self.renders.len() == Self::CACHED_RENDERS {
+            self.renders.remove(0);
+        }
+        self.renders.push((key, song));
+    }
+
+    /// Converts a position in a sound to the matching position in its [stretched](Channel::tempo) render.
+    fn stretch_seek(&self, seek: Option<Duration>) -> Option<Duration> {
+        seek.map(|seek| seek.div_f64(self.tempo))
+    }
+
+    /// The playback position of the current sound, if any.
+    fn position(&self) -> Option<Duration> {
+        self.player.get_playback_position().map(|(pos, _)| pos)
+    }
+
+    /// Whether the channel is playing and not paused, including by a fade.
+    pub fn is_playing(&self) -> bool {
+        self.player.is_playing() && self.paused.is_none()
+    }
+
+    /// Whether the current sound is partway through a fade or crossfade.
+    pub fn is_fading(&self) -> bool {
+        let Some(position) = self.position() else {
+            return false;
+        };
+        let fading = self
+            .fade
+            .map(|fade| fade.is_active(position))
+            .unwrap_or(false);
+        let crossfading = self
+            .layer
+            .as_ref()
+            .map(|layer| layer.is_active(position))
+            .unwrap_or(false);
+        fading || crossfading
+    }
+
+    /// Reconciles the tracked sound names with what the player is actually playing.
+    fn sync(&mut self) {
+        if self.paused.is_some() {
+            return;
+        }
+        let finished = if self.next.is_some() && !self.player.has_next_song() {
+            self.current = self.next.take();
+            true
+        } else {
+            !self.player.has_current_song()
+        };
+        if finished {
+            if !self.player.has_current_song() {
+                self.current = None;
+            }
+            self.fade = None;
+            self.end = None;
+            self.layer = None;
+        }
+    }
+
+    /// Immediately plays a sound, discarding any that were playing or queued.
     ///
-    /// If [`AudioPlayer`] creation using [`load_players`](Self::load_players) fails, it fails silently
-    /// and brings the down the whole audio system with it, signaling [None] within the wrapped option.
+    /// If a `fade` is provided, the sound fades in over that duration.
+    fn play_now(
+        &mut self,
+        name: &str,
+        sound: &Sound,
+        seek: Option<Duration>,
+        fade: Option<Duration>,
+    ) -> Result<()> {
+        let seek = self.stretch_seek(seek);
+        self.fade = fade.map(|length| Fade {
+            start: seek.unwrap_or_default(),
+            length,
+            from: 0.0,
+            to: 1.0,
+        });
+        self.end = None;
+        self.paused = None;
+        self.layer = None;
+        let song = self.render(name, sound, &self.mix())?;
+        self.player
+            .play_song_now(&song, seek)
+            .map_err(|err| anyhow!(err))?;
+        self.current = Some(name.to_owned());
+        self.next = None;
+        Ok(())
+    }
+
+    /// Fades out the current sound while fading in another over the same duration, discarding any queued sound.
     ///
-    /// An [`Err`] is only returned if [`load_sounds`](Self::load_sounds) errors.
-    pub fn load(loader: &Loader, config: &Manifest) -> Result<Option<Self>> {
-        Self::load_players(config)
-            .map(|result| {
-                result.ok().map(|players| {
-                    loader
-                        .load_sounds("sounds")
-                        .map(|sounds| Self { players, sounds })
-                })
-            })
-            .flatten()
-            .invert()
+    /// Both sounds share this channel's player by being [layered](Layer) into a single render;
+    /// the outgoing sound is dropped once the channel next syncs after finishing.
+    /// If nothing is playing, this behaves like [`Channel::play_now`] with a fade-in.
+    fn crossfade(
+        &mut self,
+        name: &str,
+        sound: &Sound,
+        seek: Option<Duration>,
+        fade: Duration,
+        sounds: &Sounds,
+    ) -> Result<()> {
+        self.sync();
+        let (current, position) = match (&self.current, self.position()) {
+            (Some(current), Some(position)) if self.is_playing() => (current, position),
+            _ => return self.play_now(name, sound, seek, Some(fade)),
+        };
+        let seek = self.stretch_seek(seek);
+        let start = seek.unwrap_or_default();
+        self.layer = Some(Layer {
+            sound: Audio::get_sound_from(sounds, current)?.stretched(self.tempo),
+            start,
+            offset: position,
+            fade: Fade {
+                start,
+                length: fade,
+                from: self.mix().factor(position),
+                to: 0.0,
+            },
+        });
+        self.fade = Some(Fade {
+            start,
+            length: fade,
+            from: 0.0,
+            to: 1.0,
+        });
+        self.end = None;
+        self.paused = None;
+        let song = self.render(name, sound, &self.mix())?;
+        self.player
+            .play_song_now(&song, seek)
+            .map_err(|err| anyhow!(err))?;
+        self.current = Some(name.to_owned());
+        self.next = None;
+        Ok(())
     }
 
-    /// Retrieves an [`AudioPlayer`], if any, by a channel name.
-    pub fn get_player(&self, channel: &str) -> Result<&AudioPlayer> {
-        self.players
-            .get(channel)
-            .ok_or(anyhow!("Invalid sound channel '{channel}'"))
+    /// Queues a sound to play after the current one, or immediately if the channel is free.
+    fn play_next(&mut self, name: &str, sound: &Sound, seek: Option<Duration>) -> Result<()> {
+        self.sync();
+        let idle = !self.player.has_current_song();
+        let song = self.render(name, sound, &self.queued_mix())?;
+        self.player
+            .play_song_next(&song, self.stretch_seek(seek))
+            .map_err(|err| anyhow!(err))?;
+        if idle {
+            self.current = Some(name.to_owned());
+        } else {
+            self.next = Some(name.to_owned());
+        }
+        Ok(())
     }
 
-    /// Retrieves a [`Song`], if any, by a sound name.
-    pub fn get_sound(&self, name: &str) -> Result<&Song> {
-        self.sounds
-            .get(name)
-            .ok_or(anyhow!("Invalid sound file '{name}'"))
+    /// Re-queues the tracked next sound after the player's queue has been discarded.
+    fn requeue(&mut self, sounds: &Sounds) -> Result<()> {
+        if let Some(next) = self.next.clone() {
+            let sound = Audio::get_sound_from(sounds, &next)?;
+            let song = self.render(&next, sound, &self.queued_mix())?;
+            self.player
+                .play_song_next(&song, None)
+                .map_err(|err| anyhow!(err))?;
+        }
+        Ok(())
     }
 
-    /// Returns this controller's channel names mapped to whether they are enabled on the [`Player`].
-    pub fn channel_statuses(&self, player: &Player) -> Vec<(String, bool)> {
-        self.players
-            .keys()
-            .map(|channel| (channel.clone(), player.channels.contains(channel)))
-            .collect()
+    /// Re-renders the current sound with its [`Mix`] and resumes it from `position`.
+    fn rerender(&mut self, position: Duration, sounds: &Sounds) -> Result<()> {
+        if let Some(current) = self.current.clone() {
+            let sound = Audio::get_sound_from(sounds, &current)?;
+            let song = self.render(&current, sound, &self.mix())?;
+            self.player
+                .play_song_now(&song, Some(position))
+                .map_err(|err| anyhow!(err))?;
+        }
+        Ok(())
     }
 
-    /// Creates a Lua table mapping each loaded audio player to a table of their data.
+    /// Re-renders the current sound to fade out from its present gain over `length`.
     ///
-    /// This table is formatted as follows:
-    /// - `is_playing`: Whether the player is not paused
-    /// - `has_sound`: Whether the player has a sound currently playing
-    /// - `has_sound_queued`: Whether the player has a sound queued, but not playing
-    /// - `position`: If the player has a sound playing, returns the position in milliseconds
-    /// - `sound_duration`: If the player has a sound playing, returns its duration in milliseconds
-    pub fn create_audio_table<'a>(&self, context: &Context<'a>) -> Result<Table<'a>, rlua::Error> {
-        let table = context.create_table()?;
-        for (channel, player) in &self.players {
-            let channel_table = context.create_table()?;
-            channel_table.set("is_playing", player.is_playing())?;
-            channel_table.set("has_sound", player.has_current_song())?;
-            channel_table.set("has_sound_queued", player.has_next_song())?;
-            if let Some((pos, duration)) = player.get_playback_position() {
-                channel_table.set("position", pos.as_millis())?;
-                channel_table.set("sound_duration", duration.as_millis())?;
-            }
-            table.set(channel.clone(), channel_table)?;
+    /// The rendered sound is cut off once the fade completes, and any fade already in progress is superseded.
+    /// Returns the position the sound was cut off at, or [`None`] if there was nothing to fade.
+    fn fade_out(&mut self, length: Duration, sounds: &Sounds) -> Result<Option<Duration>> {
+        self.sync();
+        let position = match self.position() {
+            Some(position) if self.current.is_some() && self.is_playing() => position,
+            _ => return Ok(None),
+        };
+        self.fade = Some(Fade {
+            start: position,
+            length,
+            from: self.mix().factor(position),
+            to: 0.0,
+        });
+        if let Some(layer) = &mut self.layer {
+            layer.fade = Fade {
+                start: position,
+                length,
+                from: layer.fade.factor(position),
+                to: 0.0,
+            };
         }
-        Ok(table)
+        let end = position + length;
+        self.end = Some(end);
+        self.rerender(position, sounds)?;
+        Ok(Some(end))
     }
 
-    /// Applies actions requiring that a specified sound file is **not** present.
-    fn accept_general(player: &AudioPlayer, seek: Option<Duration>, mode: SoundActionMode) {
-        use SoundActionMode::*;
-        if let Some(duration) = seek {
-            player.seek(duration);
+    /// Skips the current sound, moving onto the queued one if present.
+    ///
+    /// If a `fade` is provided, the sound fades out over that duration before the queued one starts.
+    fn skip(&mut self, fade: Option<Duration>, sounds: &Sounds) -> Result<()> {
+        if let Some(length) = fade {
+            if self.fade_out(length, sounds)?.is_some() {
+                return self.requeue(sounds);
+            }
         }
-        match mode {
-            Skip => player.skip(),
-            Play => player.set_playing(true),
-            Pause => player.set_playing(false),
-            _ => (),
+        self.player.skip();
+        self.current = self.next.take();
+        self.fade = None;
+        self.end = None;
+        self.layer = None;
+        Ok(())
+    }
+
+    /// Pauses the channel.
+    ///
+    /// If a `fade` is provided, the sound fades out over that duration and resumes from where the fade ended.
+    fn pause(&mut self, fade: Option<Duration>, sounds: &Sounds) -> Result<()> {
+        if let Some(length) = fade {
+            if let Some(end) = self.fade_out(length, sounds)? {
+                self.paused = Some(end);
+                return Ok(());
+            }
         }
+        self.player.set_playing(false);
+        Ok(())
     }
 
-    /// Applies actions requiring both a [`SoundActionMode`] and accompanying sound effect.
-    fn accept_specific(
-        player: &AudioPlayer,
-        sfx: &Song,
-        seek: Option<Duration>,
-        mode: SoundActionMode,
-    ) {
-        use SoundActionMode::*;
-        let _ = match mode {
-            Queue => player.play_song_next(sfx, seek),
-            Overwrite => player.play_song_now(sfx, seek),
-            Passive => {
-                if !player.has_current_song() {
-                    player.play_song_now(sfx, seek)
+    /// Un-pauses the channel.
+    ///
+    /// If a `fade` is provided, the sound fades in from where it was paused over that duration.
+    fn play(&mut self, fade: Option<Duration>, sounds: &Sounds) -> Result<()> {
+        let resume = self.paused.take().or_else(|| {
+            fade.and_then(|_| {
+                if self.player.is_playing() {
+                    None
                 } else {
-                    Ok(())
+                    self.position()
                 }
+            })
+        });
+        if let Some(position) = resume {
+            self.fade = fade.map(|length| Fade {
+                start: position,
+                length,
+                from: 0.0,
+                to: 1.0,
+            });
+            self.end = None;
+            self.rerender(position, sounds)?;
+            self.requeue(sounds)?;
+        }
+        self.player.set_playing(true);
+        Ok(())
+    }
+
+    /// Stops all playback and clears the queue.
+    ///
+    /// If a `fade` is provided, the current sound fades out over that duration first.
+    fn stop_fading(&mut self, fade: Option<Duration>, sounds: &Sounds) -> Result<()> {
+        if let Some(length) = fade {
+            self.next = None;
+            if self.fade_out(length, sounds)?.is_some() {
+                return Ok(());
             }
-            _ => Ok(()),
-        };
+        }
+        self.stop();
+        Ok(())
     }
 
-    /// Applies a [`SoundAction`] to a particular channel.
-    pub fn accept(
-        &self,
-        player: &Player,
-        action: &SoundAction,
-        text_context: &TextContext,
-    ) -> Result<()> {
-        let channel = action.channel.fill(text_context)?;
-        let audio_player = self.get_player(&channel)?;
+    /// Stops all playback and clears the queue.
+    fn stop(&mut self) {
+        self.player.stop();
+        self.current = None;
+        self.next = None;
+        self.fade = None;
+        self.end = None;
+        self.paused = None;
+        self.layer = None;
+    }
+
+    /// Sets the channel volume, clamped between `0.0` and `1.0`, and re-renders
+    /// the current and queued sounds at the new level.
+    ///
+    /// The current sound resumes from the position it was at, keeping any fade in progress.
+    fn set_volume(&mut self, volume: f32, sounds: &Sounds) -> Result<()> {
+        self.volume = volume.clamp(0.0, 1.0);
+        self.apply_mix(sounds)
+    }
 
-        if !player.channels.contains(&channel) {
+    /// Sets the master volume scaling this channel and re-renders its sounds like [`Channel::set_volume`].
+    fn set_master(&mut self, master: f32, sounds: &Sounds) -> Result<()> {
+        self.master = master;
+        self.apply_mix(sounds)
+    }
+
+    /// Sets the volume multiplier applied by [ducking](DuckingSettings), re-rendering only if it changed.
+    fn set_duck(&mut self, duck: f32, sounds: &Sounds) -> Result<()> {
+        if self.duck == duck {
             return Ok(());
         }
+        self.duck = duck;
+        self.apply_mix(sounds)
+    }
 
-        let seek = action
-            .seek
-            .as_ref()
-            .map(|ms| {
-                ms.get_value(text_context)
-                    .map(|amt| Duration::from_millis(amt))
-            })
-            .invert()?;
+    /// Sets the channel's stereo balance, clamped between `-1.0` and `1.0`, and re-renders its sounds like [`Channel::set_volume`].
+    fn set_pan(&mut self, pan: f32, sounds: &Sounds) -> Result<()> {
+        self.pan = pan.clamp(-1.0, 1.0);
+        self.apply_mix(sounds)
+    }
 
-        let mode = action.mode.get_value(text_context)?;
+    /// Sets the factor that sounds on this channel are [time-stretched](Sound::stretched) by.
+    ///
+    /// If a sound is playing, it is re-rendered at the new tempo and resumes from the same point in the sound.
+    /// Any fade or crossfade in progress is dropped, since its timing no longer lines up with the render.
+    fn set_tempo(&mut self, tempo: f64, sounds: &Sounds) -> Result<()> {
+        if self.tempo == tempo {
+            return Ok(());
+        }
+        self.sync();
+        let position = self
+            .position()
+            .map(|position| position.mul_f64(self.tempo).div_f64(tempo));
+        self.tempo = tempo;
+        self.fade = None;
+        self.end = None;
+        self.layer = None;
+        if let Some(position) = position {
+            self.rerender(position, sounds)?;
+            if self.paused.is_none() {
+                self.requeue(sounds)?;
+            }
+        }
+        Ok(())
+    }
 
-        match &action.name {
-            None => Self::accept_general(audio_player, seek, mode),
-            Some(name) => {
-                let sound = name.fill(text_context)?;
-                let sfx = self.get_sound(&sound)?;
-                Self::accept_specific(audio_player, sfx, seek, mode);
+    /// Re-renders the current and queued sounds after the channel's [gain](Channel::gain) or pan changes.
+    ///
+    /// Sounds that haven't been rendered with the new mix [recently](Channel::render) are rendered on another thread,
+    /// so that long sounds don't hold up the game. They keep playing with their previous mix until the next
+    /// [tick](Audio::tick) after rendering finishes, when the new renders are swapped in at the position reached by then.
+    fn apply_mix(&mut self, sounds: &Sounds) -> Result<()> {
+        self.sync();
+        self.pending = None;
+        let (Some(current), Some(_)) = (self.current.clone(), self.position()) else {
+            return Ok(());
+        };
+        let mix = self.mix();
+        let current =
+            (RenderKey::new(&current, &mix), Audio::get_sound_from(sounds, &current)?.clone(), mix);
+        let next = match (&self.next, self.paused) {
+            (Some(next), None) => {
+                let mix = self.queued_mix();
+                let sound = Audio::get_sound_from(sounds, next)?.clone();
+                Some((RenderKey::new(next, &mix), sound, mix))
             }
+            _ => None,
+        };
+        let current_song = self.cached(&current.0);
+        let next_song = next.as_ref().and_then(|(key, ..)| self.cached(key));
+        if let (Some(song), true) = (&current_song, next.is_none() || next_song.is_some()) {
+            let next = next.map(|(key, ..)| key).zip(next_song);
+            return self.swap_in((current.0, song.clone()), next, sounds);
+        }
+        self.pending = Some(PendingRender {
+            current: current.0.clone(),
+            next: next.as_ref().map(|(key, ..)| key.clone()),
+            handle: thread::spawn(move || {
+                let (_, sound, mix) = current;
+                let current = current_song.map_or_else(|| sound.render(&mix), Ok)?;
+                let next = next
+                    .map(|(_, sound, mix)| next_song.map_or_else(|| sound.render(&mix), Ok))
+                    .invert()?;
+                Ok((current, next))
+            }),
+        });
+        Ok(())
+    }
+
+    /// Swaps in the [pending renders](Channel::apply_mix) once they're done.
+    fn finish_render(&mut self, sounds: &Sounds) -> Result<()> {
+        if !self
+            .pending
+            .as_ref()
+            .is_some_and(|pending| pending.handle.is_finished())
+        {
+            return Ok(());
         }
+        let Some(pending) = self.pending.take() else {
+            return Ok(());
+        };
+        let (current, next) = pending
+            .handle
+            .join()
+            .map_err(|_| anyhow!("Sound rendering thread panicked"))??;
+        self.swap_in((pending.current, current), pending.next.zip(next), sounds)
+    }
 
-        if let Some(speed) = &action.speed {
-            audio_player.set_playback_speed(speed.get_value(text_context)?);
+    /// Plays a render of the current sound from the position it has reached, followed by a render of the queued sound.
+    ///
+    /// Renders that no longer match what the channel is playing, such as after it has moved onto another sound or started
+    /// a fade, are only kept for reuse. The queued sound is rendered again if its render is missing or doesn't match.
+    fn swap_in(
+        &mut self,
+        current: (RenderKey, Song),
+        next: Option<(RenderKey, Song)>,
+        sounds: &Sounds,
+    ) -> Result<()> {
+        self.sync();
+        let expected =
+            |name: &Option<String>, mix: Mix| name.as_ref().map(|name| RenderKey::new(name, &mix));
+        let playing = expected(&self.current, self.mix()).as_ref() == Some(&current.0);
+        let queued = expected(&self.next, self.queued_mix());
+        self.cache(current.0, current.1.clone());
+        let next = next.map(|(key, song)| {
+            self.cache(key.clone(), song.clone());
+            (key, song)
+        });
+        let Some(position) = self.position().filter(|_| playing) else {
+            return Ok(());
+        };
+        self.player
+            .play_song_now(&current.1, Some(position))
+            .map_err(|err| anyhow!(err))?;
+        if self.paused.is_some() {
+            return Ok(());
         }
+        match next {
+            Some((key, song)) if queued.as_ref() == Some(&key) => self
+                .player
+                .play_song_next(&song, None)
+                .map_err(|err| anyhow!(err)),
+            _ => self.requeue(sounds),
+        }
+    }
+}
 
-        Ok(())
+/// A sound played on repeat by a [`Loop`](SoundActionMode::Loop) action.
+#[derive(Debug, Clone)]
+pub struct Loop {
+    /// The name of the looping sound.
+    pub name: String,
+    /// The position the sound restarts from each time it ends.
+    pub from: Option<Duration>,
+}
+
+/// The tracks played in turn by a [`Playlist`](SoundActionMode::Playlist) action.
+#[derive(Debug, Clone)]
+pub struct Playlist {
+    /// The names of the tracks in the order they're played, after any shuffling.
+    ///
+    /// A shuffled playlist is only shuffled once when it starts, so every cycle plays in the same order.
+    pub tracks: Vec<String>,
+    /// The index of the track currently playing.
+    pub current: usize,
+}
+
+impl Playlist {
+    /// Moves onto the next track, starting over after the last, and returns its name.
+    fn advance(&mut self) -> &str {
+        self.current = (self.current + 1) % self.tracks.len();
+        &self.tracks[self.current]
+    }
+}
+
+/// A sound a channel was playing when the game was saved, recorded on the [`Player`] so that it can be
+/// [resumed](Audio::restore) when the save is loaded.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChannelSound {
+    /// The name of the sound file.
+    pub name: String,
+    #[serde(default)]
+    /// Whether the sound was [looping](SoundActionMode::Loop). Loops restart from the beginning of the sound each time.
+    pub looping: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// The playback position of the sound, in milliseconds.
+    pub position: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// The tracks of the [playlist](Playlist) the sound belongs to, in the order they were being played.
+    pub playlist: Option<Vec<String>>,
+}
+
+/// A channel that the game is blocked on by an [`Await`](SoundActionMode::Await) action.
+#[derive(Debug, Clone)]
+pub struct Await {
+    /// The name of the awaited channel.
+    pub channel: String,
+    /// The point at which the game stops waiting regardless of the channel, if any.
+    pub deadline: Option<Instant>,
+}
+
+/// The channels paused by [`Audio::suspend`], to be picked back up by [`Audio::resume`].
+pub struct Suspension {
+    channels: Vec<String>,
+    since: Instant,
+}
+
+/// A change in playback reported by [`Audio::drain_events`].
+#[derive(Debug, Clone)]
+pub enum AudioEvent {
+    /// A channel ran out of sounds to play on its own.
+    Finished(String),
+    /// A channel was skipped or stopped by an action or the player.
+    Interrupted(String),
+}
+
+/// A snapshot of a [`Channel`]'s playback state; see [`Audio::create_audio_table`] for the meaning of each field.
+pub struct ChannelState {
+    pub is_playing: bool,
+    pub has_sound: bool,
+    pub has_sound_queued: bool,
+    pub volume: f32,
+    pub pan: f32,
+    pub is_fading: bool,
+    pub is_looping: bool,
+    /// The playback position and duration of the current sound, if any.
+    pub position: Option<(Duration, Duration)>,
+}
+
+impl ChannelState {
+    /// The fields that can be read from text, paired with whether each is a boolean.
+    pub const FIELDS: [(&'static str, bool); 10] = [
+        ("is_playing", true),
+        ("has_sound", true),
+        ("has_sound_queued", true),
+        ("volume", false),
+        ("master_volume", false),
+        ("pan", false),
+        ("is_fading", true),
+        ("is_looping", true),
+        ("position", false),
+        ("sound_duration", false),
+    ];
+
+    /// The state of a channel with nothing playing, such as one that hasn't been created yet.
+    pub fn silent() -> Self {
+        Self {
+            is_playing: false,
+            has_sound: false,
+            has_sound_queued: false,
+            volume: 1.0,
+            pan: 0.0,
+            is_fading: false,
+            is_looping: false,
+            position: None,
+        }
+    }
+
+    /// Formats one of the [`FIELDS`](Self::FIELDS) for templating, or returns [`None`] if there's no such field.
+    ///
+    /// Unlike in scripts, `position` and `sound_duration` are `0` rather than missing if there's no sound.
+    pub fn field(&self, field: &str, master_volume: f32) -> Option<String> {
+        let (position, duration) = self.position.unwrap_or_default();
+        let value = match field {
+            "is_playing" => self.is_playing.to_string(),
+            "has_sound" => self.has_sound.to_string(),
+            "has_sound_queued" => self.has_sound_queued.to_string(),
+            "volume" => self.volume.to_string(),
+            "master_volume" => master_volume.to_string(),
+            "pan" => self.pan.to_string(),
+            "is_fading" => self.is_fading.to_string(),
+            "is_looping" => self.is_looping.to_string(),
+            "position" => position.as_millis().to_string(),
+            "sound_duration" => duration.as_millis().to_string(),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+/// A container for [`Channels`] and [`Sounds`].
+///
+/// A pair of a channel and an audio player corresponds to a single connection to a sound device,
+/// wherein one sound file can be playing at a time. Overlapping sounds requires multiple connections
+/// and playing on different channels.
+///
+/// Channels specified in the manifest file are created on startup. Any other channel is
+/// [created](Audio::ensure_channel) the first time a sound action references it and is enabled by default.
+///
+/// Looping sounds and [playlists](Playlist) are tracked here rather than on their [`Channel`] and are advanced by [`Audio::tick`].
+///
+/// Sound files are only decoded the first time an action plays them, unless they are listed under the
+/// manifest's `preload` setting. Decoded sounds are kept for the rest of the session.
+pub struct Audio {
+    pub channels: Channels,
+    /// The names of channels that were created at runtime rather than specified in the manifest.
+    dynamic: HashSet<String>,
+    sources: SoundSources,
+    /// The sounds decoded so far.
+    sounds: Sounds,
+    /// Sound group names mapped to the sounds they pick from.
+    groups: HashMap<String, Vec<String>>,
+    /// Manifest playlist names mapped to the tracks they play.
+    playlists: HashMap<String, Vec<String>>,
+    /// Sound files and groups mapped to the properties that actions playing them default to.
+    defaults: HashMap<String, SoundDefaults>,
+    /// Sound files that couldn't be read and were dropped in [lenient](SoundLoading::Lenient) mode, mapped to why.
+    ///
+    /// Sound groups and playlists left empty by dropped files are recorded here too, but stay in their maps without any sounds.
+    dropped: BTreeMap<String, String>,
+    /// The channel used by sound actions that don't specify one.
+    default_channel: Option<String>,
+    loops: Loops,
+    /// The playlists currently playing.
+    playing: Playlists,
+    awaits: Vec<Await>,
+    events: Vec<AudioEvent>,
+    /// When each sound, by the name its action gave, last played on each channel, for [cooldowns](SoundAction::cooldown).
+    played: HashMap<(String, String), Instant>,
+    /// The volume scaling every channel's volume, from `0.0` to `1.0`.
+    master_volume: f32,
+    /// Priority channel names mapped to the channels they duck.
+    ducking: HashMap<String, DuckingSettings>,
+    /// The priority channels currently ducking others.
+    ducking_active: HashSet<String>,
+    /// The channels that were playing when they were [disabled by a script](Self::toggle_paused), so that only they resume.
+    paused_by_toggle: HashSet<String>,
+}
+
+impl Audio {
+    /// Creates [`Channels`] and maps them to the config settings' `channels`.
+    fn load_channels(config: &Manifest) -> Option<Result<Channels>> {
+        config.settings.channels.as_ref().map(|channels| {
+            channels
+                .iter()
+                .map(|(channel, _)| {
+                    AudioPlayer::new(None)
+                        .map(|player| {
+                            let channel_data = Channel::new(player, config.settings.master_volume);
+                            (channel.clone(), channel_data)
+                        })
+                        .map_err(|err| anyhow!(err))
+                })
+                .try_collect()
+        })
+    }
+
+    /// Loads an [`Audio`] container.
+    ///
+    /// If [`AudioPlayer`] creation using [`load_channels`](Self::load_channels) fails, such as when there's no sound device,
+    /// it brings the whole audio system down with it, signaling [None] within the wrapped option. The game then continues
+    /// without sound, with every sound action being a no-op. The failure is logged, and the player is warned about it
+    /// if the manifest's [`audio_warning`](crate::core::manifest::Settings::audio_warning) setting is on.
+    ///
+    /// An [`Err`] is only returned if [`load_sound_sources`](Loader::load_sound_sources) errors
+    /// or a sound file can't be [read](Self::probe_sources).
+    pub fn load(loader: &Loader, config: &Manifest) -> Result<Option<Self>> {
+        Self::load_with(loader, config, Self::load_channels(config))
+    }
+
+    /// Loads an [`Audio`] container like [`load`](Self::load), given the result of creating its channels.
+    fn load_with(
+        loader: &Loader,
+        config: &Manifest,
+        channels: Option<Result<Channels>>,
+    ) -> Result<Option<Self>> {
+        let channels = match channels {
+            None => return Ok(None),
+            Some(Err(err)) => {
+                log::warn!("Audio unavailable: {err:#}");
+                if config.settings.audio_warning {
+                    eprintln!("{} Audio unavailable: {err:#}", "Warning:".yellow());
+                }
+                return Ok(None);
+            }
+            Some(Ok(channels)) => channels,
+        };
+        let audio = Self::with_channels(loader, config, channels)?;
+        audio.report_dropped();
+        Ok(Some(audio))
+    }
+
+    /// Loads an [`Audio`] container like [`load`](Self::load), but fails if a sound device can't be opened.
+    pub fn connect(loader: &Loader, config: &Manifest) -> Result<Option<Self>> {
+        let Some(channels) = Self::load_channels(config) else {
+            return Ok(None);
+        };
+        let audio = Self::with_channels(loader, config, channels?)?;
+        audio.report_dropped();
+        Ok(Some(audio))
+    }
+
+    /// Loads an [`Audio`] container without any channels, so that no sound device is connected to.
+    ///
+    /// This is meant for validating sound actions; the result shouldn't be used to play sounds.
+    pub fn load_headless(loader: &Loader, config: &Manifest) -> Result<Option<Self>> {
+        if config.settings.channels.is_none() {
+            return Ok(None);
+        }
+        Self::with_channels(loader, config, Channels::new()).map(Some)
+    }
+
+    fn with_channels(loader: &Loader, config: &Manifest, channels: Channels) -> Result<Self> {
+        let mut audio = Self {
+            channels,
+            dynamic: HashSet::new(),
+            paused_by_toggle: HashSet::new(),
+            sources: loader.load_sound_sources("sounds")?,
+            sounds: Sounds::new(),
+            groups: config.settings.sound_groups.clone(),
+            playlists: config.settings.playlists.clone(),
+            defaults: config.settings.sound_defaults.clone(),
+            dropped: BTreeMap::new(),
+            default_channel: config.settings.default_channel.clone(),
+            loops: Loops::new(),
+            playing: Playlists::new(),
+            awaits: Vec::new(),
+            events: Vec::new(),
+            played: HashMap::new(),
+            master_volume: config.settings.master_volume,
+            ducking: config.settings.ducking.clone(),
+            ducking_active: HashSet::new(),
+        };
+        let mode = config.settings.sound_loading;
+        audio.probe_sources(mode)?;
+        audio.preload(&config.settings.preload, mode)?;
+        Ok(audio)
+    }
+
+    /// Checks that every sound file's format can be read, so that bad files are caught at load rather than when they play.
+    ///
+    /// In [strict](SoundLoading::Strict) mode, the first unreadable file is an error. In [lenient](SoundLoading::Lenient) mode,
+    /// every unreadable file is [dropped](Self::drop_sound) instead.
+    ///
+    /// Files are [probed in parallel](SoundSource::probe_all), but the first error is still that of the first file by name.
+    fn probe_sources(&mut self, mode: SoundLoading) -> Result<()> {
+        let sources: Vec<&SoundSource> = self.sources.values().collect();
+        let mut failures = Vec::new();
+        for ((name, source), result) in self.sources.iter().zip(SoundSource::probe_all(&sources)) {
+            if let Err(err) = result {
+                let err = err.context(format!("Failed to read sound file {}", source.path()));
+                if mode == SoundLoading::Strict {
+                    return Err(err);
+                }
+                failures.push((name.clone(), err));
+            }
+        }
+        for (name, err) in failures {
+            self.drop_sound(&name, &err);
+        }
+        Ok(())
+    }
+
+    /// Decodes the sounds in the manifest's `preload` setting [in parallel](SoundSource::decode_all)
+    /// so that they don't stall the game when first played.
+    ///
+    /// Like [`probe_sources`](Self::probe_sources), sounds that fail are an error in [strict](SoundLoading::Strict) mode
+    /// and dropped in [lenient](SoundLoading::Lenient) mode.
+    fn preload(&mut self, names: &[String], mode: SoundLoading) -> Result<()> {
+        let mut failures = Vec::new();
+        let mut pending = Vec::new();
+        for name in names.iter().unique() {
+            if self.dropped.contains_key(name) || self.sounds.contains_key(name) {
+                continue;
+            }
+            match self.get_sound_source(name) {
+                Ok(source) => pending.push((name.clone(), source.clone())),
+                Err(err) if mode == SoundLoading::Lenient => failures.push((name.clone(), err)),
+                Err(err) => return Err(err),
+            }
+        }
+        let sources: Vec<&SoundSource> = pending.iter().map(|(_, source)| source).collect();
+        for ((name, _), result) in pending.iter().zip(SoundSource::decode_all(&sources)) {
+            match result.with_context(|| format!("Failed to decode sound file '{name}'")) {
+                Ok(sound) => {
+                    self.sounds.insert(name.clone(), sound);
+                }
+                Err(err) if mode == SoundLoading::Lenient => failures.push((name.clone(), err)),
+                Err(err) => return Err(err),
+            }
+        }
+        for (name, err) in failures {
+            self.drop_sound(&name, &err);
+        }
+        Ok(())
+    }
+
+    /// Removes an unreadable sound file, along with its place in any sound groups and playlists, recording why.
+    ///
+    /// Sound groups and playlists that this leaves empty are dropped as well, so that they're skipped rather than failing validation.
+    fn drop_sound(&mut self, name: &str, err: &anyhow::Error) {
+        log::warn!("Dropped sound file '{name}': {err:#}");
+        self.sources.remove(name);
+        self.sounds.remove(name);
+        self.dropped.insert(name.to_owned(), format!("{err:#}"));
+        let mut emptied = Vec::new();
+        for (group, members) in &mut self.groups {
+            if members.iter().any(|sound| sound == name) {
+                members.retain(|sound| sound != name);
+                if members.is_empty() {
+                    emptied.push(group.clone());
+                }
+            }
+        }
+        for group in &emptied {
+            log::warn!("Dropped sound group '{group}' whose sounds were all dropped");
+            self.dropped
+                .insert(group.clone(), "all of its sounds were dropped".to_owned());
+        }
+        for (playlist, tracks) in &mut self.playlists {
+            let len = tracks.len();
+            tracks.retain(|track| track != name && !emptied.contains(track));
+            if len > 0 && tracks.is_empty() {
+                log::warn!("Dropped playlist '{playlist}' whose tracks were all dropped");
+                self.dropped
+                    .insert(playlist.clone(), "all of its tracks were dropped".to_owned());
+            }
+        }
+    }
+
+    /// Warns the player about every sound file that was dropped at load, if any.
+    fn report_dropped(&self) {
+        if self.dropped.is_empty() {
+            return;
+        }
+        eprintln!(
+            "{} {} sound(s) couldn't be loaded and won't play:",
+            "Warning:".yellow(),
+            self.dropped.len()
+        );
+        for (name, reason) in &self.dropped {
+            eprintln!("- {name}: {reason}");
+        }
+    }
+
+    /// Describes each sound file, sound group, and playlist that was dropped at load, for validation output.
+    pub fn dropped_warnings(&self) -> Vec<String> {
+        self.dropped
+            .iter()
+            .map(|(name, reason)| format!("Sound '{name}' was dropped: {reason}"))
+            .collect()
+    }
+
+    /// Retrieves a [`Channel`]'s audio player by its name, creating the channel if it doesn't exist yet.
+    ///
+    /// Returns an [`Err`] if the channel's player can't connect to a sound device.
+    pub fn ensure_channel(&mut self, channel: &str) -> Result<&AudioPlayer> {
+        if !self.channels.contains_key(channel) {
+            let player = AudioPlayer::new(None)
+                .map_err(|err| anyhow!(err))
+                .with_context(|| format!("Failed to create sound channel '{channel}'"))?;
+            self.channels
+                .insert(channel.to_owned(), Channel::new(player, self.master_volume));
+            self.dynamic.insert(channel.to_owned());
+        }
+        Ok(&self.channels[channel].player)
+    }
+
+    /// Whether a channel is declared in the manifest rather than created at runtime.
+    pub fn is_declared(&self, channel: &str) -> bool {
+        self.channels.contains_key(channel) && !self.dynamic.contains(channel)
+    }
+
+    /// Whether a channel is enabled on the [`Player`].
+    ///
+    /// Channels created at runtime are enabled unless the player has explicitly disabled them.
+    pub fn is_enabled(&self, player: &Player, channel: &str) -> bool {
+        Self::is_enabled_in(&self.dynamic, player, channel)
+    }
+
+    fn is_enabled_in(dynamic: &HashSet<String>, player: &Player, channel: &str) -> bool {
+        player.channels.contains(channel)
+            || (dynamic.contains(channel) && !player.disabled_channels.contains(channel))
+    }
+
+    /// Enables or disables a channel on the [`Player`], stopping its playback if disabled.
+    pub fn set_enabled(&mut self, player: &mut Player, channel: &str, enabled: bool) -> Result<()> {
+        if enabled {
+            player.channels.insert(channel.to_owned());
+            player.disabled_channels.remove(channel);
+        } else {
+            player.channels.remove(channel);
+            player.disabled_channels.insert(channel.to_owned());
+            self.stop(channel)?;
+        }
+        Ok(())
+    }
+
+    /// Enables or disables a channel on the [`Player`] like [`set_enabled`](Self::set_enabled), but pauses its playback
+    /// if disabled rather than stopping it, and resumes it if enabled again.
+    ///
+    /// Only channels that were playing when they were disabled are resumed, so a channel that was already paused
+    /// or had finished stays that way.
+    pub fn toggle_paused(
+        &mut self,
+        player: &mut Player,
+        channel: &str,
+        enabled: bool,
+    ) -> Result<()> {
+        if self.is_enabled(player, channel) == enabled {
+            return Ok(());
+        }
+        if enabled {
+            player.channels.insert(channel.to_owned());
+            player.disabled_channels.remove(channel);
+        } else {
+            player.channels.remove(channel);
+            player.disabled_channels.insert(channel.to_owned());
+        }
+        let Some(state) = self.channels.get_mut(channel) else {
+            return Ok(());
+        };
+        if enabled {
+            if self.paused_by_toggle.remove(channel) {
+                state.play(None, &self.sounds)?;
+            }
+            return Ok(());
+        }
+        if state.paused.is_none() && state.player.is_playing() && state.player.has_current_song() {
+            self.paused_by_toggle.insert(channel.to_owned());
+            state.pause(None, &self.sounds)?;
+        }
+        Ok(())
+    }
+
+    fn get_sound_from<'a>(sounds: &'a Sounds, name: &str) -> Result<&'a Sound> {
+        sounds
+            .get(name)
+            .ok_or(anyhow!("Invalid sound file '{name}'"))
+    }
+
+    /// Retrieves a [`SoundSource`], if any, by a sound name.
+    pub fn get_sound_source(&self, name: &str) -> Result<&SoundSource> {
+        self.sources
+            .get(name)
+            .ok_or_else(|| match self.dropped.get(name) {
+                Some(reason) => anyhow!("Sound '{name}' was dropped: {reason}"),
+                None => anyhow!("Invalid sound file '{name}'"),
+            })
+    }
+
+    /// Validates that a sound name matches a loaded sound file.
+    ///
+    /// Sound files [dropped](SoundLoading::Lenient) at load are also accepted, since actions playing them do nothing.
+    pub fn validate_sound(&self, name: &str) -> Result<()> {
+        if !self.dropped.contains_key(name) {
+            let _ = self.get_sound_source(name)?;
+        }
+        Ok(())
+    }
+
+    /// Resolves a sound name, picking a random member with the [`Player`]'s generator if it names a sound group.
+    pub fn resolve_sound(&self, player: &Player, name: &str) -> String {
+        self.groups
+            .get(name)
+            .and_then(|members| player.random.pick(members))
+            .cloned()
+            .unwrap_or_else(|| name.to_owned())
+    }
+
+    /// Validates that every sound group is non-empty, doesn't share a name with a sound file,
+    /// and only contains sound files that exist.
+    ///
+    /// Groups that were [dropped](Self::drop_sound) at load are skipped.
+    pub fn validate_groups(&self) -> Result<()> {
+        for (group, members) in &self.groups {
+            if self.dropped.contains_key(group) {
+                continue;
+            }
+            if members.is_empty() {
+                return Err(anyhow!("Sound group '{group}' has no sounds"));
+            }
+            if self.sources.contains_key(group) {
+                return Err(anyhow!("Sound group '{group}' has the same name as a sound file"));
+            }
+            for member in members {
+                let _ = self
+                    .get_sound_source(member)
+                    .with_context(|| format!("Failed to validate sound group '{group}'"))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Retrieves a [`Sound`] by a sound name, decoding it if it hasn't been already.
+    pub fn load_sound(&mut self, name: &str) -> Result<&Sound> {
+        if !self.sounds.contains_key(name) {
+            let sound = self
+                .get_sound_source(name)?
+                .decode()
+                .with_context(|| format!("Failed to decode sound file '{name}'"))?;
+            self.sounds.insert(name.to_owned(), sound);
+        }
+        Ok(&self.sounds[name])
+    }
+
+    /// Validates that every manifest playlist is non-empty and only contains sound files or sound groups that exist.
+    ///
+    /// Playlists that were [dropped](Self::drop_sound) at load are skipped.
+    pub fn validate_playlists(&self) -> Result<()> {
+        for (playlist, tracks) in &self.playlists {
+            if self.dropped.contains_key(playlist) {
+                continue;
+            }
+            if tracks.is_empty() {
+                return Err(anyhow!("Playlist '{playlist}' has no tracks"));
+            }
+            for track in tracks
+                .iter()
+                .filter(|track| !self.groups.contains_key(*track))
+            {
+                let _ = self
+                    .get_sound_source(track)
+                    .with_context(|| format!("Failed to validate playlist '{playlist}'"))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates that each sound in the manifest's `sound_defaults` is a loaded sound file or sound group.
+    pub fn validate_defaults(&self) -> Result<()> {
+        for name in self
+            .defaults
+            .keys()
+            .filter(|name| !self.groups.contains_key(*name))
+        {
+            self.validate_sound(name)
+                .with_context(|| "Failed to validate `settings.sound_defaults`")?;
+        }
+        Ok(())
+    }
+
+    /// Fills in the properties an action playing `sound` leaves unset from the manifest's [`SoundDefaults`].
+    ///
+    /// The defaults of the name the action gives, such as a sound group, take priority over those of the file it resolved to.
+    fn with_defaults(&self, action: &SoundActionData, sound: &str) -> SoundDefaults {
+        let defaults: Vec<&SoundDefaults> = [action.name.as_deref(), Some(sound)]
+            .into_iter()
+            .flatten()
+            .filter_map(|name| self.defaults.get(name))
+            .collect();
+        SoundDefaults {
+            volume: action
+                .volume
+                .or_else(|| defaults.iter().find_map(|d| d.volume)),
+            speed: action
+                .speed
+                .or_else(|| defaults.iter().find_map(|d| d.speed)),
+            pan: action.pan.or_else(|| defaults.iter().find_map(|d| d.pan)),
+            fade: action
+                .fade
+                .map(|fade| fade.as_millis() as u64)
+                .or_else(|| defaults.iter().find_map(|d| d.fade)),
+        }
+    }
+
+    /// The channel an action applies to: its own, or the [default channel](crate::core::manifest::Settings::default_channel) if it doesn't specify one.
+    pub fn channel_of<'a>(&'a self, action: &'a SoundActionData) -> Option<&'a String> {
+        action.channel.as_ref().or(self.default_channel.as_ref())
+    }
+
+    /// Whether the [`Music`] an action starts is already playing on its channel, or, if it stops the music,
+    /// whether the channel is already silent.
+    ///
+    /// A sound group counts as playing if any of its sounds is.
+    pub fn is_music_playing(&mut self, action: &SoundActionData) -> bool {
+        let Some(channel_name) = self.channel_of(action).cloned() else {
+            return false;
+        };
+        let Some(channel) = self.channels.get_mut(&channel_name) else {
+            return action.name.is_none();
+        };
+        channel.sync();
+        match (&action.name, &channel.current) {
+            (None, current) => current.is_none(),
+            (Some(_), None) => false,
+            (Some(name), Some(current)) => {
+                (current == name
+                    || self
+                        .groups
+                        .get(name)
+                        .is_some_and(|members| members.contains(current)))
+                    && self
+                        .loops
+                        .get(&channel_name)
+                        .is_some_and(|looping| looping.name == *current)
+            }
+        }
+    }
+
+    /// Stops all playback on a channel, including any sound looping or playlist playing on it.
+    pub fn stop(&mut self, channel: &str) -> Result<()> {
+        self.channels
+            .get_mut(channel)
+            .ok_or(anyhow!("Invalid sound channel '{channel}'"))?
+            .stop();
+        self.loops.remove(channel);
+        self.playing.remove(channel);
+        self.paused_by_toggle.remove(channel);
+        self.events
+            .push(AudioEvent::Interrupted(channel.to_owned()));
+        Ok(())
+    }
+
+    /// The volume scaling every channel's volume, from `0.0` to `1.0`.
+    pub fn master_volume(&self) -> f32 {
+        self.master_volume
+    }
+
+    /// Sets the master volume, clamped between `0.0` and `1.0`, and re-renders every channel's sounds at the new level.
+    ///
+    /// Like [channel volume](SoundAction::volume) changes, this keeps any fades in progress on their envelopes.
+    pub fn set_master_volume(&mut self, value: f32) -> Result<()> {
+        self.master_volume = value.clamp(0.0, 1.0);
+        for channel in self.channels.values_mut() {
+            channel.set_master(self.master_volume, &self.sounds)?;
+        }
+        Ok(())
+    }
+
+    /// Starts or stops a priority channel's [ducking](DuckingSettings) based on whether it is audibly playing.
+    ///
+    /// Returns whether the set of active priority channels changed.
+    fn update_ducking(&mut self, priority: &str) -> bool {
+        if !self.ducking.contains_key(priority) {
+            return false;
+        }
+        let sounding = self
+            .channels
+            .get(priority)
+            .map(Channel::is_sounding)
+            .unwrap_or(false);
+        if sounding {
+            self.ducking_active.insert(priority.to_owned())
+        } else {
+            self.ducking_active.remove(priority)
+        }
+    }
+
+    /// Applies the strongest [ducking](DuckingSettings) amount among the active priority channels to every channel.
+    ///
+    /// Channels only return to full volume once no active priority channel ducks them.
+    fn apply_ducking(&mut self) -> Result<()> {
+        for (name, channel) in self.channels.iter_mut() {
+            let amount = self
+                .ducking_active
+                .iter()
+                .filter_map(|priority| self.ducking.get(priority))
+                .filter(|ducking| ducking.channels.contains(name))
+                .map(|ducking| ducking.amount)
+                .fold(0.0, f32::max);
+            channel.set_duck(1.0 - amount, &self.sounds)?;
+        }
+        Ok(())
+    }
+
+    /// Advances playback state that the audio players can't track on their own.
+    ///
+    /// This restarts any [looping](Loop) sounds that have ended, moves [playlists](Playlist) onto their next track,
+    /// restores channels [ducked](DuckingSettings) by priority channels that have stopped, and records channels that have
+    /// [finished](AudioEvent::Finished), so it should be called regularly from the game loop.
+    pub fn tick(&mut self) -> Result<()> {
+        for (name, channel) in self.channels.iter_mut() {
+            channel.finish_render(&self.sounds)?;
+            channel.sync();
+            let sounding = channel.player.has_current_song() || channel.paused.is_some();
+            if channel.sounding && !sounding {
+                self.events.push(AudioEvent::Finished(name.clone()));
+            }
+            channel.sounding = sounding;
+        }
+        for (name, looped) in &self.loops {
+            let Some(channel) = self.channels.get_mut(name) else {
+                continue;
+            };
+            channel.sync();
+            if !channel.player.has_current_song() && channel.paused.is_none() {
+                let sound = Self::get_sound_from(&self.sounds, &looped.name)?;
+                channel.play_now(&looped.name, sound, looped.from, None)?;
+            }
+        }
+        let ended: Vec<String> = self
+            .playing
+            .keys()
+            .filter(|name| {
+                self.channels.get_mut(*name).is_some_and(|channel| {
+                    channel.sync();
+                    !channel.player.has_current_song() && channel.paused.is_none()
+                })
+            })
+            .cloned()
+            .collect();
+        for name in ended {
+            self.next_track(&name, None)?;
+        }
+        let priorities: Vec<String> = self.ducking.keys().cloned().collect();
+        let mut changed = false;
+        for priority in priorities {
+            changed |= self.update_ducking(&priority);
+        }
+        if changed {
+            self.apply_ducking()?;
+        }
+        Ok(())
+    }
+
+    /// Moves the playlist playing on a channel onto its next track, crossfading if a `fade` is provided.
+    ///
+    /// Does nothing if the channel isn't playing a playlist.
+    fn next_track(&mut self, channel_name: &str, fade: Option<Duration>) -> Result<()> {
+        let Some(playlist) = self.playing.get_mut(channel_name) else {
+            return Ok(());
+        };
+        let track = playlist.advance().to_owned();
+        let _ = self.load_sound(&track)?;
+        let sound = Self::get_sound_from(&self.sounds, &track)?;
+        let channel = self
+            .channels
+            .get_mut(channel_name)
+            .ok_or(anyhow!("Invalid sound channel '{channel_name}'"))?;
+        match fade {
+            Some(length) => channel.crossfade(&track, sound, None, length, &self.sounds),
+            None => channel.play_now(&track, sound, None, None),
+        }
+    }
+
+    /// Starts a [`Playlist`] on a channel from its first track, shuffling the tracks first if requested.
+    ///
+    /// Sound groups among the tracks are resolved once here, so each cycle plays the same sounds.
+    fn start_playlist(
+        &mut self,
+        player: &Player,
+        channel_name: &str,
+        action: &SoundActionData,
+    ) -> Result<()> {
+        let tracks = match (&action.tracks, &action.name) {
+            (Some(tracks), _) => tracks.clone(),
+            (None, Some(name)) if self.dropped.contains_key(name) => {
+                log::debug!("Skipped dropped playlist '{name}' on channel '{channel_name}'");
+                return Ok(());
+            }
+            (None, Some(name)) => self
+                .playlists
+                .get(name)
+                .cloned()
+                .ok_or(anyhow!("Invalid playlist '{name}'"))?,
+            (None, None) => {
+                return Err(anyhow!(
+                    "Sound action 'playlist' on channel '{channel_name}' requires a playlist name or tracks"
+                ))
+            }
+        };
+        let mut tracks: Vec<String> = tracks
+            .iter()
+            .map(|track| self.resolve_sound(player, track))
+            .collect();
+        if tracks.is_empty() {
+            return Err(anyhow!(
+                "Sound action 'playlist' on channel '{channel_name}' has no tracks"
+            ));
+        }
+        tracks.retain(|track| !self.dropped.contains_key(track));
+        if tracks.is_empty() {
+            log::debug!(
+                "Skipped playlist on channel '{channel_name}' whose tracks were all dropped"
+            );
+            return Ok(());
+        }
+        if action.shuffle {
+            player.random.shuffle(&mut tracks);
+        }
+        let first = tracks[0].clone();
+        let _ = self.load_sound(&first)?;
+        self.playing
+            .insert(channel_name.to_owned(), Playlist { tracks, current: 0 });
+        let sound = Self::get_sound_from(&self.sounds, &first)?;
+        let channel = self
+            .channels
+            .get_mut(channel_name)
+            .ok_or(anyhow!("Invalid sound channel '{channel_name}'"))?;
+        if let Some(volume) = action.volume {
+            channel.volume = volume.clamp(0.0, 1.0);
+        }
+        if let Some(pan) = action.pan {
+            channel.pan = pan.clamp(-1.0, 1.0);
+        }
+        channel.play_now(&first, sound, action.seek, action.fade)
+    }
+
+    /// Stops every channel enabled on the [`Player`], including any sounds looping on them.
+    ///
+    /// If a `fade` is provided, each channel's current sound fades out over that duration first.
+    pub fn stop_all(&mut self, player: &Player, fade: Option<Duration>) -> Result<()> {
+        for (name, channel) in self.channels.iter_mut() {
+            if !Self::is_enabled_in(&self.dynamic, player, name) {
+                continue;
+            }
+            channel.stop_fading(fade, &self.sounds)?;
+            self.loops.remove(name);
+            self.playing.remove(name);
+            self.events.push(AudioEvent::Interrupted(name.clone()));
+        }
+        Ok(())
+    }
+
+    /// Skips the sound `name` on every channel it's currently playing on, or any sound from the group `name`,
+    /// stopping it from repeating if it's looping there. A playlist playing it moves onto its next track.
+    ///
+    /// If a `fade` is provided, the sound fades out over that duration first. Nothing happens if the sound isn't playing.
+    pub fn stop_sound(&mut self, name: &str, fade: Option<Duration>) -> Result<()> {
+        let members = self.groups.get(name);
+        let matches = |sound: &String| {
+            sound == name || members.is_some_and(|members| members.contains(sound))
+        };
+        for (channel_name, channel) in self.channels.iter_mut() {
+            channel.sync();
+            if !channel.current.as_ref().is_some_and(matches) {
+                continue;
+            }
+            if self
+                .loops
+                .get(channel_name)
+                .is_some_and(|looping| matches(&looping.name))
+            {
+                self.loops.remove(channel_name);
+            }
+            channel.skip(fade, &self.sounds)?;
+            self.events
+                .push(AudioEvent::Interrupted(channel_name.clone()));
+        }
+        Ok(())
+    }
+
+    /// Fades out every channel over `fade`, blocking until the fade completes, then stops all playback.
+    ///
+    /// This is meant for when the game exits; nothing is restarted afterward, and no [`AudioEvent`]s are recorded.
+    pub fn fade_out_all(&mut self, fade: Duration) -> Result<()> {
+        self.loops.clear();
+        self.playing.clear();
+        let mut faded = false;
+        for channel in self.channels.values_mut().filter(|_| !fade.is_zero()) {
+            channel.next = None;
+            faded |= channel.fade_out(fade, &self.sounds)?.is_some();
+        }
+        if faded {
+            thread::sleep(fade);
+        }
+        for channel in self.channels.values_mut() {
+            channel.stop();
+        }
+        Ok(())
+    }
+
+    /// Records the sound playing on each channel, along with whether it's looping or part of a playlist.
+    ///
+    /// Channels paused by a fade record the position they would resume from.
+    pub fn snapshot(&mut self) -> ChannelSounds {
+        let mut sounds = ChannelSounds::new();
+        for (name, channel) in self.channels.iter_mut() {
+            channel.sync();
+            let Some(current) = &channel.current else {
+                continue;
+            };
+            if !channel.player.has_current_song() && channel.paused.is_none() {
+                continue;
+            }
+            let position = channel
+                .paused
+                .or_else(|| channel.position())
+                .map(|position| position.mul_f64(channel.tempo).as_millis() as u64);
+            let sound = ChannelSound {
+                name: current.clone(),
+                looping: self.loops.contains_key(name),
+                position,
+                playlist: self
+                    .playing
+                    .get(name)
+                    .map(|playlist| playlist.tracks.clone()),
+            };
+            sounds.insert(name.clone(), sound);
+        }
+        sounds
+    }
+
+    /// Plays a [`ChannelSound`] from a loaded save on its channel, resuming from its recorded position.
+    ///
+    /// Like [`accept`](Self::accept), this does nothing if the channel is disabled on the [`Player`]
+    /// or can't connect to a sound device.
+    pub fn restore(
+        &mut self,
+        player: &Player,
+        channel_name: &str,
+        sound: &ChannelSound,
+    ) -> Result<()> {
+        if self.ensure_channel(channel_name).is_err()
+            || !self.is_enabled(player, channel_name)
+            || self.dropped.contains_key(&sound.name)
+        {
+            return Ok(());
+        }
+        let _ = self.load_sound(&sound.name)?;
+        self.loops.remove(channel_name);
+        self.playing.remove(channel_name);
+        if sound.looping {
+            self.loops.insert(
+                channel_name.to_owned(),
+                Loop {
+                    name: sound.name.clone(),
+                    from: None,
+                },
+            );
+        }
+        if let Some(tracks) = sound.playlist.clone().filter(|tracks| !tracks.is_empty()) {
+            let current = tracks
+                .iter()
+                .position(|track| *track == sound.name)
+                .unwrap_or(0);
+            self.playing
+                .insert(channel_name.to_owned(), Playlist { tracks, current });
+        }
+        let sfx = Self::get_sound_from(&self.sounds, &sound.name)?;
+        let channel = self
+            .channels
+            .get_mut(channel_name)
+            .ok_or(anyhow!("Invalid sound channel '{channel_name}'"))?;
+        channel.play_now(&sound.name, sfx, sound.position.map(Duration::from_millis), None)
+    }
+
+    /// Takes every [`AudioEvent`] recorded since the last call.
+    pub fn drain_events(&mut self) -> Vec<AudioEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Whether the game is blocked on any [`Await`]s.
+    ///
+    /// Awaits whose channel has finished its sound or whose deadline has passed are discarded first.
+    pub fn is_awaiting(&mut self) -> bool {
+        let now = Instant::now();
+        let channels = &mut self.channels;
+        self.awaits.retain(|wait| {
+            if wait
+                .deadline
+                .map(|deadline| now >= deadline)
+                .unwrap_or(false)
+            {
+                return false;
+            }
+            channels
+                .get_mut(&wait.channel)
+                .map(|channel| {
+                    channel.sync();
+                    channel.player.has_current_song() && channel.paused.is_none()
+                })
+                .unwrap_or(false)
+        });
+        !self.awaits.is_empty()
+    }
+
+    /// Discards all [`Await`]s, unblocking the game.
+    pub fn clear_awaits(&mut self) {
+        self.awaits.clear();
+    }
+
+    /// Pauses every playing channel where it is, such as while the game is paused.
+    pub fn suspend(&mut self) -> Suspension {
+        let channels: Vec<String> = self
+            .channels
+            .iter()
+            .filter(|(_, channel)| channel.player.is_playing())
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in &channels {
+            self.channels[name].player.set_playing(false);
+        }
+        Suspension {
+            channels,
+            since: Instant::now(),
+        }
+    }
+
+    /// Plays the channels paused by [`suspend`](Self::suspend) again from the same positions.
+    ///
+    /// [`Await`] deadlines are pushed back by however long playback was suspended for.
+    pub fn resume(&mut self, suspension: Suspension) {
+        for name in &suspension.channels {
+            if let Some(channel) = self.channels.get(name) {
+                channel.player.set_playing(true);
+            }
+        }
+        let suspended = suspension.since.elapsed();
+        for deadline in self
+            .awaits
+            .iter_mut()
+            .filter_map(|wait| wait.deadline.as_mut())
+        {
+            *deadline += suspended;
+        }
+    }
+
+    /// Returns this controller's channel names mapped to whether they are enabled on the [`Player`].
+    pub fn channel_statuses(&self, player: &Player) -> Vec<(String, bool)> {
+        Self::channel_statuses_in(self.channels.keys(), &self.dynamic, player)
+    }
+
+    fn channel_statuses_in<'a>(
+        channels: impl Iterator<Item = &'a String>,
+        dynamic: &HashSet<String>,
+        player: &Player,
+    ) -> Vec<(String, bool)> {
+        channels
+            .map(|channel| (channel.clone(), Self::is_enabled_in(dynamic, player, channel)))
+            .collect()
+    }
+
+    /// Creates a Lua table mapping each loaded audio player to a table of their data.
+    ///
+    /// This table is formatted as follows:
+    /// - `is_playing`: Whether the player is not paused
+    /// - `has_sound`: Whether the player has a sound currently playing
+    /// - `has_sound_queued`: Whether the player has a sound queued, but not playing
+    /// - `volume`: The channel volume, from `0.0` to `1.0`
+    /// - `master_volume`: The master volume scaling the channel volume, from `0.0` to `1.0`
+    /// - `pan`: The channel's stereo balance, from `-1.0` (full left) to `1.0` (full right)
+    /// - `is_fading`: Whether the current sound is partway through a fade
+    /// - `is_looping`: Whether the current sound restarts when it ends
+    /// - `position`: If the player has a sound playing, returns the position in milliseconds
+    /// - `sound_duration`: If the player has a sound playing, returns its duration in milliseconds
+    ///
+    /// Fades are applied to the sound itself rather than the channel volume, so `volume` is unaffected by them.
+    /// A channel paused with a fade reports `is_playing` as `false` as soon as the fade begins;
+    /// a sound skipped with a fade still reports `has_sound` until the fade completes.
+    /// Requesting a new fade on a channel cuts the previous one short, starting from whatever level it had reached.
+    pub fn create_audio_table<'a>(&self, context: &Context<'a>) -> Result<Table<'a>, rlua::Error> {
+        let table = context.create_table()?;
+        for (name, state) in self.channel_states() {
+            let channel_table = context.create_table()?;
+            channel_table.set("is_playing", state.is_playing)?;
+            channel_table.set("has_sound", state.has_sound)?;
+            channel_table.set("has_sound_queued", state.has_sound_queued)?;
+            channel_table.set("volume", state.volume)?;
+            channel_table.set("master_volume", self.master_volume)?;
+            channel_table.set("pan", state.pan)?;
+            channel_table.set("is_fading", state.is_fading)?;
+            channel_table.set("is_looping", state.is_looping)?;
+            if let Some((pos, duration)) = state.position {
+                channel_table.set("position", pos.as_millis())?;
+                channel_table.set("sound_duration", duration.as_millis())?;
+            }
+            table.set(name.clone(), channel_table)?;
+        }
+        Ok(table)
+    }
+
+    /// Reads a [field](ChannelState::field) of a channel's state, treating channels that haven't been created as [silent](ChannelState::silent).
+    pub fn channel_field(&self, channel: &str, field: &str) -> Option<String> {
+        self.channel_states()
+            .into_iter()
+            .find(|(name, _)| *name == channel)
+            .map_or_else(ChannelState::silent, |(_, state)| state)
+            .field(field, self.master_volume)
+    }
+
+    /// Takes a [`ChannelState`] snapshot of each loaded channel.
+    pub fn channel_states(&self) -> Vec<(&String, ChannelState)> {
+        self.channels
+            .iter()
+            .map(|(name, channel)| {
+                let state = ChannelState {
+                    is_playing: channel.is_playing(),
+                    has_sound: channel.player.has_current_song(),
+                    has_sound_queued: channel.player.has_next_song(),
+                    volume: channel.volume,
+                    pan: channel.pan,
+                    is_fading: channel.is_fading(),
+                    is_looping: self.loops.contains_key(name),
+                    position: channel.player.get_playback_position(),
+                };
+                (name, state)
+            })
+            .collect()
+    }
+
+    /// Applies actions requiring that a specified sound file is **not** present.
+    fn accept_general(
+        channel: &mut Channel,
+        sounds: &Sounds,
+        seek: Option<Duration>,
+        fade: Option<Duration>,
+        mode: SoundActionMode,
+    ) -> Result<()> {
+        use SoundActionMode::*;
+        if let Some(duration) = channel.stretch_seek(seek) {
+            channel.player.seek(duration);
+        }
+        match mode {
+            Skip => channel.skip(fade, sounds),
+            Play => channel.play(fade, sounds),
+            Pause => channel.pause(fade, sounds),
+            _ => Ok(()),
+        }
+    }
+
+    /// Applies actions requiring both a [`SoundActionMode`] and accompanying sound effect,
+    /// returning whether the sound was played or queued.
+    fn accept_specific(
+        channel: &mut Channel,
+        sounds: &Sounds,
+        name: &str,
+        sfx: &Sound,
+        seek: Option<Duration>,
+        fade: Option<Duration>,
+        mode: SoundActionMode,
+    ) -> bool {
+        use SoundActionMode::*;
+        let played = match mode {
+            Queue => channel.play_next(name, sfx, seek),
+            Overwrite | Loop => channel.play_now(name, sfx, seek, fade),
+            Crossfade => match fade {
+                Some(length) => channel.crossfade(name, sfx, seek, length, sounds),
+                None => channel.play_now(name, sfx, seek, None),
+            },
+            Passive if !channel.player.has_current_song() => {
+                channel.play_now(name, sfx, seek, fade)
+            }
+            _ => return false,
+        };
+        played.is_ok()
+    }
+
+    /// Applies a [`SoundActionData`] to a particular channel.
+    ///
+    /// If a `volume` or `pan` is provided without a sound name, or along with a [queued](SoundActionMode::Queue) one,
+    /// it applies to whatever is already playing on the channel.
+    ///
+    /// A `speed` with `preserve_pitch` set [time-stretches](Sound::stretched) the channel's sounds instead of
+    /// changing the player's playback speed; the stretch stays in effect until another `speed` is applied to the channel.
+    ///
+    /// Any action that [breaks a loop](SoundActionMode::breaks_loop) stops the channel's looping sound from restarting,
+    /// and any that [ends a playlist](SoundActionMode::ends_playlist) stops its next track from playing.
+    /// [`Queue`](SoundActionMode::Queue) lets the current repetition or track finish before the queued sound plays.
+    ///
+    /// [`Await`](SoundActionMode::Await) actions only register the wait; see [`Resources::await_audio`](crate::core::resources::Resources::await_audio).
+    /// Awaiting a looping channel without a `timeout` blocks until the wait is skipped.
+    ///
+    /// Actions without a channel use the [default channel](Self::channel_of).
+    /// If the channel doesn't exist yet and a sound device connection can't be made for it, the action is ignored.
+    /// [`StopAll`](SoundActionMode::StopAll) and [`StopNamed`](SoundActionMode::StopNamed) actions are routed to
+    /// [`Audio::stop_all`] and [`Audio::stop_sound`] instead.
+    pub fn accept(&mut self, player: &Player, action: &SoundActionData) -> Result<()> {
+        let channel_name = match (&action.mode, self.channel_of(action)) {
+            (SoundActionMode::StopAll, _) => return self.stop_all(player, action.fade),
+            (SoundActionMode::StopNamed, _) => {
+                let name = action.name.as_ref().ok_or(anyhow!(
+                    "Sound action 'stop_named' requires a sound effect name, but none is provided"
+                ))?;
+                return self.stop_sound(name, action.fade);
+            }
+            (_, Some(channel)) => &channel.clone(),
+            (mode, None) => {
+                return Err(anyhow!(
+                    "Sound action '{mode}' requires a channel, but none is provided and `settings.default_channel` isn't set"
+                ))
+            }
+        };
+        if self.ensure_channel(channel_name).is_err() {
+            return Ok(());
+        }
+        if !self.is_enabled(player, channel_name) {
+            return Ok(());
+        }
+        let cooldown_key = match (action.mode.is_specific(), &action.name) {
+            (true, Some(name)) => {
+                let key = (channel_name.clone(), name.clone());
+                let cooling = self.played.get(&key).is_some_and(|played| {
+                    action
+                        .cooldown
+                        .is_some_and(|cooldown| played.elapsed() < cooldown)
+                });
+                if cooling {
+                    log::debug!(
+                        "Skipped sound '{name}' on channel '{channel_name}' during its cooldown"
+                    );
+                    return Ok(());
+                }
+                Some(key)
+            }
+            _ => None,
+        };
+
+        if action.mode.breaks_loop() {
+            self.loops.remove(channel_name);
+        }
+        if action.mode.ends_playlist() {
+            self.playing.remove(channel_name);
+        }
+        if let SoundActionMode::Skip | SoundActionMode::NextTrack = &action.mode {
+            self.events
+                .push(AudioEvent::Interrupted(channel_name.clone()));
+        }
+        if let SoundActionMode::Await = &action.mode {
+            self.awaits.push(Await {
+                channel: channel_name.clone(),
+                deadline: action.timeout.map(|timeout| Instant::now() + timeout),
+            });
+        }
+        match &action.mode {
+            SoundActionMode::Playlist => self.start_playlist(player, channel_name, action)?,
+            SoundActionMode::NextTrack => self.next_track(channel_name, action.fade)?,
+            _ => {}
+        }
+        let name = match &action.mode {
+            SoundActionMode::Playlist => None,
+            _ => action
+                .name
+                .as_ref()
+                .map(|name| self.resolve_sound(player, name)),
+        };
+        if let Some(name) = &name {
+            if self.dropped.contains_key(name) {
+                log::debug!("Skipped dropped sound '{name}' on channel '{channel_name}'");
+                return Ok(());
+            }
+            let _ = self.load_sound(name)?;
+        }
+        let properties = match &name {
+            Some(name) => self.with_defaults(action, name),
+            None => SoundDefaults {
+                volume: action.volume,
+                speed: action.speed,
+                pan: action.pan,
+                fade: action.fade.map(|fade| fade.as_millis() as u64),
+            },
+        };
+        let fade = properties.fade.map(Duration::from_millis);
+        if let (SoundActionMode::Loop, Some(name)) = (&action.mode, &name) {
+            self.loops.insert(
+                channel_name.clone(),
+                Loop {
+                    name: name.clone(),
+                    from: action.seek,
+                },
+            );
+        }
+
+        let sounds = &self.sounds;
+        let channel = self
+            .channels
+            .get_mut(channel_name)
+            .ok_or(anyhow!("Invalid sound channel '{channel_name}'"))?;
+
+        if let Some(speed) = properties.speed {
+            let (tempo, playback) = if action.preserve_pitch {
+                (speed, 1.0)
+            } else {
+                (1.0, speed)
+            };
+            channel.set_tempo(tempo, sounds)?;
+            channel.player.set_playback_speed(playback);
+        }
+
+        match &name {
+            None if matches!(action.mode, SoundActionMode::Playlist) => {}
+            None if matches!(action.mode, SoundActionMode::Crossfade) => {
+                return Err(anyhow!(
+                    "Sound action 'crossfade' on channel '{}' requires a sound effect name",
+                    channel_name
+                ));
+            }
+            None => {
+                if let Some(volume) = properties.volume {
+                    channel.set_volume(volume, sounds)?;
+                }
+                if let Some(pan) = properties.pan {
+                    channel.set_pan(pan, sounds)?;
+                }
+                Self::accept_general(channel, sounds, action.seek, fade, action.mode.clone())?;
+            }
+            Some(name) => {
+                let sfx = Self::get_sound_from(sounds, name)?;
+                // A passive action is ignored while something's playing, so it leaves the mix alone
+                let ignored = matches!(action.mode, SoundActionMode::Passive)
+                    && channel.player.has_current_song();
+                if !ignored {
+                    if let Some(volume) = properties.volume {
+                        channel.volume = volume.clamp(0.0, 1.0);
+                    }
+                    if let Some(pan) = properties.pan {
+                        channel.pan = pan.clamp(-1.0, 1.0);
+                    }
+                }
+                // Other sounds replace what's playing and are rendered with the new mix as they start,
+                // but a queued sound plays after it, so what's playing has to be re-rendered
+                let mixed = properties.volume.is_some() || properties.pan.is_some();
+                if mixed && matches!(action.mode, SoundActionMode::Queue) {
+                    channel.apply_mix(sounds)?;
+                }
+                let played = Self::accept_specific(
+                    channel,
+                    sounds,
+                    name,
+                    sfx,
+                    action.seek,
+                    fade,
+                    action.mode.clone(),
+                );
+                if let (true, Some(key)) = (played, cooldown_key) {
+                    self.played.insert(key, Instant::now());
+                }
+            }
+        }
+
+        if self.update_ducking(channel_name) {
+            self.apply_ducking()?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use camino::Utf8PathBuf;
+
+    use super::*;
+    use crate::{
+        core::{context::StaticContext, resources::Resources},
+        text_context,
+    };
+
+    fn manifest() -> Manifest {
+        Resources::test_manifest(
+            "test",
+            "channels: { music: true, sfx: true }, audio_warning: false",
+        )
+    }
+
+    #[test]
+    fn player_creation_failure_disables_audio() {
+        let config = manifest();
+        let dir = Utf8PathBuf::try_from(std::env::temp_dir()).unwrap();
+        let loader = Loader::new(dir, &None, &None).unwrap();
+        let failed = Some(Err(anyhow!("No sound device")));
+        let audio = Audio::load_with(&loader, &config, failed).unwrap();
+        assert!(audio.is_none());
+
+        let mut resources = Resources::empty(&config);
+        resources.audio = audio.map(RefCell::new);
+        let stc = StaticContext::new(&config, &resources);
+        let mut player = Player::new(&config);
+        let context = text_context!(&stc, player);
+        let sounds: SoundActions = serde_yaml::from_str(
+            "[{ name: theme, channel: music, mode: overwrite }, { channel: sfx, mode: await }, \
+             { channel: music, mode: pause }]",
+        )
+        .unwrap();
+        resources.submit_audio(&player, &sounds, &context).unwrap();
+        let music: Music = serde_yaml::from_str("theme").unwrap();
+        resources.submit_music(&player, &music, &context).unwrap();
+        resources.tick_audio().unwrap();
+
+        assert!(resources.audio.is_none());
+        for field in ["is_playing", "has_sound", "has_sound_queued"] {
+            let value = context.audio_variable(&format!("audio.music.{field}"));
+            assert_eq!(value.unwrap().unwrap(), "false", "{field}");
+        }
+        resources.record_audio(&mut player);
+        assert!(player.sounds.is_empty());
+    }
+
+    #[test]
+    fn non_positive_speeds_are_rejected() {
+        let config = manifest();
+        let resources = Resources::empty(&config);
+        let stc = StaticContext::new(&config, &resources);
+        let player = Player::new(&config);
+        let context = text_context!(&stc, player);
+        for speed in ["0", "-1.5", "NaN", "inf", "'{stats.choices_made}'"] {
+            let action: SoundAction =
+                serde_yaml::from_str(&format!("{{ channel: music, mode: play, speed: {speed} }}"))
+                    .unwrap();
+            assert!(action.fill(&context).is_err(), "{speed}");
+        }
+        let action: SoundAction =
+            serde_yaml::from_str("{ channel: music, mode: play, speed: 0.5 }").unwrap();
+        assert_eq!(action.fill(&context).unwrap().speed, Some(0.5));
+    }
+
+    #[test]
+    fn channel_statuses_follow_the_player() {
+        let config = manifest();
+        let channels: Vec<String> = ["music", "sfx", "voice"].map(str::to_owned).to_vec();
+        let dynamic = HashSet::from(["voice".to_owned()]);
+        let mut player = Player::new(&config);
+        let statuses = |player: &Player| -> BTreeMap<String, bool> {
+            Audio::channel_statuses_in(channels.iter(), &dynamic, player)
+                .into_iter()
+                .collect()
+        };
+        assert!(statuses(&player).values().all(|enabled| *enabled));
+
+        player.channels.remove("music");
+        player.disabled_channels.insert("music".to_owned());
+        player.disabled_channels.insert("voice".to_owned());
+        let content = serde_yaml::to_string(&player).unwrap();
+        let loaded = Player::load(content).unwrap();
+        assert_eq!(
+            statuses(&loaded),
+            BTreeMap::from([
+                ("music".to_owned(), false),
+                ("sfx".to_owned(), true),
+                ("voice".to_owned(), false),
+            ])
+        );
     }
 }
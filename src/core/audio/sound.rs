@@ -0,0 +1,596 @@
+use std::{
+    f32::consts::PI,
+    io::Cursor,
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use playback_rs::{Hint, Song};
+use symphonia::core::{
+    audio::SampleBuffer,
+    codecs::{Decoder, DecoderOptions},
+    errors::Error as SymphoniaError,
+    formats::{FormatOptions, FormatReader},
+    io::{MediaSource, MediaSourceStream},
+    meta::MetadataOptions,
+};
+
+/// A linear change in gain over a span of a sound.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fade {
+    /// The position in the sound at which the fade begins.
+    pub start: Duration,
+    /// How long the fade lasts.
+    pub length: Duration,
+    /// The gain multiplier before and at the start of the fade.
+    pub from: f32,
+    /// The gain multiplier at the end of the fade, held afterwards.
+    pub to: f32,
+}
+
+impl Fade {
+    /// Returns the gain multiplier at some position in the sound.
+    pub fn factor(&self, position: Duration) -> f32 {
+        if position <= self.start {
+            return self.from;
+        }
+        let elapsed = (position - self.start).as_secs_f32();
+        let length = self.length.as_secs_f32();
+        if elapsed >= length {
+            return self.to;
+        }
+        self.from + (self.to - self.from) * (elapsed / length)
+    }
+
+    /// Whether the fade is still in progress at some position in the sound.
+    pub fn is_active(&self, position: Duration) -> bool {
+        position < self.start + self.length
+    }
+}
+
+/// A second sound mixed on top of the one being rendered, such as the outgoing half of a crossfade.
+#[derive(Debug, Clone)]
+pub struct Layer {
+    /// The layered sound's content.
+    pub sound: Sound,
+    /// The position in the rendered sound at which the layer begins.
+    pub start: Duration,
+    /// The position in the layered sound that lines up with `start`.
+    pub offset: Duration,
+    /// The fade envelope of the layer, in terms of the rendered sound's positions.
+    pub fade: Fade,
+}
+
+impl Layer {
+    /// Whether the layer is still audible at some position in the rendered sound.
+    pub fn is_active(&self, position: Duration) -> bool {
+        position < self.start + self.sound.duration().saturating_sub(self.offset)
+            && (self.fade.is_active(position) || self.fade.to > 0.0)
+    }
+
+    /// Returns the layer's sample for a channel at some position in the rendered sound, before any gain.
+    fn sample(&self, channel: usize, position: Duration) -> f32 {
+        if position < self.start {
+            return 0.0;
+        }
+        let position = self.offset + (position - self.start);
+        let index = (position.as_secs_f64() * self.sound.sample_rate as f64) as usize;
+        let samples = &self.sound.samples[channel % self.sound.samples.len()];
+        samples.get(index).copied().unwrap_or(0.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Parameters controlling how a [`Sound`] is rendered into a [`Song`].
+pub struct Mix {
+    /// The gain applied to every sample.
+    pub gain: f32,
+    /// A fade envelope further scaling the gain, if any.
+    pub fade: Option<Fade>,
+    /// The position at which the rendered song is cut off, if any.
+    pub end: Option<Duration>,
+    /// Another sound mixed into the rendered song, if any.
+    pub layer: Option<Layer>,
+    /// The stereo balance of the rendered song, from `-1.0` (full left) to `1.0` (full right).
+    pub pan: f32,
+    /// The factor the sound is [time-stretched](Sound::stretched) by before rendering, where `1.0` leaves it untouched.
+    pub tempo: f64,
+}
+
+impl Mix {
+    /// A mix that only applies a constant gain.
+    pub fn gain(gain: f32) -> Self {
+        Self {
+            gain,
+            fade: None,
+            end: None,
+            layer: None,
+            pan: 0.0,
+            tempo: 1.0,
+        }
+    }
+
+    /// A mix that only applies a constant gain, stereo balance, and tempo.
+    pub fn flat(gain: f32, pan: f32, tempo: f64) -> Self {
+        Self {
+            pan,
+            tempo,
+            ..Self::gain(gain)
+        }
+    }
+
+    /// Returns the gain multiplier of the left or right output channel.
+    fn balance(&self, right: bool) -> f32 {
+        let pan = if right { self.pan } else { -self.pan };
+        (1.0 + pan).min(1.0)
+    }
+
+    /// Returns the gain multiplier of this mix's fade at some position, or `1.0` if there is no fade.
+    pub fn factor(&self, position: Duration) -> f32 {
+        self.fade.map(|fade| fade.factor(position)).unwrap_or(1.0)
+    }
+}
+
+/// A sound file that hasn't necessarily been decoded yet.
+#[derive(Debug, Clone)]
+pub enum SoundSource {
+    /// A file on disk, read when decoded.
+    File(Utf8PathBuf),
+    /// A file already read into memory, such as from an archive, along with its path.
+    Memory(Utf8PathBuf, Arc<Vec<u8>>),
+}
+
+/// The content of a file in memory, shared rather than copied each time it's [opened](SoundSource::open).
+struct SharedBytes(Arc<Vec<u8>>);
+
+impl AsRef<[u8]> for SharedBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl SoundSource {
+    /// The path of the sound file.
+    pub fn path(&self) -> &Utf8Path {
+        use SoundSource::*;
+        match self {
+            File(path) | Memory(path, _) => path,
+        }
+    }
+
+    /// Opens this source for reading along with a hint of its format.
+    fn open(&self) -> Result<(Box<dyn MediaSource>, Hint)> {
+        use SoundSource::*;
+        let source: Box<dyn MediaSource> = match self {
+            File(path) => Box::new(std::fs::File::open(path)?),
+            Memory(_, content) => Box::new(Cursor::new(SharedBytes(content.clone()))),
+        };
+        let mut hint = Hint::new();
+        if let Some(extension) = self.path().extension() {
+            hint.with_extension(extension);
+        }
+        Ok((source, hint))
+    }
+
+    /// Reads and decodes this source into a [`Sound`].
+    pub fn decode(&self) -> Result<Sound> {
+        let (source, hint) = self.open()?;
+        Sound::decode(source, &hint)
+    }
+
+    /// Checks that this source's format and codec are supported without decoding it.
+    pub fn probe(&self) -> Result<()> {
+        let (source, hint) = self.open()?;
+        let _ = Sound::reader(source, &hint)?;
+        Ok(())
+    }
+
+    /// Runs a task on each source, spread across as many threads as the machine can run at once.
+    ///
+    /// The results are in the same order as the sources, no matter which thread finishes first.
+    /// If a thread panics, each of its sources gets an error instead.
+    fn in_parallel<T, F>(sources: &[&SoundSource], task: F) -> Vec<Result<T>>
+    where
+        T: Send,
+        F: Fn(&SoundSource) -> Result<T> + Sync,
+    {
+        let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        let chunk = sources.len().div_ceil(threads).max(1);
+        let task = &task;
+        thread::scope(|scope| {
+            let handles: Vec<_> = sources
+                .chunks(chunk)
+                .map(|chunk| {
+                    let handle = scope
+                        .spawn(move || chunk.iter().map(|source| task(source)).collect::<Vec<_>>());
+                    (chunk, handle)
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|(chunk, handle)| {
+                    handle.join().unwrap_or_else(|_| {
+                        chunk
+                            .iter()
+                            .map(|source| {
+                                Err(anyhow!(
+                                    "Sound loading thread panicked while loading '{}'",
+                                    source.path()
+                                ))
+                            })
+                            .collect()
+                    })
+                })
+                .collect()
+        })
+    }
+
+    /// [Probes](Self::probe) several sources in parallel, returning the results in order.
+    pub fn probe_all(sources: &[&SoundSource]) -> Vec<Result<()>> {
+        Self::in_parallel(sources, Self::probe)
+    }
+
+    /// [Decodes](Self::decode) several sources in parallel, returning the results in order.
+    pub fn decode_all(sources: &[&SoundSource]) -> Vec<Result<Sound>> {
+        Self::in_parallel(sources, Self::decode)
+    }
+}
+
+/// A sound file decoded into memory as planar samples.
+///
+/// [`playback_rs`] offers no control over a [`Song`]'s samples once it has been created,
+/// so sounds are kept in this form and [rendered](Sound::render) into songs whenever they are played.
+#[derive(Debug, Clone)]
+pub struct Sound {
+    samples: Arc<Vec<Vec<f32>>>,
+    sample_rate: u32,
+    /// The most recent [time-stretched](Sound::stretched) copy of this sound and its tempo, if any.
+    stretched: Arc<Mutex<Option<(f64, Sound)>>>,
+}
+
+impl Sound {
+    /// The length of each [time-stretching](Sound::stretched) window, in seconds.
+    const STRETCH_WINDOW: f64 = 0.03;
+
+    fn from_samples(samples: Vec<Vec<f32>>, sample_rate: u32) -> Self {
+        Self {
+            samples: Arc::new(samples),
+            sample_rate,
+            stretched: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Returns a copy of this sound played `tempo` times as fast without changing its pitch.
+    ///
+    /// This uses WSOLA (waveform similarity overlap-add): the sound is cut into overlapping windows,
+    /// each shifted slightly to line up with the previous one before they are added back together.
+    /// Stretching is expensive, taking a noticeable fraction of a second for a song several minutes long,
+    /// so the most recent result is cached and reused for as long as the tempo doesn't change.
+    pub fn stretched(&self, tempo: f64) -> Sound {
+        if tempo == 1.0 || tempo <= 0.0 {
+            return self.clone();
+        }
+        let mut cache = self.stretched.lock().unwrap();
+        if let Some((cached, sound)) = cache.as_ref() {
+            if *cached == tempo {
+                return sound.clone();
+            }
+        }
+        let sound = Self::from_samples(self.time_stretch(tempo), self.sample_rate);
+        *cache = Some((tempo, sound.clone()));
+        sound
+    }
+
+    /// The average of every channel's sample at some frame, used to compare windows while stretching.
+    fn mono(&self, frame: usize) -> f32 {
+        self.samples
+            .iter()
+            .map(|channel| channel[frame])
+            .sum::<f32>()
+            / self.samples.len() as f32
+    }
+
+    /// Time-stretches the samples of this sound; see [`Sound::stretched`].
+    fn time_stretch(&self, tempo: f64) -> Vec<Vec<f32>> {
+        let frames = self.samples[0].len();
+        let window = ((self.sample_rate as f64 * Self::STRETCH_WINDOW) as usize).max(64);
+        let hop = window / 2;
+        let tolerance = (window / 4) as isize;
+        if frames <= window {
+            return self.samples.as_ref().clone();
+        }
+        // A periodic Hann window sums to exactly one at half overlap
+        let weights: Vec<f32> = (0..window)
+            .map(|n| 0.5 - 0.5 * (2.0 * PI * n as f32 / window as f32).cos())
+            .collect();
+        let last_start = (frames - window) as isize;
+        let output_frames = (frames as f64 / tempo) as usize + window;
+        let mut output = vec![vec![0.0; output_frames]; self.samples.len()];
+
+        let mut previous = 0isize;
+        let mut written = 0;
+        for out_start in (0..output_frames - window).step_by(hop) {
+            let nominal = (out_start as f64 * tempo) as isize;
+            if nominal > last_start {
+                break;
+            }
+            // Find the shift that best matches the natural continuation of the previous window
+            let target = (previous + hop as isize).min(last_start) as usize;
+            let start = if out_start == 0 {
+                0
+            } else {
+                let mut best = (f32::MIN, nominal.clamp(0, last_start));
+                for delta in (-tolerance..=tolerance).step_by(2) {
+                    let candidate = (nominal + delta).clamp(0, last_start) as usize;
+                    let similarity: f32 = (0..hop)
+                        .step_by(4)
+                        .map(|n| self.mono(candidate + n) * self.mono(target + n))
+                        .sum();
+                    if similarity > best.0 {
+                        best = (similarity, candidate as isize);
+                    }
+                }
+                best.1
+            };
+            for (channel, samples) in output.iter_mut().zip(self.samples.iter()) {
+                for n in 0..window {
+                    channel[out_start + n] += samples[start as usize + n] * weights[n];
+                }
+            }
+            previous = start;
+            written = out_start + window;
+        }
+        for channel in output.iter_mut() {
+            channel.truncate(written);
+        }
+        output
+    }
+
+    /// The length of this sound.
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.samples[0].len() as f64 / self.sample_rate as f64)
+    }
+
+    /// Decodes an entire media source into memory.
+    ///
+    /// Packets that fail to decode are skipped rather than failing the whole sound.
+    pub fn decode(source: Box<dyn MediaSource>, hint: &Hint) -> Result<Self> {
+        let (mut format, mut decoder) = Self::reader(source, hint)?;
+        let mut samples: Vec<Vec<f32>> = Vec::new();
+        let mut sample_rate = 0;
+        loop {
+            let packet = match format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(_)) => break,
+                Err(err) => return Err(anyhow!(err)),
+            };
+            let decoded = match decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                Err(SymphoniaError::DecodeError(_)) => continue,
+                Err(err) => return Err(anyhow!(err)),
+            };
+            let spec = *decoded.spec();
+            if samples.is_empty() {
+                samples = vec![Vec::new(); spec.channels.count()];
+                sample_rate = spec.rate;
+            } else if spec.channels.count() != samples.len() || spec.rate != sample_rate {
+                return Err(anyhow!("Sound file changes format partway through"));
+            }
+            if decoded.frames() == 0 {
+                continue;
+            }
+            let mut buffer = SampleBuffer::<f32>::new(decoded.frames() as u64, spec);
+            buffer.copy_interleaved_ref(decoded);
+            for frame in buffer.samples().chunks(samples.len()) {
+                for (channel, sample) in frame.iter().enumerate() {
+                    samples[channel].push(*sample);
+                }
+            }
+        }
+
+        if samples.first().map(Vec::is_empty).unwrap_or(true) {
+            return Err(anyhow!("No sound data decoded"));
+        }
+        Ok(Self::from_samples(samples, sample_rate))
+    }
+
+    /// Detects the container format of a sound file and creates a decoder for its default track.
+    fn reader(
+        source: Box<dyn MediaSource>,
+        hint: &Hint,
+    ) -> Result<(Box<dyn FormatReader>, Box<dyn Decoder>)> {
+        let stream = MediaSourceStream::new(source, Default::default());
+        let format_options = FormatOptions {
+            enable_gapless: true,
+            ..Default::default()
+        };
+        let probed = symphonia::default::get_probe()
+            .format(hint, stream, &format_options, &MetadataOptions::default())
+            .map_err(|err| anyhow!("Unrecognized sound format ({err})"))?;
+        let track = probed
+            .format
+            .default_track()
+            .ok_or(anyhow!("No default track in sound file"))?;
+        let decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .map_err(|err| anyhow!("Unsupported sound codec ({err})"))?;
+        Ok((probed.format, decoder))
+    }
+
+    /// Encodes this sound as an in-memory 32-bit float WAV file with the [`Mix`] applied to its samples.
+    ///
+    /// Mono sounds are rendered in stereo if the mix is panned.
+    fn to_wav(&self, mix: &Mix) -> Vec<u8> {
+        let channels = if self.samples.len() == 1 && mix.pan != 0.0 {
+            2
+        } else {
+            self.samples.len()
+        };
+        let frames = mix
+            .end
+            .map(|end| (end.as_secs_f64() * self.sample_rate as f64) as usize)
+            .unwrap_or(usize::MAX)
+            .min(self.samples[0].len());
+        let block_align = channels * 4;
+        let data_len = (frames * block_align) as u32;
+
+        let mut wav = Vec::with_capacity(44 + data_len as usize);
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        // IEEE float format code
+        wav.extend_from_slice(&3u16.to_le_bytes());
+        wav.extend_from_slice(&(channels as u16).to_le_bytes());
+        wav.extend_from_slice(&self.sample_rate.to_le_bytes());
+        wav.extend_from_slice(&(self.sample_rate * block_align as u32).to_le_bytes());
+        wav.extend_from_slice(&(block_align as u16).to_le_bytes());
+        wav.extend_from_slice(&32u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_len.to_le_bytes());
+        for frame in 0..frames {
+            let position = Duration::from_secs_f64(frame as f64 / self.sample_rate as f64);
+            let gain = mix.gain * mix.factor(position);
+            let layer = mix
+                .layer
+                .as_ref()
+                .filter(|layer| position >= layer.start && layer.is_active(position));
+            for index in 0..channels {
+                let mut sample = self.samples[index % self.samples.len()][frame] * gain;
+                if let Some(layer) = layer {
+                    sample +=
+                        layer.sample(index, position) * mix.gain * layer.fade.factor(position);
+                }
+                if channels == 2 {
+                    sample *= mix.balance(index == 1);
+                }
+                wav.extend_from_slice(&sample.to_le_bytes());
+            }
+        }
+        wav
+    }
+
+    /// Renders this sound into a [`Song`] that can be submitted to an audio player.
+    ///
+    /// This is how volume, fades, crossfades, panning, and pitch-preserving speed changes are applied to sounds; see [`Mix`].
+    pub fn render(&self, mix: &Mix) -> Result<Song> {
+        let mut hint = Hint::new();
+        hint.with_extension("wav");
+        let wav = self.stretched(mix.tempo).to_wav(mix);
+        Song::new(Box::new(Cursor::new(wav)), &hint, None).map_err(|err| anyhow!(err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    /// An in-memory WAV source holding a short mono tone with `frames` samples.
+    fn wav_source(name: &str, frames: usize) -> SoundSource {
+        let samples = (0..frames)
+            .map(|frame| (frame as f32 / 10.0).sin())
+            .collect();
+        let wav = Sound::from_samples(vec![samples], 8000).to_wav(&Mix::gain(1.0));
+        SoundSource::Memory(format!("{name}.wav").into(), Arc::new(wav))
+    }
+
+    fn sources() -> BTreeMap<String, SoundSource> {
+        (0..16)
+            .map(|index| {
+                let name = format!("sound_{index:02}");
+                let source = wav_source(&name, 100 + (16 - index) * 500);
+                (name, source)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn keeps_order_whichever_thread_finishes_first() {
+        let sources = sources();
+        let sources: Vec<&SoundSource> = sources.values().collect();
+        // Earlier sources take longer, so later threads finish first
+        let paths = SoundSource::in_parallel(&sources, |source| {
+            let index = sources
+                .iter()
+                .position(|other| other.path() == source.path());
+            thread::sleep(Duration::from_millis(2 * (16 - index.unwrap() as u64)));
+            Ok(source.path().to_owned())
+        });
+        let paths: Vec<Utf8PathBuf> = paths.into_iter().map(Result::unwrap).collect();
+        let expected: Vec<Utf8PathBuf> = sources
+            .iter()
+            .map(|source| source.path().to_owned())
+            .collect();
+        assert_eq!(paths, expected);
+    }
+
+    #[test]
+    fn panicking_threads_give_errors() {
+        let sources = sources();
+        let sources: Vec<&SoundSource> = sources.values().collect();
+        let panicking = sources[3].path();
+        let results =
+            SoundSource::in_parallel(&sources, |source| match source.path() == panicking {
+                true => panic!("failed to load"),
+                false => Ok(()),
+            });
+        assert_eq!(results.len(), sources.len());
+        let err = results[3].as_ref().unwrap_err();
+        assert!(err.to_string().contains("panicked"), "{err}");
+    }
+
+    #[test]
+    fn decodes_the_same_as_serially() {
+        let sources = sources();
+        let serial: BTreeMap<&String, Sound> = sources
+            .iter()
+            .map(|(name, source)| (name, source.decode().unwrap()))
+            .collect();
+        let values: Vec<&SoundSource> = sources.values().collect();
+        let parallel: BTreeMap<&String, Sound> = sources
+            .keys()
+            .zip(SoundSource::decode_all(&values))
+            .map(|(name, sound)| (name, sound.unwrap()))
+            .collect();
+        assert_eq!(serial.len(), parallel.len());
+        for ((name, serial), (other, parallel)) in serial.iter().zip(&parallel) {
+            assert_eq!(name, other);
+            assert_eq!(serial.sample_rate, parallel.sample_rate);
+            assert_eq!(serial.samples, parallel.samples, "{name}");
+        }
+    }
+
+    #[test]
+    fn reports_the_first_error_in_order() {
+        let mut sources = sources();
+        sources.insert("sound_03".to_owned(), SoundSource::File("missing/sound_03.wav".into()));
+        sources.insert(
+            "sound_09".to_owned(),
+            SoundSource::Memory("sound_09.wav".into(), Arc::new(b"not a sound".to_vec())),
+        );
+        let values: Vec<&SoundSource> = sources.values().collect();
+        for results in [
+            SoundSource::probe_all(&values),
+            SoundSource::decode_all(&values)
+                .into_iter()
+                .map(|result| result.map(|_| ()))
+                .collect(),
+        ] {
+            let failed: Vec<&String> = sources
+                .keys()
+                .zip(&results)
+                .filter(|(_, result)| result.is_err())
+                .map(|(name, _)| name)
+                .collect();
+            assert_eq!(failed, ["sound_03", "sound_09"]);
+            let first = results.into_iter().find_map(Result::err).unwrap();
+            assert!(first.downcast_ref::<std::io::Error>().is_some(), "{first:#}");
+        }
+    }
+}
@@ -1,14 +1,17 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
 use super::{
     audio::{SoundAction, SoundActions},
     context::{StaticContext, TextContext},
-    path::{Path, PathData, PathLookup},
+    path::{Path, PathData, PathLookup, WeightedPaths},
     player::HistoryEntry,
-    prompt::{Prompt, PromptModel},
+    prompt::{ArrangedChoice, Prompt, PromptModel, Prompts},
     state::{
         info::{InfoApplication, InfoApplications},
+        inventory::{self, Cost},
         notes::{NoteActions, Notes},
+        random::SeededRandom,
+        selection::Selection,
         variables::{
             NamedVariableEntry, VariableApplications, VariableEntries, VariableEntry,
             VariableInput, Variables,
@@ -17,10 +20,12 @@ use super::{
     text::{
         display::{choice_text, Text, TextLines},
         templating::{TemplatableString, TemplatableValue},
+        typewriter,
     },
 };
 
 use anyhow::{anyhow, Context, Result};
+use crossterm::{cursor, style::Stylize, terminal};
 use result::OptionResultExt;
 use serde::{Deserialize, Serialize};
 
@@ -47,13 +52,25 @@ pub struct Choice {
     /// See [`Choice::tag`] for more information.
     pub tag: Option<TemplatableString>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    /// A shortcut key that selects this choice in addition to its number, ignoring case.
+    /// Shown next to the choice's number. No two choices in a prompt can share a key.
+    pub key: Option<char>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// A container to prompt player input to save to a variable.
     /// There can only be one choice in an input prompt. It also has its own prompt model: [`Input`](PromptModel::Input).
     pub input: Option<VariableInput>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    /// A container letting the player pick several options to save to a variable before continuing.
+    /// There can only be one choice in a select prompt. It also has its own prompt model: [`Select`](PromptModel::Select).
+    pub select: Option<Selection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// The prompt to jump to after the choice is made and state is modified.
-    /// Mutually exclusive with `ending`.
+    /// Mutually exclusive with `jumps` and `ending`.
     pub jump: Option<Path>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Prompts to randomly pick one to jump to by weight, using the player's [`SeededRandom`].
+    /// Mutually exclusive with `jump` and `ending`.
+    pub jumps: Option<WeightedPaths>,
     #[serde(default = "default_true")]
     /// Whether to display the next prompt's introductory text.
     pub display: TemplatableValue<bool>,
@@ -62,6 +79,28 @@ pub struct Choice {
     /// If [`None`], defaults to the config.
     pub lock: Option<TemplatableValue<bool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    /// A condition that must be `true` for a player to use this choice, usually filled by a script
+    /// or a [visited reference](crate::core::context::TextContext::visited_variable) like `<visited.secret_room>`.
+    pub requires: Option<TemplatableValue<bool>>,
+    #[serde(default)]
+    /// Whether this choice is hidden once the player has used it, such as for a question that can only be asked once.
+    /// Used choices are tracked per prompt on the [`Player`](super::player::Player) and become usable again if the choice is reversed.
+    /// If using it leaves the prompt without any usable choices, the player returns to the previous prompt,
+    /// or the prompt's used choices are offered again if there's none to return to.
+    pub single_use: bool,
+    #[serde(default, alias = "show locked")]
+    /// Whether to display this choice greyed-out when its `requires` condition isn't met, rather than hiding it.
+    /// Locked choices aren't numbered, so the numbers of usable choices stay contiguous.
+    pub show_locked: TemplatableValue<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Items and currencies to take from a player's inventory upon using this choice.
+    /// If the player can't afford them, the choice is displayed greyed-out along with what it needs.
+    pub cost: Option<Cost>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether the player is asked to confirm this choice before it's made, such as for an irreversible decision.
+    /// Cancelling returns the player to the choices as if this one hadn't been picked. A choice made by its `timeout` isn't confirmed.
+    pub confirm: Option<Confirmation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// Note actions to apply and require from a player.
     pub notes: Option<NoteActions>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -77,6 +116,10 @@ pub struct Choice {
     /// Ordered sound actions to submit to the game's [`Audio`] resource upon using this choice.
     pub sounds: Option<SoundActions>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    /// How long, in milliseconds, a player has to answer the prompt before this choice is made for them.
+    /// A countdown is shown while the player answers. Only one choice in a prompt can have a timeout.
+    pub timeout: Option<TemplatableValue<u64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// Text lines to signify the ending of a game. Printed in the same way as prompt text.
     /// If this ending choice is the only one in a prompt, `response` is optional.
     /// If in this case `response` is [`None`], the prompt will have the [`Ending`](PromptModel::Ending) model.
@@ -86,37 +129,71 @@ pub struct Choice {
     pub drp: Option<TemplatableString>,
 }
 
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(untagged)]
+/// Whether a [`Choice`] asks the player to confirm it, either with the [default question](Confirmation::DEFAULT) or a custom one.
+pub enum Confirmation {
+    Flag(bool),
+    Question(TemplatableString),
+}
+
+impl Confirmation {
+    /// The question asked when a choice's `confirm` key is `true`.
+    pub const DEFAULT: &'static str = "Are you sure?";
+}
+
 /// A list of ordered [`Choice`]s.
 pub type Choices = Vec<Choice>;
 
-pub type UsableChoices<'a> = Vec<(&'a Choice, Option<String>)>;
+/// The choices a player can make in a prompt, in order, each along with the note it sets only once, if any,
+/// and how its prompt's `on_enter` hook [arranged](ArrangedChoice) it, if it did.
+pub type UsableChoices<'a> = Vec<(&'a Choice, Option<String>, Option<&'a ArrangedChoice>)>;
+
+/// How a [`Choice`] is displayed amongst a prompt's responses.
+pub enum ChoiceDisplay {
+    /// The choice is numbered and can be made.
+    Usable,
+    /// The choice is greyed-out and can't be made, optionally with the reason why.
+    Locked(Option<String>),
+}
 
 impl Choice {
     /// Validates a choice amongst the global prompt context.
     ///
     /// A choice is valid if:
-    /// - It has either a `jump` or `ending` section
-    /// - Its `jump` section **is not templatable** and points to a valid prompt
+    /// - It has either a `jump`, `jumps`, or `ending` section, and not both a `jump` and `jumps` section
+    /// - Its `jump` section and every path in its `jumps` section point to a valid prompt, if **not templatable**
     /// 	- The `file` key has to exist and the `prompt` key has to exist in that [`PromptFile`]
+    /// - Its `jumps` section isn't empty and every weight in it is positive
     /// - It has a `response` section if there is more than one choice in the prompt
+    /// - Its `key`, if any, isn't a digit, a command, or an input already bound in the manifest's `keybindings`
     pub fn validate(&self, local_file: &str, has_company: bool, stc: &StaticContext) -> Result<()> {
-        match &self.jump {
-            None => {
+        match (&self.jump, &self.jumps) {
+            (None, None) => {
                 if self.ending.is_none() {
                     return Err(anyhow!(
                         "Lacks `jump` section, but doesn't have an `ending` section"
                     ));
                 }
             }
-            Some(jump) => {
-                if let Some(file) = &jump.static_file(local_file) {
-                    if let Some(prompt) = jump.prompt().content() {
-                        let _ = Prompt::get(
-                            &stc.resources.prompts,
-                            &PathLookup::new(&file, prompt).into(),
-                        )
-                        .with_context(|| "`jump` section points to invalid prompt")?;
+            (Some(_), Some(_)) => {
+                return Err(anyhow!("'jump' and 'jumps' are mutually exclusive"));
+            }
+            (Some(jump), None) => {
+                Self::validate_jump(jump, local_file, stc)
+                    .with_context(|| "`jump` section points to invalid prompt")?;
+            }
+            (None, Some(jumps)) => {
+                if jumps.is_empty() {
+                    return Err(anyhow!("`jumps` section is empty"));
+                }
+                for (index, weighted) in jumps.iter().enumerate() {
+                    if weighted.weight == 0 {
+                        return Err(anyhow!("`jumps` path #{} has a weight of 0", index + 1));
                     }
+                    Self::validate_jump(&weighted.path, local_file, stc).with_context(|| {
+                        format!("`jumps` path #{} points to invalid prompt", index + 1)
+                    })?;
                 }
             }
         }
@@ -128,17 +205,91 @@ impl Choice {
         if self.response.is_some() && self.input.is_some() {
             return Err(anyhow!("'response' and 'input' are mutually exclusive"));
         }
+        if let Some(selection) = &self.select {
+            if self.response.is_some() || self.input.is_some() {
+                return Err(anyhow!("'select' is mutually exclusive with 'response' and 'input'"));
+            }
+            if self.jump.is_none() && self.jumps.is_none() {
+                return Err(anyhow!("Has a `select` section, but lacks a jump to continue to"));
+            }
+            selection
+                .validate()
+                .with_context(|| "Invalid `select` section")?;
+            for option in &selection.options {
+                stc.resources.scripts.validate_segments(option.text())?;
+            }
+        }
+        if self.response.is_none() && self.confirm.is_some() {
+            return Err(anyhow!(
+                "Has a `confirm` section, but lacks a `response` section to confirm"
+            ));
+        }
+        if let Some(range) = self.input.as_ref().and_then(|input| input.range.as_ref()) {
+            range
+                .validate()
+                .with_context(|| "Invalid `range` section of `input`")?;
+        }
+        if let Some(key) = self.key {
+            let bound = stc
+                .config
+                .settings
+                .keybindings
+                .choice_action(&key.to_string())
+                .is_some();
+            if key.is_ascii_digit() || key == '.' || key.is_whitespace() || bound {
+                return Err(anyhow!(
+                    "Shortcut key '{key}' can't be a digit, a command, or an input bound in `settings.keybindings`"
+                ));
+            }
+        }
         if let Some(apps) = &self.info_pages {
             InfoApplication::validate_all(apps, &stc.resources.info_pages)?;
         }
+        let templated = [
+            self.response.as_ref().map(|response| &response.content),
+            self.tag.as_ref(),
+            self.requires.as_ref().and_then(TemplatableValue::templated),
+        ];
+        for string in templated.into_iter().flatten() {
+            stc.resources.scripts.validate_segments(string)?;
+        }
         if let Some(audio) = &stc.resources.audio {
             if let Some(sounds) = &self.sounds {
-                SoundAction::validate_all(sounds, audio)?;
+                SoundAction::validate_all(sounds, &audio.borrow())?;
+            }
+            if let Some(sounds) = self.response.as_ref().and_then(|text| text.sounds.as_ref()) {
+                SoundAction::validate_all(sounds, &audio.borrow())
+                    .with_context(|| "Failed to validate `response` section")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates that a [`Path`] points to a valid prompt if it isn't templatable.
+    pub fn validate_jump(jump: &Path, local_file: &str, stc: &StaticContext) -> Result<()> {
+        let prompts = &stc.resources.prompts;
+        if let Some(file) = jump.static_file(local_file, prompts).invert()? {
+            if let Some(prompt) = jump.prompt().content() {
+                let target: PathData = PathLookup::new(&file, prompt).into();
+                let _ = Prompt::get(prompts, &target)
+                    .with_context(|| format!("Failed to resolve jump to {target}"))?;
             }
         }
         Ok(())
     }
 
+    /// Returns the path this choice jumps to, picking one of its `jumps` by weight if it has them.
+    fn resolve_jump(&self, random: &SeededRandom) -> Option<&Path> {
+        if let Some(jump) = &self.jump {
+            return Some(jump);
+        }
+        let jumps = self.jumps.as_ref()?;
+        let weights: Vec<u32> = jumps.iter().map(|weighted| weighted.weight).collect();
+        random
+            .pick_weighted(&weights)
+            .map(|index| &jumps[index].path)
+    }
+
     /// Creates a map of variable entries to use when creating a new [`HistoryEntry`].
     ///
     /// If both the input result and this choice's `variables` key are [`None`], returns none.
@@ -167,6 +318,7 @@ impl Choice {
     /// Constructs a [`HistoryEntry`] based on this choice object.
     ///
     /// Copies over control flags, the path based on the latest history entry, and notes and variable applications.
+    /// If this choice has weighted `jumps`, one is picked using the text context's [random generator](TextContext::random).
     pub fn to_history_entry(
         &self,
         latest: &HistoryEntry,
@@ -177,7 +329,7 @@ impl Choice {
         stc: &StaticContext,
         text_context: &TextContext,
     ) -> Option<Result<HistoryEntry>> {
-        self.jump.as_ref().map(|jump| {
+        self.resolve_jump(text_context.random()).map(|jump| {
             Ok(HistoryEntry {
                 path: jump.fill(&latest.path, text_context)?,
                 display: self.display.get_value(text_context)?,
@@ -194,14 +346,57 @@ impl Choice {
                     .map(|n| n.to_note_entries(once, text_context))
                     .invert()?,
                 variables: self.create_variable_entries(input, variables, text_context)?,
+                cost: self
+                    .cost
+                    .as_ref()
+                    .map(|cost| cost.fill(text_context))
+                    .invert()?,
+                gained: None,
+                random: None,
+                used: None,
                 log: self.log.is_some(),
             })
         })
     }
 
+    /// Whether this choice's `requires` condition, if any, is met.
+    pub fn meets_requirement(&self, text_context: &TextContext) -> Result<bool> {
+        self.requires
+            .as_ref()
+            .map(|requires| requires.get_value(text_context))
+            .invert()
+            .map(|met| met.unwrap_or(true))
+    }
+
+    /// Whether this choice should be displayed as locked, having failed its `requires` condition with `show_locked` set.
+    pub fn is_shown_locked(&self, text_context: &TextContext) -> Result<bool> {
+        Ok(self.show_locked.get_value(text_context)? && !self.meets_requirement(text_context)?)
+    }
+
+    /// The question to ask the player before this choice is made, if it asks for [confirmation](Confirmation).
+    pub fn confirmation(&self, text_context: &TextContext) -> Result<Option<String>> {
+        let result = match &self.confirm {
+            None | Some(Confirmation::Flag(false)) => None,
+            Some(Confirmation::Flag(true)) => Some(Confirmation::DEFAULT.to_owned()),
+            Some(Confirmation::Question(question)) => Some(question.fill(text_context)?),
+        };
+        Ok(result)
+    }
+
+    /// Describes what a player lacks to afford this choice's `cost`, if anything.
+    ///
+    /// The player's inventory is read from the [`TextContext`] snapshot.
+    pub fn shortfall(&self, text_context: &TextContext) -> Result<Option<String>> {
+        let Some(cost) = &self.cost else {
+            return Ok(None);
+        };
+        Ok(inventory::shortfall(&text_context.inventory, &cost.fill(text_context)?))
+    }
+
     /// Determines if a player can use this choice.
     ///
     /// This check passes if:
+    /// - The `requires` condition, if any, is met,
     /// - All note requirement `has` fields match the state of the provided [`Notes`] object, and
     /// - The notes object does not contain the `once` value, if any is present
     ///     - The filled `once` value is also returned. It must be carried over to when the choice is used and applied then.
@@ -210,6 +405,9 @@ impl Choice {
         notes: &Notes,
         text_context: &TextContext,
     ) -> Result<(bool, Option<String>)> {
+        if !self.meets_requirement(text_context)? {
+            return Ok((false, None));
+        }
         let once = 'outer: {
             if let Some(actions) = &self.notes {
                 if let Some(require) = &actions.require {
@@ -246,37 +444,178 @@ impl Choice {
         Ok(result)
     }
 
-    /// Constructs the response line for display in the game's runtime.
+    /// Constructs the response line for display in the game's runtime, including the choice's shortcut `key`, if any.
     ///
     /// ### Examples
     ///
     /// - `1) [ROGUE] "Ain't no thief."`
-    /// - `2) Put down the sword`
-    fn response_line(&self, index: usize, text_context: &TextContext) -> Result<String> {
+    /// - `2/s) Put down the sword`
+    fn response_line(
+        &self,
+        index: usize,
+        text: Option<&str>,
+        text_context: &TextContext,
+    ) -> Result<String> {
+        let tag = self.tag(text_context)?;
+        let response = self.response_text(text, text_context)?;
+        let key = self.key.map(|key| format!("/{key}")).unwrap_or_default();
+        Ok(format!("{index}{key}) {tag}{response}"))
+    }
+
+    /// Constructs the greyed-out response line of a locked choice, along with the reason it's locked, if any.
+    ///
+    /// ### Examples
+    ///
+    /// - `-) [ROGUE] Pick the lock`
+    /// - `-) Buy the sword (needs 10 gold)`
+    fn locked_line(
+        &self,
+        reason: Option<&str>,
+        text: Option<&str>,
+        text_context: &TextContext,
+    ) -> Result<String> {
         let tag = self.tag(text_context)?;
-        let response = self.response.as_ref().unwrap().get(text_context)?;
-        Ok(format!("{index}) {tag}{response}"))
+        let response = self.response_text(text, text_context)?;
+        let reason = reason
+            .map(|reason| format!(" ({reason})"))
+            .unwrap_or_default();
+        Ok(format!("-) {tag}{response}{reason}")
+            .dark_grey()
+            .to_string())
+    }
+
+    /// Retrieves this choice's response, with its content replaced by some already-filled `text` if provided.
+    fn response_text(&self, text: Option<&str>, text_context: &TextContext) -> Result<String> {
+        let response = self.response.as_ref().unwrap();
+        match text {
+            Some(text) => response.get_as(text, text_context),
+            None => response.get(text_context),
+        }
+    }
+
+    /// Constructs the lines of ordered choice responses, each along with its choice's number.
+    ///
+    /// Only [usable](ChoiceDisplay::Usable) choices are numbered. Each choice's response text can be replaced,
+    /// such as by an [`ArrangedChoice`].
+    pub fn display(
+        choices: &[(&Choice, ChoiceDisplay, Option<&str>)],
+        text_context: &TextContext,
+    ) -> Result<Vec<(Option<usize>, String)>> {
+        let mut index = 0;
+        choices
+            .iter()
+            .filter(|(choice, ..)| choice.response.is_some())
+            .map(|(choice, display, text)| match display {
+                ChoiceDisplay::Locked(reason) => choice
+                    .locked_line(reason.as_deref(), *text, text_context)
+                    .map(|line| (None, line)),
+                ChoiceDisplay::Usable => {
+                    index += 1;
+                    choice
+                        .response_line(index, *text, text_context)
+                        .map(|line| (Some(index), line))
+                }
+            })
+            .try_collect()
     }
 
-    /// Constructs a [`String`] of ordered choice responses.
-    pub fn display(choices: &Vec<&Choice>, text_context: &TextContext) -> Result<String> {
-        let result = choices
+    /// Finds the usable choice with a `timeout`, if any, returning its index and filled timeout.
+    pub fn find_timed(
+        choices: &UsableChoices,
+        text_context: &TextContext,
+    ) -> Result<Option<(usize, Duration)>> {
+        choices
             .iter()
             .enumerate()
-            .filter(|(_, choice)| choice.response.is_some())
-            .map(|(index, choice)| choice.response_line(index + 1, text_context))
-            .try_collect::<Vec<String>>()?
-            .join("\n");
-        Ok(result)
+            .find_map(|(index, (choice, ..))| {
+                choice.timeout.as_ref().map(|timeout| {
+                    timeout
+                        .get_value(text_context)
+                        .map(|ms| (index, Duration::from_millis(ms)))
+                })
+            })
+            .invert()
     }
 
     /// Whether this choice jumps to a specific prompt.
     ///
-    /// Returns `true` if the choice has a `jump` path and [`Path::matches`] passes.
-    pub fn has_jump_to(&self, current_file: &str, other: &PathData) -> bool {
-        match &self.jump {
-            None => false,
-            Some(jump) => jump.matches(current_file, other),
+    /// Returns `true` if the choice has a `jump` path, or any `jumps` path, that [`Path::matches`].
+    pub fn has_jump_to(&self, current_file: &str, other: &PathData, prompts: &Prompts) -> bool {
+        let jumps = self.jumps.iter().flatten().map(|weighted| &weighted.path);
+        self.jump
+            .iter()
+            .chain(jumps)
+            .any(|jump| jump.matches(current_file, other, prompts))
+    }
+}
+
+/// The screen rows that each numbered choice line was printed on, so that a click can be resolved to a choice.
+#[derive(Debug)]
+pub struct ChoiceLayout {
+    /// The row that input is read on, which mustn't have moved for the layout to still be accurate.
+    input_row: u16,
+    /// Each numbered choice along with the first and last rows it takes up.
+    rows: Vec<(usize, u16, u16)>,
+}
+
+impl ChoiceLayout {
+    /// Measures the layout of choice [lines](Choice::display) that were just printed and followed by a blank line,
+    /// with the cursor now on the row that input is read on.
+    ///
+    /// Returns [`None`] if the terminal's size or the cursor's position can't be read.
+    pub fn measure(lines: &[(Option<usize>, String)]) -> Option<Self> {
+        let width = terminal::size().ok()?.0.max(1) as usize;
+        let (_, input_row) = cursor::position().ok()?;
+        let mut rows = Vec::new();
+        let mut bottom = input_row.checked_sub(2)?;
+        for (number, line) in lines.iter().rev() {
+            let height = Self::height(line, width) as u16;
+            let Some(top) = (bottom + 1).checked_sub(height) else {
+                break;
+            };
+            if let Some(number) = number {
+                rows.push((*number, top, bottom));
+            }
+            let Some(next) = top.checked_sub(1) else {
+                break;
+            };
+            bottom = next;
+        }
+        Some(Self { input_row, rows })
+    }
+
+    /// The number of rows a line takes up on a terminal `width` columns wide.
+    ///
+    /// Each of the line's own lines starts on a new row and wraps separately, taking up at least one row even if empty.
+    fn height(line: &str, width: usize) -> usize {
+        line.split('\n')
+            .map(|segment| typewriter::visible_len(segment).div_ceil(width).max(1))
+            .sum()
+    }
+
+    /// The number of the choice printed on a row, if input is still read on the row the layout was measured with.
+    pub fn choice_at(&self, row: u16, input_row: u16) -> Option<usize> {
+        if input_row != self.input_row {
+            return None;
         }
+        self.rows
+            .iter()
+            .find(|(_, top, bottom)| (*top..=*bottom).contains(&row))
+            .map(|(number, ..)| *number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measures_wrapped_and_multiline_choices() {
+        assert_eq!(ChoiceLayout::height("", 10), 1);
+        assert_eq!(ChoiceLayout::height("1) Go", 10), 1);
+        assert_eq!(ChoiceLayout::height("1) Go north, then east", 10), 3);
+        assert_eq!(ChoiceLayout::height("1) Go\n   north", 10), 2);
+        assert_eq!(ChoiceLayout::height("1) Go north\n\n   or east", 10), 4);
+        assert_eq!(ChoiceLayout::height("\x1b[31m1) Go\x1b[0m", 5), 1);
     }
 }
@@ -1,10 +1,20 @@
+use std::{collections::HashSet, time::Duration};
+
+use anyhow::{anyhow, Result};
 use rlua::{Context, Table};
 
 use crate::core::{
+    audio::ChannelState,
     manifest::Manifest,
     resources::Resources,
-    state::{notes::Notes, variables::Variables},
-    text::display::TranslationFile,
+    state::{
+        inventory::Inventory, notes::Notes, random::SeededRandom, store::Store,
+        variables::Variables,
+    },
+    text::{
+        display::{format_duration, TranslationFile},
+        plural::PluralRule,
+    },
 };
 
 /// A wrapper for content that is explicitly constant from after the game is loaded until its end.
@@ -40,27 +50,136 @@ impl<'a> Clone for StaticContext<'a> {
 /// - `game_authors`: The metadata's `authors` key, represented as a sequence
 /// - `game_version`: The metadata's `version` key
 /// - `lang`: The currently loaded language key
+/// - `playtime`: The player's total playtime, formatted like `2h 13m` when templated and in seconds in scripts
+/// - `choices_made`: How many choices the player has made, not counting prompts that redirect on their own
+///
+/// The player's stats can also be referenced with `{stats.name}`, such as `{stats.playtime}`; see [`TextContext::stat`].
+///
+/// Player [`Store`] values can be referenced with `{var.name}`; see [`TemplatableString::fill`](crate::core::text::templating::TemplatableString::fill).
+///
+/// Words can be pluralized with `{plural:count:singular:plural}` following the current language's rules; see [`TextContext::plural`].
+///
+/// Translations can also be referenced with the `lang:` prefix, such as `<lang:some.key>`; see [`TextContext::translate`].
+///
+/// Whether the player has visited a prompt can be referenced like `<visited.secret_room>`, or used as a condition
+/// like `{if visited.secret_room: ...}`; see [`TextContext::visited_variable`].
+///
+/// The playback state of audio channels can be referenced like `<audio.music.volume>`, or used as a condition
+/// like `{if audio.music.is_playing: ...}`; see [`TextContext::audio_variable`].
 pub struct TextContext<'a> {
     stc: StaticContext<'a>,
     lang: String,
     pub notes: Notes,
     pub variables: Variables,
+    pub inventory: Inventory,
+    pub store: Store,
+    playtime: u64,
+    /// How many [choices the player has made](crate::core::player::Player::choices_made).
+    choices_made: u64,
+    /// The paths of the prompts the player has [visited](crate::core::player::Player::visited).
+    visited: HashSet<String>,
+    /// The file of the player's current prompt, which [visited references](Self::visited_variable) are relative to.
+    file: Option<String>,
+    /// A copy of the player's random generator, which scripts start from and weighted jumps draw from.
+    random: SeededRandom,
+    /// The player's [text speed factor](crate::core::state::preferences::Preferences::text_speed).
+    text_speed: f32,
+    /// Whether the player has [accessibility mode](crate::core::state::preferences::Preferences::accessibility) on.
+    accessible: bool,
 }
 
 impl<'a> TextContext<'a> {
+    /// The prefix of [audio references](Self::audio_variable).
+    const AUDIO: &'static str = "audio.";
+
+    /// The prefix of [visited references](Self::visited_variable).
+    const VISITED: &'static str = "visited.";
+
+    /// The names of the player's [stats](Self::stat).
+    pub const STATS: [&'static str; 2] = ["playtime", "choices_made"];
+
     /// Constructs a new [`TextContext`] object using owned snapshots of player data and a [`StaticContext`] reference.
     ///
     /// The resulting text context does not own the provided [`StaticContext`] reference, rather a new copy based on
     /// the static context [`Clone`] implementation, which preserves the internal references.
-    pub fn new(stc: &'a StaticContext, lang: String, notes: Notes, variables: Variables) -> Self {
+    pub fn new(
+        stc: &'a StaticContext,
+        lang: String,
+        notes: Notes,
+        variables: Variables,
+        inventory: Inventory,
+        store: Store,
+        playtime: u64,
+    ) -> Self {
         TextContext {
             stc: stc.clone(),
             lang,
             notes,
             variables,
+            inventory,
+            store,
+            playtime,
+            choices_made: 0,
+            visited: HashSet::new(),
+            file: None,
+            random: SeededRandom::new(0),
+            text_speed: 1.0,
+            accessible: false,
         }
     }
 
+    /// Sets the player's [random generator state](SeededRandom::state) that scripts and weighted jumps draw from.
+    pub fn with_random(self, random: u64) -> Self {
+        self.random.restore(random);
+        self
+    }
+
+    /// Sets how many [choices the player has made](crate::core::player::Player::choices_made).
+    pub fn with_choices_made(mut self, choices_made: u64) -> Self {
+        self.choices_made = choices_made;
+        self
+    }
+
+    /// Sets the prompts the player has [visited](crate::core::player::Player::visited) and the file of their current prompt.
+    pub fn with_visited(mut self, visited: HashSet<String>, file: Option<String>) -> Self {
+        self.visited = visited;
+        self.file = file;
+        self
+    }
+
+    /// Sets the player's [text speed factor](crate::core::state::preferences::Preferences::text_speed).
+    pub fn with_text_speed(mut self, text_speed: f32) -> Self {
+        self.text_speed = text_speed;
+        self
+    }
+
+    /// The factor that text is printed faster by.
+    pub fn text_speed(&self) -> f32 {
+        self.text_speed
+    }
+
+    /// Sets whether the player has [accessibility mode](crate::core::state::preferences::Preferences::accessibility) on.
+    pub fn with_accessibility(mut self, accessible: bool) -> Self {
+        self.accessible = accessible;
+        self
+    }
+
+    /// Whether output should be kept screen-reader-friendly.
+    pub fn is_accessible(&self) -> bool {
+        self.accessible
+    }
+
+    /// The state of this context's random generator, which is the player's when this context was created
+    /// unless it's been [drawn from](Self::random) since.
+    pub fn random_state(&self) -> u64 {
+        self.random.state()
+    }
+
+    /// This context's copy of the player's random generator.
+    pub fn random(&self) -> &SeededRandom {
+        &self.random
+    }
+
     pub fn config(&self) -> &Manifest {
         &self.stc.config
     }
@@ -73,6 +192,21 @@ impl<'a> TextContext<'a> {
         self.stc.resources.lang_file(&self.lang)
     }
 
+    /// Looks up a translation key in the current lang file, falling back to the manifest's default language.
+    pub fn translate(&self, key: &str) -> Option<&String> {
+        self.lang_file().and_then(|file| file.get(key)).or_else(|| {
+            self.stc
+                .resources
+                .lang_file(&self.stc.config.settings.text.lang())
+                .and_then(|file| file.get(key))
+        })
+    }
+
+    /// Picks the plural form for `count` according to the current language's [`PluralRule`].
+    pub fn plural<'b>(&self, count: i64, forms: &[&'b str]) -> &'b str {
+        PluralRule::for_lang(&self.lang).select(count, forms)
+    }
+
     /// Attempts to fetch a global variable for direct templating.
     /// These variables are prefixed under `nage:`.
     ///
@@ -85,11 +219,78 @@ impl<'a> TextContext<'a> {
                 "game_authors" => Some(self.stc.config.metadata.authors.join(", ")),
                 "game_version" => Some(self.stc.config.metadata.version.to_string()),
                 "lang" => Some(self.lang.to_owned()),
+                "playtime" | "choices_made" => self.stat(name),
                 _ => None,
             })
             .flatten()
     }
 
+    /// Formats one of the player's [`STATS`](Self::STATS) for templating, or returns [`None`] if there's no such stat.
+    ///
+    /// - `playtime`: The total playtime, like `2h 13m`, which starts at `0m`
+    /// - `choices_made`: The number of choices made, starting at `0`
+    pub fn stat(&self, name: &str) -> Option<String> {
+        match name {
+            "playtime" => Some(format_duration(Duration::from_secs(self.playtime))),
+            "choices_made" => Some(self.choices_made.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Whether the player has visited a prompt, given a reference like `visited.secret_room` or `visited.caves/secret_room`.
+    ///
+    /// A prompt without a file refers to one in the current prompt's file. The current prompt itself only counts
+    /// as visited once the player has [moved on](crate::core::player::Player::visited) from it at least once.
+    ///
+    /// Returns [`None`] if `var` isn't a visited reference.
+    pub fn visited_variable(&self, var: &str) -> Option<bool> {
+        let path = var.strip_prefix(Self::VISITED)?.trim();
+        let visited = match (path.contains('/'), &self.file) {
+            (true, _) => self.visited.contains(path),
+            (false, Some(file)) => self.visited.contains(&format!("{file}/{path}")),
+            (false, None) => false,
+        };
+        Some(visited)
+    }
+
+    /// Reads an audio channel's playback state for templating, given a reference like `audio.music.is_playing`.
+    ///
+    /// The fields are the same as in the scripts' [audio table](crate::core::audio::Audio::create_audio_table); see [`ChannelState::FIELDS`].
+    /// Channels that haven't been created yet, and every channel if audio is unavailable, read as [silent](ChannelState::silent).
+    ///
+    /// Returns [`None`] if `var` isn't an audio reference, or an error if the field doesn't exist.
+    pub fn audio_variable(&self, var: &str) -> Option<Result<String>> {
+        let (channel, field) = var.strip_prefix(Self::AUDIO)?.rsplit_once('.')?;
+        let value = match &self.stc.resources.audio {
+            Some(audio) => audio.borrow().channel_field(channel, field),
+            None => ChannelState::silent().field(field, self.stc.config.settings.master_volume),
+        };
+        Some(value.ok_or(anyhow!("Audio channel state has no field '{field}'")))
+    }
+
+    /// Checks the field of an [audio reference](Self::audio_variable), if it is one.
+    ///
+    /// The channel isn't checked, since channels are created on demand and one that doesn't exist yet reads as silent.
+    /// References used as a `condition` must name a boolean field.
+    pub fn validate_audio_variable(var: &str, condition: bool) -> Result<()> {
+        let Some(reference) = var.strip_prefix(Self::AUDIO) else {
+            return Ok(());
+        };
+        let Some((_, field)) = reference.rsplit_once('.') else {
+            return Ok(());
+        };
+        match ChannelState::FIELDS.iter().find(|(name, _)| *name == field) {
+            None => return Err(anyhow!("Audio channel state has no field '{field}'")),
+            Some((_, false)) if condition => {
+                return Err(anyhow!(
+                "Audio channel state field '{field}' isn't a boolean, so it can't be a condition"
+            ))
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
     /// Creates a global variable table for use in scripts.
     /// This should be set as a global `nage` table.
     pub fn create_variable_table<'b>(
@@ -104,6 +305,13 @@ impl<'a> TextContext<'a> {
         )?;
         table.set("game_version", self.stc.config.metadata.version.to_string())?;
         table.set("lang", self.lang.clone())?;
+        table.set("playtime", self.playtime)?;
+        table.set("choices_made", self.choices_made)?;
+        let visited = context.create_table()?;
+        for path in &self.visited {
+            visited.set(path.clone(), true)?;
+        }
+        table.set("visited", visited)?;
         Ok(table)
     }
 }
@@ -116,6 +324,17 @@ macro_rules! text_context {
             $player.lang.clone(),
             $player.notes.clone(),
             $player.variables.clone(),
+            $player.inventory.clone(),
+            $player.store.clone(),
+            $player.playtime,
+        )
+        .with_random($player.random.state())
+        .with_choices_made($player.choices_made)
+        .with_visited(
+            $player.visited.clone(),
+            $player.history.back().map(|entry| entry.path.file.clone()),
         )
+        .with_text_speed($player.preferences.text_speed())
+        .with_accessibility($player.preferences.accessibility($stc.config))
     };
 }
@@ -0,0 +1,61 @@
+use std::cell::Cell;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+use super::manifest::KeyBindings;
+
+#[derive(Debug)]
+/// A mode toggled by the [fast-forward key](KeyBindings::fast_forward) that advances instantly through prompts
+/// the player has already seen.
+///
+/// While it's active, text is printed all at once, waits are skipped, and prompts with a single choice are
+/// advanced through without input. It stops on its own once the player reaches a prompt they haven't seen before.
+pub struct FastForward {
+    key: Option<char>,
+    active: Cell<bool>,
+}
+
+impl FastForward {
+    pub fn new(bindings: &KeyBindings) -> Self {
+        Self {
+            key: bindings.fast_forward,
+            active: Cell::new(false),
+        }
+    }
+
+    /// Whether a key press is the fast-forward key held with Ctrl.
+    pub fn is_key(&self, event: &KeyEvent) -> bool {
+        match (self.key, event.code) {
+            (Some(key), KeyCode::Char(c)) => {
+                event.kind == KeyEventKind::Press
+                    && c == key
+                    && event.modifiers.contains(KeyModifiers::CONTROL)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the game is currently fast-forwarding.
+    pub fn is_active(&self) -> bool {
+        self.active.get()
+    }
+
+    /// Starts or stops fast-forwarding, returning whether it's now active.
+    pub fn toggle(&self) -> bool {
+        let active = !self.active.get();
+        self.active.set(active);
+        active
+    }
+
+    /// Starts fast-forwarding if a key pressed while the game is blocked is the fast-forward key.
+    pub fn start_blocked(&self, event: &KeyEvent) {
+        if self.is_key(event) {
+            self.active.set(true);
+        }
+    }
+
+    /// Stops fast-forwarding, such as when reaching unseen content.
+    pub fn stop(&self) {
+        self.active.set(false);
+    }
+}
@@ -1,10 +1,12 @@
 use std::{
     collections::{HashMap, HashSet},
     str::FromStr,
+    time::Duration,
 };
 
 use anyhow::{anyhow, Context, Result};
-
+use camino::Utf8PathBuf;
+use log::LevelFilter;
 use semver::{Version, VersionReq};
 use serde::Deserialize;
 
@@ -13,7 +15,7 @@ use crate::{
         display::{TextLines, TextSpeed},
         templating::{TemplatableString, TemplatableValue},
     },
-    loading::loader::Loader,
+    loading::{loader::Loader, protection::SaveProtection},
     NAGE_VERSION,
 };
 
@@ -23,7 +25,13 @@ use super::{
     discord::{RichPresence, RichPresenceMode},
     path::PathData,
     player::{HistoryEntry, Player},
-    state::{info::UnlockedInfoPages, notes::Notes, variables::Variables},
+    state::{
+        achievements::{Achievement, Achievements},
+        info::UnlockedInfoPages,
+        inventory::Inventory,
+        notes::Notes,
+        variables::Variables,
+    },
 };
 
 #[derive(Deserialize, Debug)]
@@ -90,6 +98,12 @@ pub struct HistorySettings {
     pub locked: bool,
     #[serde(alias = "max size", alias = "max entries")]
     pub size: usize,
+    #[serde(alias = "back input")]
+    /// Superseded by [`KeyBindings::back`], which this is used for if unset.
+    pub back_input: Option<String>,
+    /// How many [bookmarks](crate::core::player::Bookmark) the player can keep at once during a session,
+    /// with the oldest dropped to make room. Bookmarking is turned off if this is `0`.
+    pub bookmarks: usize,
 }
 
 impl Default for HistorySettings {
@@ -97,10 +111,358 @@ impl Default for HistorySettings {
         Self {
             locked: false,
             size: 5,
+            back_input: None,
+            bookmarks: 1,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+/// How often the game automatically saves the player.
+pub enum AutosaveMode {
+    Off,
+    #[serde(alias = "every-prompt", alias = "every prompt")]
+    /// Saves whenever the player arrives at a prompt.
+    EveryPrompt,
+    #[serde(alias = "every-choice", alias = "every choice")]
+    /// Saves whenever the player makes a choice.
+    EveryChoice,
+}
+
+#[derive(Deserialize, Debug, Default, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+/// Whether the terminal is cleared before each prompt is shown.
+pub enum ScreenMode {
+    #[default]
+    /// Prompts are printed after one another with a separator between them, keeping the scrollback.
+    Append,
+    /// The terminal is cleared before every prompt.
+    Clear,
+    #[serde(alias = "clear-on-chapter", alias = "clear on chapter")]
+    /// The terminal is cleared before a prompt that starts a [chapter](crate::core::prompt::Chapter) or is from a different file than the last,
+    /// and prompts are separated otherwise.
+    ClearOnChapter,
+}
+
+impl ScreenMode {
+    /// Whether the terminal should be cleared before showing a prompt from `file`, given the `previous` prompt's file
+    /// and whether the prompt starts a new `chapter`.
+    ///
+    /// The first prompt shown is never cleared before, so that nothing printed before the game loop is lost.
+    pub fn clears(&self, previous: Option<&str>, file: &str, chapter: bool) -> bool {
+        match (self, previous) {
+            (_, None) | (ScreenMode::Append, _) => false,
+            (ScreenMode::Clear, _) => true,
+            (ScreenMode::ClearOnChapter, Some(previous)) => chapter || previous != file,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+/// The most detailed kind of record written to the [log file](crate::loading::logger::GameLogger).
+pub enum LogLevel {
+    #[default]
+    Off,
+    /// Crashes and script errors.
+    Error,
+    /// Failed commands and other recoverable problems.
+    Warn,
+    /// Prompt transitions and choices taken.
+    Info,
+    /// Audio events.
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    pub fn filter(&self) -> LevelFilter {
+        match self {
+            Self::Off => LevelFilter::Off,
+            Self::Error => LevelFilter::Error,
+            Self::Warn => LevelFilter::Warn,
+            Self::Info => LevelFilter::Info,
+            Self::Debug => LevelFilter::Debug,
+            Self::Trace => LevelFilter::Trace,
         }
     }
 }
 
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct AutosaveSettings {
+    pub mode: AutosaveMode,
+    #[serde(alias = "max slots")]
+    /// How many of the most recent autosaves to keep.
+    pub slots: usize,
+}
+
+impl Default for AutosaveSettings {
+    fn default() -> Self {
+        Self {
+            mode: AutosaveMode::Off,
+            slots: 3,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+/// Maps logical input actions to the keys or inputs that trigger them.
+///
+/// Choices can always be selected by number, and Ctrl+C and Ctrl+D always quit.
+pub struct KeyBindings {
+    /// A key that stops waiting on sounds early.
+    pub advance: Option<char>,
+    /// An input that goes back a choice like the `.back` command when entered at a choice prompt.
+    pub back: Option<String>,
+    /// A key that quits the game when held with Ctrl.
+    pub quit: Option<char>,
+    /// A key that toggles the [debug overlay](crate::game::overlay::DebugOverlay) when held with Ctrl in debug mode.
+    pub overlay: Option<char>,
+    /// A key that opens the [pause menu](crate::core::pause::PauseMenu) when held with Ctrl.
+    pub pause: Option<char>,
+    #[serde(alias = "fast forward")]
+    /// A key that toggles [fast-forwarding](crate::core::fast_forward::FastForward) through seen prompts when held with Ctrl.
+    pub fast_forward: Option<char>,
+    /// Inputs that select choices by position; the first selects the first choice, and so on.
+    pub select: Vec<String>,
+    /// Gamepad buttons and axes, used when built with the `gamepad` feature.
+    pub gamepad: GamepadBindings,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+/// Maps gamepad buttons and axes to choice prompt actions, by the numbers the joystick driver gives them.
+///
+/// The defaults match common Xbox-style controllers.
+pub struct GamepadBindings {
+    /// A button that submits the selected choice.
+    pub confirm: Option<u8>,
+    /// A button that goes back a choice like the `.back` command.
+    pub back: Option<u8>,
+    /// A button that opens the [pause menu](crate::core::pause::PauseMenu).
+    pub pause: Option<u8>,
+    /// Axes that move the selection between choices, such as a stick and the D-pad.
+    pub vertical: Vec<u8>,
+    /// How far an axis must be pushed, out of 32767, to move the selection.
+    pub threshold: i16,
+}
+
+impl Default for GamepadBindings {
+    fn default() -> Self {
+        Self {
+            confirm: Some(0),
+            back: Some(1),
+            pause: Some(7),
+            vertical: vec![1, 7],
+            threshold: 16384,
+        }
+    }
+}
+
+impl GamepadBindings {
+    fn validate(&self) -> Result<()> {
+        let mut buttons: HashMap<u8, &str> = HashMap::new();
+        for (action, button) in [
+            ("confirm", self.confirm),
+            ("back", self.back),
+            ("pause", self.pause),
+        ] {
+            let Some(button) = button else {
+                continue;
+            };
+            if let Some(other) = buttons.insert(button, action) {
+                return Err(anyhow!(
+                    "`settings.keybindings.gamepad` binds button {button} to both {other} and {action}"
+                ));
+            }
+        }
+        if self.threshold <= 0 {
+            return Err(anyhow!("`settings.keybindings.gamepad.threshold` must be positive"));
+        }
+        Ok(())
+    }
+}
+
+/// An action bound to an input at a choice prompt; see [`KeyBindings::choice_action`].
+pub enum ChoiceAction {
+    Back,
+    /// Selects the choice at a 1-based position.
+    Select(usize),
+}
+
+impl KeyBindings {
+    /// Fills unset bindings from the settings they supersede.
+    fn inherit(&mut self, skip_key: Option<char>, back_input: &Option<String>) {
+        self.advance = self.advance.or(skip_key);
+        if self.back.is_none() {
+            self.back = back_input.clone();
+        }
+    }
+
+    /// Returns the action bound to an input at a choice prompt, if any.
+    pub fn choice_action(&self, input: &str) -> Option<ChoiceAction> {
+        if self.back.as_deref() == Some(input) {
+            return Some(ChoiceAction::Back);
+        }
+        self.select
+            .iter()
+            .position(|select| select == input)
+            .map(|index| ChoiceAction::Select(index + 1))
+    }
+
+    fn validate(&self) -> Result<()> {
+        let mut inputs: HashMap<&str, String> = HashMap::new();
+        let actions = self
+            .back
+            .iter()
+            .map(|back| (back, "back".to_owned()))
+            .chain(
+                self.select
+                    .iter()
+                    .enumerate()
+                    .map(|(index, select)| (select, format!("select {}", index + 1))),
+            );
+        for (input, action) in actions {
+            if input.is_empty() || input.starts_with('.') || input.parse::<usize>().is_ok() {
+                return Err(anyhow!(
+                    "`settings.keybindings` can't bind {action} to an empty input, a command, or a number"
+                ));
+            }
+            if let Some(other) = inputs.insert(input, action.clone()) {
+                return Err(anyhow!(
+                    "`settings.keybindings` binds '{input}' to both {other} and {action}"
+                ));
+            }
+        }
+        let mut ctrl_keys: HashMap<char, &str> = HashMap::from([('c', "quit"), ('d', "quit")]);
+        for (action, key) in [
+            ("quit", self.quit),
+            ("overlay", self.overlay),
+            ("pause", self.pause),
+            ("fast_forward", self.fast_forward),
+        ] {
+            let Some(key) = key else {
+                continue;
+            };
+            if !key.is_ascii_lowercase() || "ijm".contains(key) {
+                return Err(anyhow!(
+                    "`settings.keybindings.{action}` must be a lowercase letter other than i, j, or m"
+                ));
+            }
+            if let Some(other) = ctrl_keys.insert(key, action) {
+                if other != action {
+                    return Err(anyhow!(
+                        "`settings.keybindings` binds Ctrl+{key} to both {other} and {action}"
+                    ));
+                }
+            }
+        }
+        self.gamepad.validate()
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+/// Recall of previous entries at free-text input prompts.
+pub struct InputHistorySettings {
+    #[serde(alias = "max size", alias = "max entries")]
+    /// How many of the most recent entries to keep. Zero disables recall.
+    pub size: usize,
+    #[serde(alias = "save entries")]
+    /// Whether entries are kept in the player's save file across sessions.
+    pub save: bool,
+}
+
+impl Default for InputHistorySettings {
+    fn default() -> Self {
+        Self {
+            size: 20,
+            save: false,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct PlaytimeSettings {
+    #[serde(alias = "active only")]
+    /// Whether to only count time the player spends actively playing.
+    pub active_only: bool,
+    #[serde(alias = "idle limit")]
+    /// The most seconds a single input can add to the playtime when only counting active time.
+    pub idle_limit: u64,
+}
+
+impl Default for PlaytimeSettings {
+    fn default() -> Self {
+        Self {
+            active_only: false,
+            idle_limit: 300,
+        }
+    }
+}
+
+impl PlaytimeSettings {
+    /// Returns how much of an `elapsed` span of time counts towards the playtime.
+    pub fn counted(&self, elapsed: Duration) -> Duration {
+        if self.active_only {
+            elapsed.min(Duration::from_secs(self.idle_limit))
+        } else {
+            elapsed
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+/// Scripts run whenever certain events happen anywhere in the game.
+pub struct HookSettings {
+    #[serde(alias = "on choice")]
+    /// A script to [run](crate::core::scripts::Scripts::run) each time the player makes a choice, before its changes are applied.
+    /// It's passed the path of the prompt and the number of the choice, starting at `1`.
+    pub on_choice: Option<String>,
+    /// Whether errors in hook scripts stop the game rather than only being logged.
+    pub strict: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+/// Lowers the volume of other channels while a priority channel is playing.
+pub struct DuckingSettings {
+    /// The channels to lower.
+    pub channels: HashSet<String>,
+    /// How much to lower the channels by, from `0.0` (not at all) to `1.0` (silence).
+    pub amount: f32,
+}
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// How sound files that can't be read are handled when the game loads.
+pub enum SoundLoading {
+    #[default]
+    /// The game fails to load on the first unreadable sound file.
+    Strict,
+    /// Every unreadable sound file is reported together and dropped, and sound actions playing them do nothing.
+    Lenient,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+/// Properties that [sound actions](crate::core::audio::SoundAction) playing a sound file or sound group use unless they set their own.
+pub struct SoundDefaults {
+    /// The volume of the channel, from `0.0` to `1.0`.
+    pub volume: Option<f32>,
+    /// The playback multiplier of the sound.
+    pub speed: Option<f64>,
+    /// The stereo balance of the channel, from `-1.0` (full left) to `1.0` (full right).
+    pub pan: Option<f32>,
+    /// How long, in milliseconds, to fade the sound in or out over.
+    pub fade: Option<u64>,
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(default, deny_unknown_fields)]
 pub struct TextSettings {
@@ -108,6 +470,14 @@ pub struct TextSettings {
     pub wait: Option<u64>,
     #[serde(alias = "language")]
     lang: Option<String>,
+    #[serde(alias = "auto advance")]
+    pub auto_advance: AutoAdvanceSettings,
+    /// Whether to style text with [markup](crate::core::text::markup::render) and colors.
+    /// Even if set, text is unstyled on terminals that don't support it.
+    pub color: bool,
+    #[serde(alias = "fallback width")]
+    /// The width to [wrap](crate::core::text::typewriter::wrap) text to if the terminal's width can't be detected.
+    pub fallback_width: usize,
 }
 
 impl Default for TextSettings {
@@ -116,6 +486,44 @@ impl Default for TextSettings {
             speed: TextSpeed::Delay(TemplatableValue::value(5)),
             wait: None,
             lang: None,
+            auto_advance: AutoAdvanceSettings::default(),
+            color: true,
+            fallback_width: 80,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+/// How long auto-advance waits after a prompt's text is printed before continuing past its only choice.
+pub struct AutoAdvanceSettings {
+    /// Whether auto-advance is on for players who haven't toggled it themselves.
+    pub enabled: bool,
+    /// The amount of milliseconds to always wait.
+    pub delay: u64,
+    #[serde(alias = "per character")]
+    /// The amount of milliseconds to wait for each character printed.
+    pub per_character: u64,
+}
+
+impl Default for AutoAdvanceSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            delay: 1000,
+            per_character: 25,
+        }
+    }
+}
+
+impl AutoAdvanceSettings {
+    /// The time to wait after `length` characters were printed, scaled by the player's
+    /// [text speed](TextContext::text_speed) like the text itself.
+    pub fn delay(&self, length: usize, context: &TextContext) -> Duration {
+        let delay = Duration::from_millis(self.delay + self.per_character * length as u64);
+        match context.text_speed() {
+            speed if speed > 0.0 => delay.div_f32(speed),
+            _ => delay,
         }
     }
 }
@@ -126,6 +534,16 @@ impl TextSettings {
     pub fn lang(&self) -> String {
         self.lang.clone().unwrap_or(Self::DEFAULT_LANG.to_owned())
     }
+
+    /// The width to wrap text to, which is the terminal's current width if it can be detected.
+    ///
+    /// This is checked each time text is printed, so resizing the terminal affects the next text printed.
+    pub fn wrap_width(&self) -> usize {
+        match crossterm::terminal::size() {
+            Ok((columns, _)) if columns > 0 => columns as usize,
+            _ => self.fallback_width,
+        }
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -155,12 +573,75 @@ impl RichPresenceSettings {
 pub struct Settings {
     #[serde(alias = "save on quit")]
     pub save: bool,
+    /// Whether to write a separate continue save whenever the player quits, which they can pick up from at launch.
+    ///
+    /// Unlike `save`, this never asks for a save file name. Nothing is written if the game crashes,
+    /// and the continue save is removed once the player reaches an ending.
+    pub save_on_quit: bool,
+    #[serde(alias = "save protection")]
+    /// How save files are protected from being edited by hand.
+    pub save_protection: SaveProtection,
     #[serde(alias = "developer mode")]
     pub debug: bool,
+    /// Mod directories to load on top of the game's content, in order; see [`Loader::add_mods`].
+    pub mods: Vec<String>,
+    #[serde(alias = "hot reload")]
+    /// Whether to reload content when its files change, for use during development.
+    pub hot_reload: bool,
+    #[serde(alias = "log level")]
+    pub log_level: LogLevel,
+    #[serde(alias = "script sandbox")]
+    /// Whether scripts are kept from the filesystem and OS; see [`Scripts::load`](crate::core::scripts::Scripts::load).
+    pub script_sandbox: bool,
+    #[serde(alias = "script hooks")]
+    pub hooks: HookSettings,
     #[serde(alias = "sound channels", alias = "audio")]
     pub channels: Option<HashMap<String, bool>>,
+    #[serde(alias = "default channel")]
+    /// The channel used by sound actions that don't specify one. Must be one of the `channels`.
+    pub default_channel: Option<String>,
+    #[serde(alias = "master volume")]
+    pub master_volume: f32,
+    #[serde(alias = "shutdown fade")]
+    /// How many milliseconds all audio fades out over when the game exits. Zero stops it at once.
+    pub shutdown_fade: u64,
+    #[serde(alias = "audio warning")]
+    /// Whether to warn the player once if no sound device can be opened, rather than silently playing without sound.
+    pub audio_warning: bool,
+    pub preload: Vec<String>,
+    #[serde(alias = "skip key")]
+    /// Superseded by [`KeyBindings::advance`], which this is used for if unset.
+    pub skip_key: Option<char>,
+    #[serde(alias = "key bindings", alias = "keys")]
+    pub keybindings: KeyBindings,
+    pub ducking: HashMap<String, DuckingSettings>,
+    #[serde(alias = "sound groups")]
+    pub sound_groups: HashMap<String, Vec<String>>,
+    /// Named lists of sound files or sound groups for [`Playlist`](crate::core::audio::SoundActionMode::Playlist) actions to play in order.
+    pub playlists: HashMap<String, Vec<String>>,
+    #[serde(alias = "sound defaults")]
+    /// Sound files or sound groups mapped to the properties that actions playing them default to.
+    pub sound_defaults: HashMap<String, SoundDefaults>,
+    #[serde(alias = "sound loading")]
+    /// How sound files that can't be read are handled.
+    pub sound_loading: SoundLoading,
     pub history: HistorySettings,
+    #[serde(alias = "input history")]
+    pub input_history: InputHistorySettings,
+    pub autosave: AutosaveSettings,
+    pub playtime: PlaytimeSettings,
     pub text: TextSettings,
+    /// Whether players can click a choice to select it on terminals that support mouse reporting.
+    /// Choices are then read key by key, without line editing or history recall.
+    pub mouse: bool,
+    /// Whether output is kept screen-reader-friendly for players who haven't toggled it themselves.
+    ///
+    /// Text is revealed at once without wrapping or styling, countdowns aren't redrawn in place,
+    /// and sounds are announced with their captions.
+    pub accessibility: bool,
+    #[serde(alias = "screen mode")]
+    /// Whether the terminal is cleared between prompts. The terminal is never cleared in accessibility mode.
+    pub screen_mode: ScreenMode,
     #[serde(alias = "discord rich presence")]
     drp: RichPresenceSettings,
 }
@@ -169,27 +650,61 @@ impl Default for Settings {
     fn default() -> Self {
         Self {
             save: true,
+            save_on_quit: false,
+            save_protection: SaveProtection::default(),
             debug: false,
+            mods: Vec::new(),
+            hot_reload: false,
+            log_level: LogLevel::default(),
+            script_sandbox: true,
+            hooks: HookSettings::default(),
             channels: None,
+            default_channel: None,
+            master_volume: 1.0,
+            shutdown_fade: 300,
+            audio_warning: false,
+            preload: Vec::new(),
+            skip_key: None,
+            keybindings: KeyBindings::default(),
+            ducking: HashMap::new(),
+            sound_groups: HashMap::new(),
+            playlists: HashMap::new(),
+            sound_defaults: HashMap::new(),
+            sound_loading: SoundLoading::default(),
             history: HistorySettings::default(),
+            input_history: InputHistorySettings::default(),
+            autosave: AutosaveSettings::default(),
+            playtime: PlaytimeSettings::default(),
             text: TextSettings::default(),
+            mouse: false,
+            accessibility: false,
+            screen_mode: ScreenMode::default(),
             drp: RichPresenceSettings::default(),
         }
     }
 }
 
 impl Settings {
-    pub fn enabled_audio_channels(&self) -> HashSet<String> {
+    /// Returns the names of the audio channels whose manifest entry is `enabled`.
+    fn audio_channels_where(&self, enabled: bool) -> HashSet<String> {
         self.channels
             .as_ref()
             .map(|map| {
                 map.iter()
-                    .filter(|(_, &enabled)| enabled)
+                    .filter(|(_, &value)| value == enabled)
                     .map(|(key, _)| key.clone())
                     .collect()
             })
             .unwrap_or(HashSet::new())
     }
+
+    pub fn enabled_audio_channels(&self) -> HashSet<String> {
+        self.audio_channels_where(true)
+    }
+
+    pub fn disabled_audio_channels(&self) -> HashSet<String> {
+        self.audio_channels_where(false)
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -198,16 +713,28 @@ pub struct EntrypointSoundAction {
     channel: String,
     seek: Option<u64>,
     speed: Option<f64>,
+    volume: Option<f32>,
+    pan: Option<f32>,
+    fade: Option<u64>,
 }
 
 impl Into<SoundAction> for EntrypointSoundAction {
     fn into(self) -> SoundAction {
         SoundAction {
             name: Some(self.name.into()),
-            channel: self.channel.into(),
+            channel: Some(self.channel.into()),
             mode: TemplatableValue::value(SoundActionMode::default()),
             seek: self.seek.map(TemplatableValue::value),
             speed: self.speed.map(TemplatableValue::value),
+            preserve_pitch: None,
+            volume: self.volume.map(TemplatableValue::value),
+            pan: self.pan.map(TemplatableValue::value),
+            fade: self.fade.map(TemplatableValue::value),
+            timeout: None,
+            cooldown: None,
+            caption: None,
+            tracks: None,
+            shuffle: None,
         }
     }
 }
@@ -219,6 +746,7 @@ pub struct Entrypoint {
     pub background: Option<TextLines>,
     pub notes: Option<Notes>,
     pub variables: Option<Variables>,
+    pub inventory: Option<Inventory>,
     #[serde(rename = "info")]
     pub info_pages: Option<UnlockedInfoPages>,
     pub log: Option<Vec<String>>,
@@ -250,23 +778,104 @@ pub struct Manifest {
     pub settings: Settings,
     #[serde(alias = "entrypoint")]
     pub entry: Entrypoint,
+    #[serde(default)]
+    pub achievements: Achievements,
+    #[serde(default)]
+    /// Named pieces of text that prompts can reuse with [snippet references](TemplatableString::fill_snippets).
+    pub snippets: HashMap<String, String>,
+    #[serde(default)]
+    /// Files or directories of [`Definitions`], relative to the game directory, to merge into the manifest.
+    include: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+/// Definitions that can be split out of the manifest into the files it [includes](Manifest::include_definitions).
+pub struct Definitions {
+    pub achievements: Achievements,
 }
 
 impl Manifest {
     pub const FILE: &'static str = "nage.yml";
 
     pub fn load(loader: &Loader) -> Result<Self> {
-        let config: Self = loader.load(Self::FILE, true)?;
+        let mut config: Self = loader.load(Self::FILE, true)?;
+        config
+            .include_definitions(loader)
+            .with_context(|| "Failed to include manifest definitions")?;
+        let settings = &mut config.settings;
+        settings
+            .keybindings
+            .inherit(settings.skip_key, &settings.history.back_input);
         config
             .validate()
             .with_context(|| "Failed to validate manifest")?;
         Ok(config)
     }
 
+    /// Merges the [`Definitions`] in every included file into the manifest, in order.
+    ///
+    /// An ID defined in more than one file is an error naming both files.
+    fn include_definitions(&mut self, loader: &Loader) -> Result<()> {
+        let mut sources: HashMap<String, Utf8PathBuf> = self
+            .achievements
+            .iter()
+            .map(|achievement| (achievement.id.clone(), Utf8PathBuf::from(Self::FILE)))
+            .collect();
+        for include in &self.include {
+            for (file, definitions) in loader.load_definitions::<_, Definitions>(include)? {
+                for achievement in definitions.achievements {
+                    if let Some(other) = sources.insert(achievement.id.clone(), file.clone()) {
+                        return Err(anyhow!(
+                            "Achievement '{}' is defined in both {other} and {file}",
+                            achievement.id
+                        ));
+                    }
+                    self.achievements.push(achievement);
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn validate(&self) -> Result<()> {
         if self.settings.history.size == 0 {
             return Err(anyhow!("`settings.history.size` must be non-zero"));
         }
+        self.settings.keybindings.validate()?;
+        if self.settings.autosave.slots == 0 {
+            return Err(anyhow!("`settings.autosave.slots` must be non-zero"));
+        }
+        if self.settings.playtime.idle_limit == 0 {
+            return Err(anyhow!("`settings.playtime.idle_limit` must be non-zero"));
+        }
+        if !(0.0..=1.0).contains(&self.settings.master_volume) {
+            return Err(anyhow!("`settings.master_volume` must be between 0.0 and 1.0"));
+        }
+        if let Some(channel) = &self.settings.default_channel {
+            if !self
+                .settings
+                .channels
+                .as_ref()
+                .is_some_and(|channels| channels.contains_key(channel))
+            {
+                return Err(anyhow!(
+                    "`settings.default_channel` '{channel}' isn't one of the `settings.channels`"
+                ));
+            }
+        }
+        for (channel, ducking) in &self.settings.ducking {
+            if !(0.0..=1.0).contains(&ducking.amount) {
+                return Err(anyhow!(
+                    "`settings.ducking.{channel}.amount` must be between 0.0 and 1.0"
+                ));
+            }
+        }
+        Achievement::validate_all(&self.achievements)?;
+        for (name, snippet) in &self.snippets {
+            TemplatableString::fill_snippets(snippet, &self.snippets)
+                .with_context(|| anyhow!("Invalid snippet '{name}'"))?;
+        }
         let nage_version = Version::from_str(NAGE_VERSION)?;
         self.dependencies.check(nage_version)?;
         Ok(())
@@ -1,7 +1,6 @@
 use std::fmt::Display;
 
-use anyhow::Result;
-use result::OptionResultExt;
+use anyhow::{anyhow, Result};
 use serde::{
     de::{value::MapAccessDeserializer, Visitor},
     Deserialize, Deserializer, Serialize,
@@ -9,7 +8,7 @@ use serde::{
 
 use crate::core::text::templating::TemplatableString;
 
-use super::context::TextContext;
+use super::{context::TextContext, prompt::Prompts};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PathContents {
@@ -38,9 +37,20 @@ impl<'de> Visitor<'de> for PathVisitor {
         E: serde::de::Error,
     {
         // rsplit: some/file/prompt -> some/file + prompt
-        let result = match v.rsplit_once('/') {
+        // Namespaced: some.file.prompt -> some/file + prompt, unless templated
+        let namespaced = match TemplatableString::is_str_templatable(v) {
+            true => None,
+            false => v
+                .rsplit_once('.')
+                .map(|(namespace, prompt)| (namespace.replace('.', "/"), prompt)),
+        };
+        let split = v
+            .rsplit_once('/')
+            .map(|(file, prompt)| (file.to_owned(), prompt))
+            .or(namespaced);
+        let result = match split {
             Some((file, prompt)) => PathContents {
-                file: Some(file.to_owned().into()),
+                file: Some(file.into()),
                 prompt: prompt.to_owned().into(),
             },
             None => PathContents {
@@ -96,55 +106,104 @@ impl Path {
             && self.prompt().is_validatable()
     }
 
+    /// Finds the file that an unqualified prompt name refers to from within `current_file`.
+    ///
+    /// The current file is checked first. Otherwise, the prompt must exist in exactly one other file;
+    /// if it exists in none or in several, the error names both the referring file and the prompt.
+    pub fn resolve_file(prompts: &Prompts, current_file: &str, prompt: &str) -> Result<String> {
+        if prompts
+            .get(current_file)
+            .is_some_and(|file| file.contains_key(prompt))
+        {
+            return Ok(current_file.to_owned());
+        }
+        let files: Vec<&String> = prompts
+            .iter()
+            .filter(|(_, file)| file.contains_key(prompt))
+            .map(|(name, _)| name)
+            .collect();
+        match files.as_slice() {
+            [file] => Ok((*file).to_owned()),
+            [] => Err(anyhow!(
+                "Prompt '{prompt}' referenced from '{current_file}' doesn't exist in any file"
+            )),
+            several => {
+                let names: Vec<String> =
+                    several.iter().map(|file| file.replace('/', ".")).collect();
+                Err(anyhow!(
+                    "Prompt '{prompt}' referenced from '{current_file}' is ambiguous between {}; qualify it like '{}.{prompt}'",
+                    names.join(", "),
+                    names[0]
+                ))
+            }
+        }
+    }
+
+    /// Fills both components of this path, [resolving](Self::resolve_file) the file if it's unqualified.
     pub fn fill(&self, current: &PathData, text_context: &TextContext) -> Result<PathData> {
-        let file = self
-            .file()
-            .as_ref()
-            .map(|t| t.fill(text_context))
-            .invert()?
-            .unwrap_or(current.file.clone());
-        Ok(PathData {
-            file,
-            prompt: self.prompt().fill(text_context)?,
-        })
+        let prompt = self.prompt().fill(text_context)?;
+        let file = match self.file() {
+            Some(file) => file.fill(text_context)?,
+            None => Self::resolve_file(&text_context.resources().prompts, &current.file, &prompt)?,
+        };
+        Ok(PathData { file, prompt })
     }
 
-    pub fn static_file(&self, current_file: &str) -> Option<String> {
+    /// The file this points to from within `current_file`, if it [is validatable](Self::is_validatable).
+    pub fn static_file(&self, current_file: &str, prompts: &Prompts) -> Option<Result<String>> {
         if !self.is_validatable() {
             return None;
         }
-        let result = self
-            .file()
-            .as_ref()
-            .and_then(|t| t.content())
-            .unwrap_or(current_file);
-        Some(result.to_owned())
-    }
-
-    fn static_data(&self, current_file: &str) -> Option<PathData> {
-        self.static_file(&current_file).and_then(|file| {
-            let path = PathData {
-                file,
-                prompt: self.prompt().content()?.to_owned(),
-            };
-            Some(path)
-        })
+        let result = match self.file().as_ref().and_then(|t| t.content()) {
+            Some(file) => Ok(file.to_owned()),
+            None => Self::resolve_file(prompts, current_file, self.prompt().content()?),
+        };
+        Some(result)
     }
 
-    pub fn matches(&self, current_file: &str, other: &PathData) -> bool {
-        match self.static_data(current_file) {
-            Some(data) => data.prompt == other.prompt && data.file == other.file,
-            None => false,
+    /// The full path this points to, if it [is validatable](Self::is_validatable).
+    pub fn static_data(&self, current_file: &str, prompts: &Prompts) -> Option<Result<PathData>> {
+        let file = self.static_file(current_file, prompts)?;
+        let prompt = self.prompt().content()?.to_owned();
+        Some(file.map(|file| PathData { file, prompt }))
+    }
+
+    pub fn matches(&self, current_file: &str, other: &PathData, prompts: &Prompts) -> bool {
+        match self.static_data(current_file, prompts) {
+            Some(Ok(data)) => data.prompt == other.prompt && data.file == other.file,
+            _ => false,
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+/// A [`Path`] that is picked amongst others at random, with a greater `weight` making it more likely.
+pub struct WeightedPath {
+    pub path: Path,
+    /// How likely this path is to be picked relative to the others. Must be positive.
+    pub weight: u32,
+}
+
+/// A list of [`WeightedPath`]s to pick from.
+pub type WeightedPaths = Vec<WeightedPath>;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct PathData {
     pub file: String,
     pub prompt: String,
 }
 
+impl PathData {
+    /// Parses a path written as `file/prompt`, or as just `prompt` within `current_file`.
+    pub fn parse(path: &str, current_file: &str) -> Self {
+        match path.rsplit_once('/') {
+            Some((file, prompt)) => PathLookup::new(file, prompt).into(),
+            None => PathLookup::new(current_file, path).into(),
+        }
+    }
+}
+
 impl Display for PathData {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}/{}", self.file, self.prompt)
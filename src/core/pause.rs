@@ -0,0 +1,121 @@
+use std::{
+    cell::{Cell, RefCell},
+    time::Duration,
+};
+
+use anyhow::Result;
+use crossterm::{
+    event::{KeyCode, KeyEvent, KeyModifiers},
+    terminal,
+};
+use strum::{Display, EnumIter, IntoEnumIterator};
+
+use super::manifest::KeyBindings;
+
+#[derive(Display, EnumIter, Debug, PartialEq, Clone, Copy)]
+/// An option picked from the [`PauseMenu`].
+pub enum PauseAction {
+    Resume,
+    Save,
+    Settings,
+    Quit,
+}
+
+#[derive(Debug)]
+/// The menu shown when the player presses the [pause key](KeyBindings::pause).
+///
+/// The game can be paused while it's blocked on printing text or [awaiting](crate::core::audio::SoundActionMode::Await)
+/// sounds as well as at a prompt. Since only the game loop can save or quit, any action other than resuming that's picked
+/// while the game is blocked is kept [pending](Self::take_pending) until the game reaches the next prompt.
+pub struct PauseMenu {
+    key: Option<char>,
+    pending: Cell<Option<PauseAction>>,
+    /// The player's current [chapter](crate::core::prompt::Chapter), shown in the menu's title.
+    chapter: RefCell<Option<String>>,
+    /// The time spent in the menu and the actions picked from it since it was last [taken](Self::take_paused).
+    paused: Cell<Duration>,
+}
+
+impl PauseMenu {
+    pub fn new(bindings: &KeyBindings) -> Self {
+        Self {
+            key: bindings.pause,
+            pending: Cell::new(None),
+            chapter: RefCell::new(None),
+            paused: Cell::new(Duration::ZERO),
+        }
+    }
+
+    /// Whether a key press is the pause key held with Ctrl.
+    pub fn is_pause_key(&self, event: &KeyEvent) -> bool {
+        match (self.key, event.code) {
+            (Some(key), KeyCode::Char(c)) => {
+                c == key && event.modifiers.contains(KeyModifiers::CONTROL)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the pause key is bound at all.
+    pub fn is_bound(&self) -> bool {
+        self.key.is_some()
+    }
+
+    /// Shows the menu and returns the picked action.
+    ///
+    /// If the terminal is in raw mode, it's left for as long as the menu is shown.
+    pub fn show(&self) -> Result<PauseAction> {
+        let raw = terminal::is_raw_mode_enabled()?;
+        if raw {
+            terminal::disable_raw_mode()?;
+        }
+        println!();
+        let title = match self.chapter.borrow().as_deref() {
+            Some(chapter) => format!("Paused ({chapter})"),
+            None => "Paused".to_owned(),
+        };
+        let question = requestty::Question::select(title)
+            .choices(PauseAction::iter().map(|action| action.to_string()))
+            .build();
+        let answer = requestty::prompt_one(question);
+        if raw {
+            terminal::enable_raw_mode()?;
+        }
+        let index = answer?.as_list_item().unwrap().index;
+        Ok(PauseAction::iter()
+            .nth(index)
+            .unwrap_or(PauseAction::Resume))
+    }
+
+    /// Sets the chapter shown in the menu's title.
+    pub fn set_chapter(&self, chapter: Option<String>) {
+        *self.chapter.borrow_mut() = chapter;
+    }
+
+    /// Keeps an action picked while the game was blocked until the game loop can carry it out.
+    pub fn defer(&self, action: PauseAction) {
+        if action != PauseAction::Resume {
+            self.pending.set(Some(action));
+        }
+    }
+
+    /// Whether the player has picked to quit while the game was blocked.
+    pub fn is_quitting(&self) -> bool {
+        self.pending.get() == Some(PauseAction::Quit)
+    }
+
+    /// Removes and returns the action picked while the game was blocked, if any.
+    pub fn take_pending(&self) -> Option<PauseAction> {
+        self.pending.take()
+    }
+
+    /// Records time spent paused, which doesn't count towards the player's playtime.
+    pub fn add_paused(&self, elapsed: Duration) {
+        self.paused.set(self.paused.get() + elapsed);
+    }
+
+    /// Removes and returns the time spent paused since this was last called.
+    pub fn take_paused(&self) -> Duration {
+        self.paused.take()
+    }
+}
@@ -1,5 +1,6 @@
 use std::{
     collections::{HashMap, HashSet, VecDeque},
+    time::Duration,
     vec,
 };
 
@@ -8,23 +9,33 @@ use result::OptionResultExt;
 use serde::{Deserialize, Serialize};
 use unicode_truncate::UnicodeTruncateStr;
 
-use crate::text_context;
+use crate::{
+    loading::{loader::Loader, migrations},
+    text_context,
+};
 
 use super::{
+    audio::ChannelSounds,
     choice::Choice,
     context::{StaticContext, TextContext},
     discord::RichPresence,
     manifest::Manifest,
     path::PathData,
-    prompt::PromptModel,
+    prompt::{Prompt, PromptModel},
     state::{
+        achievements::{Achievement, UnlockedAchievements},
         info::UnlockedInfoPages,
+        inventory::{self, CostEntries, Inventory},
         notes::{NoteEntries, Notes},
+        preferences::Preferences,
+        random::SeededRandom,
+        store::{Store, StoreValue},
         variables::{NamedVariableEntry, VariableEntries, Variables},
     },
+    text::display::format_duration,
 };
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 /// A reversible recording of a prompt jump.
 pub struct HistoryEntry {
     /// The prompt path the player jumped to.
@@ -39,6 +50,18 @@ pub struct HistoryEntry {
     pub notes: Option<NoteEntries>,
     /// The variables applied during this entry, if any.
     pub variables: Option<VariableEntries>,
+    #[serde(default)]
+    /// The items paid for this entry's choice, if any.
+    pub cost: Option<CostEntries>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// The items gained during this entry, such as from a [selection](crate::core::state::selection::Selection), if any.
+    pub gained: Option<CostEntries>,
+    #[serde(default)]
+    /// The [random generator state](SeededRandom::state) from right before this entry's choice was made, if recorded.
+    pub random: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// The number of the [one-time](Choice::single_use) choice used in the previous entry's prompt to make this entry, if any.
+    pub used: Option<usize>,
     /// Whether a log entry was gained during this entry.
     pub log: bool,
 }
@@ -53,30 +76,115 @@ impl HistoryEntry {
             redirect: false,
             notes: None,
             variables: None,
+            cost: None,
+            gained: None,
+            random: None,
+            used: None,
             log: false,
         }
     }
 }
 
+#[derive(Debug)]
+/// A snapshot of a player's progress through the story, which they can [return to](Player::return_to) later in the session.
+///
+/// Only the story state is kept, so returning discards the notes, variables, items, info pages, log entries,
+/// visited prompts, and history gained since the bookmark was made. Playtime, achievements, language, sound channels, and preferences
+/// aren't part of the story and are kept as they are.
+pub struct Bookmark {
+    /// The prompt the player was at.
+    pub path: PathData,
+    /// The title of the chapter the player was in, if any.
+    pub chapter: Option<String>,
+    notes: Notes,
+    variables: Variables,
+    inventory: Inventory,
+    info_pages: UnlockedInfoPages,
+    log: Vec<String>,
+    history: VecDeque<HistoryEntry>,
+    random: SeededRandom,
+    store: Store,
+    used_choices: HashMap<String, HashSet<usize>>,
+    visited: HashSet<String>,
+}
+
+impl Bookmark {
+    /// The label to display for this bookmark when choosing between several.
+    pub fn label(&self) -> String {
+        match &self.chapter {
+            Some(chapter) => format!("{} ({chapter})", self.path),
+            None => self.path.to_string(),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 /// A player data tracker.
 pub struct Player {
+    #[serde(default)]
+    /// The [save format version](migrations::CURRENT) this player was saved with.
+    pub version: u32,
     /// Whether the player has started playing the game.
     pub began: bool,
     /// The player's display language.
     pub lang: String,
     /// The player's enabled sound channels.
     pub channels: HashSet<String>,
+    #[serde(default)]
+    /// The sound channels the player has explicitly disabled.
+    pub disabled_channels: HashSet<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    /// The sounds playing on each channel when the player was last [recorded](crate::core::resources::Resources::record_audio),
+    /// which are resumed when the player is loaded.
+    pub sounds: ChannelSounds,
     /// The player's current notes.
     pub notes: Notes,
     /// The player's current variables.
     pub variables: Variables,
+    #[serde(default)]
+    /// The player's current items and currencies.
+    pub inventory: Inventory,
     /// The player's current unlocked info pages.
     pub info_pages: UnlockedInfoPages,
     /// The player's current log entries.
     pub log: Vec<String>,
     /// Recordings of each prompt jump and their associated value changes.
     pub history: VecDeque<HistoryEntry>,
+    #[serde(default)]
+    /// The player's random number generator.
+    pub random: SeededRandom,
+    #[serde(default)]
+    /// The player's total playtime, in seconds.
+    pub playtime: u64,
+    #[serde(default)]
+    /// How many choices the player has made, not counting [redirects](PromptModel::Redirect).
+    /// Going back doesn't take choices off the count.
+    pub choices_made: u64,
+    #[serde(default)]
+    /// The IDs of the achievements the player has unlocked.
+    pub achievements: UnlockedAchievements,
+    #[serde(default)]
+    /// The player's flags, counters, and strings set by scripts.
+    pub store: Store,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    /// The numbers of the [one-time](Choice::single_use) choices the player has used, keyed by prompt path.
+    pub used_choices: HashMap<String, HashSet<usize>>,
+    #[serde(default, skip_serializing_if = "HashSet::is_empty")]
+    /// The paths of the prompts the player has visited. A prompt only counts as visited once the player moves on from it,
+    /// so it reads as unvisited while it's shown for the first time.
+    pub visited: HashSet<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// The player's recent free-text inputs, if the manifest keeps them across sessions.
+    pub input_history: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// The title of the last [chapter](crate::core::prompt::Chapter) the player started, if any.
+    pub chapter: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// The path of the last [checkpoint](crate::core::prompt::Prompt::checkpoint) prompt the player entered, if any.
+    pub checkpoint: Option<String>,
+    #[serde(default, skip_serializing_if = "Preferences::is_default")]
+    /// The settings the player has changed from within the game.
+    pub preferences: Preferences,
 }
 
 impl Player {
@@ -84,14 +192,142 @@ impl Player {
     pub fn new(config: &Manifest) -> Self {
         let entry = HistoryEntry::new(&config.entry.path);
         Self {
+            version: migrations::CURRENT,
             began: false,
             lang: config.settings.text.lang(),
             channels: config.settings.enabled_audio_channels(),
+            disabled_channels: config.settings.disabled_audio_channels(),
+            sounds: ChannelSounds::new(),
             notes: config.entry.notes.clone().unwrap_or(HashSet::new()),
             variables: config.entry.variables.clone().unwrap_or(HashMap::new()),
+            inventory: config.entry.inventory.clone().unwrap_or_default(),
             info_pages: config.entry.info_pages.clone().unwrap_or(Vec::new()),
             log: config.entry.log.clone().unwrap_or(Vec::new()),
             history: VecDeque::from(vec![entry]),
+            random: SeededRandom::default(),
+            playtime: 0,
+            choices_made: 0,
+            achievements: HashSet::new(),
+            store: Store::new(),
+            used_choices: HashMap::new(),
+            visited: HashSet::new(),
+            input_history: Vec::new(),
+            chapter: None,
+            checkpoint: None,
+            preferences: Preferences::default(),
+        }
+    }
+
+    /// Parses a player from the content of a save file, [migrating](migrations::migrate) it from an older
+    /// save format first if needed.
+    pub fn load(content: String) -> Result<Self> {
+        let mut save: serde_yaml::Value = Loader::parse(content.clone())?;
+        if !migrations::migrate(&mut save)? {
+            return Loader::parse(content);
+        }
+        Ok(serde_yaml::from_value(save)?)
+    }
+
+    /// Sets the [`Store`] value for `name`, returning the previous value, if any.
+    pub fn set_var(&mut self, name: String, value: StoreValue) -> Option<StoreValue> {
+        self.store.insert(name, value)
+    }
+
+    /// Applies the changes requested by scripts since this was last called.
+    ///
+    /// [`Store`] values are set first, then sound channels are turned on or off, then achievements are [unlocked](Self::unlock_achievements).
+    pub fn apply_script_changes(
+        &mut self,
+        stc: &StaticContext,
+        text_context: &TextContext,
+    ) -> Result<()> {
+        for (name, value) in stc.resources.scripts.take_store_writes()? {
+            self.set_var(name, value);
+        }
+        if let Some(state) = stc.resources.scripts.take_random_state()? {
+            self.random.restore(state);
+        }
+        let toggles = stc.resources.scripts.take_channel_toggles()?;
+        if let Some(audio) = &stc.resources.audio {
+            let mut audio = audio.borrow_mut();
+            for (channel, enabled) in toggles {
+                audio.toggle_paused(self, &channel, enabled)?;
+            }
+        }
+        self.unlock_achievements(stc, text_context)
+    }
+
+    /// Adds a [`HistoryEntry`], dropping the oldest entry if the history is full.
+    ///
+    /// The prompt the player is moving on from is marked as [visited](Self::visited).
+    fn push_history(&mut self, entry: HistoryEntry, config: &Manifest) {
+        if let Some(latest) = self.history.back() {
+            self.visited.insert(latest.path.to_string());
+        }
+        self.history.push_back(entry);
+        if self.history.len() > config.settings.history.size {
+            self.history.pop_front();
+        }
+    }
+
+    /// Replaces the prompt of the latest history entry, keeping the changes it recorded,
+    /// so that the player moves on as though the entry had jumped to `path` all along.
+    ///
+    /// This is used by [prompt hooks](crate::core::prompt::Prompt::on_enter) that jump elsewhere.
+    pub fn redirect(&mut self, path: &PathData) -> Result<()> {
+        self.history
+            .back_mut()
+            .ok_or(anyhow!("History empty"))?
+            .path = path.clone();
+        Ok(())
+    }
+
+    /// Moves the player directly to a prompt, bypassing the choices and requirements that would lead there.
+    ///
+    /// This is meant for debugging, [idle handlers](crate::core::prompt::IdleHandler), and leaving prompts
+    /// whose [one-time](Choice::single_use) choices have all been used;
+    /// the resulting history entry has no changes to reverse.
+    pub fn jump(&mut self, path: &PathData, config: &Manifest) {
+        self.push_history(HistoryEntry::new(path), config);
+    }
+
+    /// Unlocks any achievements requested by scripts, notifying the player of each newly unlocked one.
+    ///
+    /// Unlocking an achievement the player already has does nothing.
+    fn unlock_achievements(
+        &mut self,
+        stc: &StaticContext,
+        text_context: &TextContext,
+    ) -> Result<()> {
+        for id in stc.resources.scripts.take_unlocks()? {
+            let achievement = Achievement::get(&stc.config.achievements, &id)?;
+            if self.achievements.insert(id) {
+                achievement.notify(self, stc, text_context)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the part of an `elapsed` span of time that counts towards the playtime based on the [`Manifest`].
+    pub fn add_playtime(&mut self, elapsed: Duration, config: &Manifest) {
+        self.playtime += config.settings.playtime.counted(elapsed).as_secs();
+    }
+
+    /// Formats the total playtime for display, such as `Playtime: 2h 13m`.
+    pub fn playtime_display(&self) -> String {
+        format!("Playtime: {}", format_duration(Duration::from_secs(self.playtime)))
+    }
+
+    /// Enables any audio channels in the [`Manifest`] that this player has neither enabled nor disabled.
+    ///
+    /// This accounts for channels added to the manifest since the player was last saved.
+    pub fn sync_channels(&mut self, config: &Manifest) {
+        if let Some(channels) = &config.settings.channels {
+            for channel in channels.keys() {
+                if !self.channels.contains(channel) && !self.disabled_channels.contains(channel) {
+                    self.channels.insert(channel.clone());
+                }
+            }
         }
     }
 
@@ -109,6 +345,41 @@ impl Player {
         Ok(())
     }
 
+    /// Takes a [`Bookmark`] of the player's current story state.
+    pub fn bookmark(&self) -> Result<Bookmark> {
+        Ok(Bookmark {
+            path: self.latest_entry()?.path.clone(),
+            chapter: self.chapter.clone(),
+            notes: self.notes.clone(),
+            variables: self.variables.clone(),
+            inventory: self.inventory.clone(),
+            info_pages: self.info_pages.clone(),
+            log: self.log.clone(),
+            history: self.history.clone(),
+            random: self.random.clone(),
+            store: self.store.clone(),
+            used_choices: self.used_choices.clone(),
+            visited: self.visited.clone(),
+        })
+    }
+
+    /// Restores the story state recorded by a [`Bookmark`], discarding any changes made since.
+    ///
+    /// The bookmark is left as it is, so that the player can return to it again.
+    pub fn return_to(&mut self, bookmark: &Bookmark) {
+        self.chapter = bookmark.chapter.clone();
+        self.notes = bookmark.notes.clone();
+        self.variables = bookmark.variables.clone();
+        self.inventory = bookmark.inventory.clone();
+        self.info_pages = bookmark.info_pages.clone();
+        self.log = bookmark.log.clone();
+        self.history = bookmark.history.clone();
+        self.random = bookmark.random.clone();
+        self.store = bookmark.store.clone();
+        self.used_choices = bookmark.used_choices.clone();
+        self.visited = bookmark.visited.clone();
+    }
+
     /// Returns the latest history entry, if any.
     pub fn latest_entry(&self) -> Result<&HistoryEntry> {
         self.history.back().ok_or(anyhow!("History empty"))
@@ -140,9 +411,24 @@ impl Player {
                     };
                 }
             }
+            if let Some(cost) = &latest.cost {
+                inventory::apply_cost(&mut self.inventory, cost, true);
+            }
+            if let Some(gained) = &latest.gained {
+                inventory::apply_cost(&mut self.inventory, gained, false);
+            }
+            if let Some(state) = latest.random {
+                self.random.restore(state);
+            }
             if latest.log {
                 self.log.pop();
             }
+            if let Some(number) = latest.used {
+                let path = self.latest_entry()?.path.to_string();
+                if let Some(used) = self.used_choices.get_mut(&path) {
+                    used.remove(&number);
+                }
+            }
             if !latest.redirect {
                 break;
             }
@@ -150,6 +436,19 @@ impl Player {
         Ok(())
     }
 
+    /// The numbers of the [one-time](Choice::single_use) choices the player has used in a prompt.
+    pub fn used_choices_at(&self, path: &PathData) -> HashSet<usize> {
+        self.used_choices
+            .get(&path.to_string())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Makes every [one-time](Choice::single_use) choice the player has used in a prompt usable again.
+    pub fn reset_used_choices(&mut self, path: &PathData) {
+        self.used_choices.remove(&path.to_string());
+    }
+
     /// Whether a specified info page ID has already been unlocked.
     fn is_page_unlocked(&self, page: &str) -> bool {
         for unlocked in &self.info_pages {
@@ -165,6 +464,7 @@ impl Player {
     /// The following data is applied:
     /// - `notes` actions
     /// - `variables` map
+    /// - `cost` deductions
     /// - `info` unlocks
     ///
     /// The applied data is sensitive and relies on the previous unaltered state.
@@ -188,6 +488,9 @@ impl Player {
                 .collect();
             self.variables.extend(values);
         }
+        if let Some(cost) = &entry.cost {
+            inventory::apply_cost(&mut self.inventory, cost, false);
+        }
         // Info pages are not stored in history entries, so we can fill the name here
         if let Some(pages) = &choice.info_pages {
             for page in pages {
@@ -209,16 +512,57 @@ impl Player {
         stc: &StaticContext,
         text_context: &TextContext,
     ) -> Result<()> {
+        if let Some(reason) = choice.shortfall(text_context)? {
+            return Err(anyhow!("Can't make this choice; it {reason}"));
+        }
         let latest = self.latest_entry()?;
-        if let Some(result) =
-            choice.to_history_entry(&latest, input, &self.variables, model, once, stc, text_context)
-        {
-            let entry = result?;
-            self.apply_entry(&entry, choice, text_context)?;
-            self.history.push_back(entry);
-            if self.history.len() > stc.config.settings.history.size {
-                self.history.pop_front();
+        let random = self.random.state();
+        let number = Prompt::get(&stc.resources.prompts, &latest.path)?
+            .choices
+            .iter()
+            .position(|other| std::ptr::eq(other, choice))
+            .map(|index| index + 1);
+        let hooks = &stc.config.settings.hooks;
+        if let (Some(hook), Some(number)) = (&hooks.on_choice, number) {
+            let result = stc.resources.scripts.run::<_, ()>(
+                hook,
+                (latest.path.to_string(), number),
+                text_context,
+            );
+            // The script has already logged the error
+            if hooks.strict {
+                result?;
+            }
+        }
+        let used = number
+            .filter(|_| choice.single_use)
+            .map(|number| (latest.path.to_string(), number));
+        // Weighted jumps continue from wherever the hook left the generator, drawing from the context's,
+        // so it's brought up to date with the player's and back
+        let drawn = stc.resources.scripts.take_random_state()?.unwrap_or(random);
+        text_context.random().restore(drawn);
+        let entry = choice.to_history_entry(
+            &latest,
+            input,
+            &self.variables,
+            model,
+            once,
+            stc,
+            text_context,
+        );
+        self.random.restore(text_context.random_state());
+        if let Some(result) = entry {
+            let mut entry = result?;
+            entry.random = Some(random);
+            if let Some((path, number)) = used {
+                self.used_choices.entry(path).or_default().insert(number);
+                entry.used = Some(number);
             }
+            self.apply_entry(&entry, choice, text_context)?;
+            self.push_history(entry, stc.config);
+        }
+        if !matches!(model, PromptModel::Redirect(_)) {
+            self.choices_made += 1;
         }
         if let Some(sounds) = &choice.sounds {
             stc.resources.submit_audio(&self, sounds, text_context)?;
@@ -226,6 +570,17 @@ impl Player {
         Ok(())
     }
 
+    /// Adds some items to the inventory, recording them in the latest history entry so that they're taken back if it's reversed.
+    pub fn gain_items(&mut self, items: CostEntries) {
+        inventory::apply_cost(&mut self.inventory, &items, true);
+        if let Some(entry) = self.history.back_mut() {
+            let gained = entry.gained.get_or_insert_with(CostEntries::new);
+            for (item, amount) in items {
+                *gained.entry(item).or_default() += amount;
+            }
+        }
+    }
+
     pub fn after_choice(
         &mut self,
         choice: &Choice,
@@ -289,3 +644,65 @@ impl Player {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::core::resources::Resources;
+
+    fn manifest(channels: &str) -> Manifest {
+        Resources::test_manifest("test", &format!("channels: {{ {channels} }}"))
+    }
+
+    fn names(channels: &HashSet<String>) -> Vec<&str> {
+        channels.iter().map(String::as_str).sorted().collect()
+    }
+
+    #[test]
+    fn starts_with_the_manifest_channels() {
+        let player = Player::new(&manifest("music: true, sfx: true, ambience: false"));
+        assert_eq!(names(&player.channels), ["music", "sfx"]);
+        assert_eq!(names(&player.disabled_channels), ["ambience"]);
+    }
+
+    #[test]
+    fn keeps_channels_across_saves() {
+        let config = manifest("music: true, sfx: true");
+        let mut player = Player::new(&config);
+        player.channels.remove("music");
+        player.disabled_channels.insert("music".to_owned());
+        let mut loaded = Player::load(serde_yaml::to_string(&player).unwrap()).unwrap();
+        loaded.sync_channels(&config);
+        assert_eq!(names(&loaded.channels), ["sfx"]);
+        assert_eq!(names(&loaded.disabled_channels), ["music"]);
+    }
+
+    #[test]
+    fn enables_channels_added_since_saving() {
+        let mut player = Player::new(&manifest("music: true, sfx: true"));
+        player.channels.remove("sfx");
+        player.disabled_channels.insert("sfx".to_owned());
+        let content = serde_yaml::to_string(&player).unwrap();
+        let mut loaded = Player::load(content).unwrap();
+        loaded.sync_channels(&manifest("music: true, sfx: true, voice: true"));
+        assert_eq!(names(&loaded.channels), ["music", "voice"]);
+        assert_eq!(names(&loaded.disabled_channels), ["sfx"]);
+    }
+
+    #[test]
+    fn enables_channels_missing_from_old_saves() {
+        // Saves from before disabled channels were recorded only list the enabled ones
+        let mut player = Player::new(&manifest("music: true"));
+        player.disabled_channels.clear();
+        let content = serde_yaml::to_string(&player)
+            .unwrap()
+            .replace("disabled_channels: []\n", "");
+        assert!(!content.contains("disabled_channels"));
+        let mut loaded = Player::load(content).unwrap();
+        loaded.sync_channels(&manifest("music: true, sfx: true"));
+        assert_eq!(names(&loaded.channels), ["music", "sfx"]);
+        assert!(loaded.disabled_channels.is_empty());
+    }
+}
@@ -1,22 +1,32 @@
-use std::{collections::HashMap, fmt::Display};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Display,
+    time::Duration,
+};
 
 use anyhow::{anyhow, Context, Result};
+use crossterm::style::Stylize;
+use rlua::{Context as LuaContext, FromLua, ToLua, Value};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    core::text::{
-        display::{Text, TextLines},
-        templating::TemplatableString,
+    core::{
+        audio::{Audio, Music, SoundAction, SoundActions},
+        text::{
+            display::{Text, TextLines},
+            templating::{TemplatableString, TemplatableValue},
+            typewriter,
+        },
     },
     loading::loader::{ContentFile, Contents},
 };
 
 use super::{
-    choice::{Choice, Choices, UsableChoices},
+    choice::{Choice, ChoiceDisplay, ChoiceLayout, Choices, UsableChoices},
     context::{StaticContext, TextContext},
-    path::{PathData, PathLookup},
+    path::{Path, PathData, PathLookup},
     player::Player,
-    state::notes::Notes,
+    state::{notes::Notes, random::SeededRandom, selection::Selection, variables::VariableInput},
 };
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -28,14 +38,225 @@ use super::{
 pub struct Prompt {
     #[serde(rename = "prompt", skip_serializing_if = "Option::is_none")]
     pub text: Option<TextLines>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Pages of text shown one after another after the text prompt, such as for a long stretch of lore.
+    pub slideshow: Option<Slideshow>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// A script to run as a [sequence](crate::core::scripts::Scripts::start_sequence) after the text prompt is displayed.
+    pub sequence: Option<String>,
+    #[serde(alias = "on enter", skip_serializing_if = "Option::is_none")]
+    /// A script to [run](crate::core::scripts::Scripts::run) each time the player enters this prompt, before the text prompt is displayed,
+    /// so that it can set variables used in the text.
+    ///
+    /// The script is passed this prompt's [arrangement](Prompt::arrangement) and can return a modified one to change the choices
+    /// displayed. If it moves the player elsewhere, this prompt is left without being displayed; see [`run_hook`](crate::game::gloop::run_hook).
+    pub on_enter: Option<String>,
+    #[serde(alias = "on exit", skip_serializing_if = "Option::is_none")]
+    /// A script to [run](crate::core::scripts::Scripts::run) each time the player leaves this prompt for another, after the changes
+    /// of the choice that led away are applied. If the script jumps, its jump replaces the choice's.
+    pub on_exit: Option<String>,
     pub choices: Choices,
+    #[serde(default, alias = "shuffle choices", skip_serializing_if = "std::ops::Not::not")]
+    /// Whether the displayed choices are shuffled rather than kept in the order they're written,
+    /// such as to keep players from favoring the first one; see [`shuffle_arrangement`](Prompt::shuffle_arrangement).
+    pub shuffle_choices: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether this prompt's text and the choices taken in it are written to the [transcript](crate::core::transcript::Transcript).
+    /// Defaults to `true`.
+    pub transcript: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// What happens if the player goes idle while answering this prompt.
+    pub idle: Option<IdleHandler>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Marks this prompt as the start of a chapter, which is introduced when the player enters it.
+    pub chapter: Option<Chapter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The background music that plays while the player is at this prompt. Music carries over to prompts without any.
+    pub music: Option<Music>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    /// Marks this prompt as a checkpoint, which [writes the checkpoint save](crate::loading::saves::SaveManager::write_checkpoint) when the player enters it.
+    /// The player is offered to restart from the last checkpoint when they reach an ending or the game crashes.
+    pub checkpoint: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// Labels for organizing prompts, such as `combat` or `ending`, that can be listed and jumped between in debug mode.
+    /// Prompts tagged [`wip`](Prompt::WIP_TAG) are warned about if they can be reached.
+    pub tags: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+/// A response choice as listed to a prompt's [`on_enter`](Prompt::on_enter) hook, which is converted to and from a Lua table.
+///
+/// Choices left out of the list a hook returns are hidden, and the rest are displayed in the order they're listed.
+pub struct ArrangedChoice {
+    /// The number of the prompt's choice this arranges, starting at `1`.
+    pub number: usize,
+    /// Filled response text to display in place of the choice's own.
+    pub text: Option<String>,
+    /// The path of a prompt to jump to in place of the choice's own jump, relative to the prompt's file.
+    pub target: Option<String>,
+    /// Whether the choice can be used. Defaults to `true`; disabled choices are shown locked.
+    pub enabled: bool,
+}
+
+/// The response choices of a prompt, in the order they're displayed.
+pub type ChoiceArrangement = Vec<ArrangedChoice>;
+
+impl<'lua> ToLua<'lua> for ArrangedChoice {
+    fn to_lua(self, lua: LuaContext<'lua>) -> rlua::Result<Value<'lua>> {
+        let table = lua.create_table()?;
+        table.set("number", self.number)?;
+        table.set("text", self.text)?;
+        table.set("target", self.target)?;
+        table.set("enabled", self.enabled)?;
+        Ok(Value::Table(table))
+    }
+}
+
+impl<'lua> FromLua<'lua> for ArrangedChoice {
+    fn from_lua(value: Value<'lua>, _: LuaContext<'lua>) -> rlua::Result<Self> {
+        let Value::Table(table) = value else {
+            return Err(rlua::Error::FromLuaConversionError {
+                from: value.type_name(),
+                to: "arranged choice",
+                message: Some("expected a table".to_owned()),
+            });
+        };
+        Ok(Self {
+            number: table.get("number")?,
+            text: table.get("text")?,
+            target: table.get("target")?,
+            enabled: table.get::<_, Option<bool>>("enabled")?.unwrap_or(true),
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
+/// The start of a chapter, introduced with a title card when the player enters its prompt from another chapter.
+///
+/// The player's current chapter is tracked on the [`Player`] and shown in their saves and the pause menu.
+/// With the [`ClearOnChapter`](crate::core::manifest::ScreenMode::ClearOnChapter) screen mode, the terminal is cleared first.
+pub struct Chapter {
+    /// The chapter's title, which the player's current chapter is tracked by.
+    pub title: TemplatableString,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Text shown under the title, such as the chapter's number.
+    pub subtitle: Option<TemplatableString>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Ordered sound actions to submit to the game's [`Audio`] resource as the chapter starts, such as to change the music.
+    pub sounds: Option<SoundActions>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
+/// Pages of text that are printed in turn, each advancing to the next on its own after a delay.
+///
+/// While a page is waiting, pressing any key pauses the slideshow and pressing another resumes it.
+/// Pressing the `skip` key or Escape jumps past the remaining pages to the prompt's choices.
+pub struct Slideshow {
+    /// The pages to show in order, each made up of text lines.
+    pub pages: Vec<TextLines>,
+    /// How long, in milliseconds, each page is shown after it's printed before the next one is.
+    pub delay: TemplatableValue<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The key that skips the remaining pages. Defaults to the manifest's [`advance`](crate::core::manifest::KeyBindings::advance) key.
+    pub skip: Option<char>,
+}
+
+impl Slideshow {
+    /// Validates that there are pages to show, along with their text's references, script segments, and sound keys.
+    pub fn validate(&self, stc: &StaticContext) -> Result<()> {
+        if self.pages.is_empty() {
+            return Err(anyhow!("`pages` is empty"));
+        }
+        for (index, page) in self.pages.iter().enumerate() {
+            for line in page {
+                line.content
+                    .validate_references(stc.config)
+                    .and_then(|_| stc.resources.scripts.validate_segments(&line.content))
+                    .with_context(|| format!("Failed to validate page #{}", index + 1))?;
+            }
+            if let Some(audio) = &stc.resources.audio {
+                Text::validate_all(page, &audio.borrow())
+                    .with_context(|| format!("Failed to validate page #{}", index + 1))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Prints each page in turn, [waiting](crate::core::resources::Resources::wait_page) between them until the player skips the rest.
+    pub fn play(&self, player: &Player, text_context: &TextContext) -> Result<()> {
+        let delay = Duration::from_millis(self.delay.get_value(text_context)?);
+        let skip = self
+            .skip
+            .or(text_context.config().settings.keybindings.advance);
+        for (index, page) in self.pages.iter().enumerate() {
+            Text::print_lines_nl(page, player, text_context)?;
+            if index + 1 == self.pages.len() || !text_context.resources().wait_page(delay, skip)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The paths of the prompts carrying each tag, in order.
+pub type PromptTags = BTreeMap<String, Vec<PathData>>;
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
+/// Reacts to a player going without pressing a key for some time while answering a [`Prompt`].
+///
+/// A handler fires at most once per visit to its prompt unless it `repeat`s, in which case its timer restarts.
+/// Any key press also restarts the timer.
+pub struct IdleHandler {
+    /// How long, in milliseconds, the player can go without pressing a key before the handler fires.
+    pub after: TemplatableValue<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Text lines to print when the handler fires.
+    pub text: Option<TextLines>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// A script to run as a [sequence](crate::core::scripts::Scripts::start_sequence) when the handler fires.
+    pub sequence: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// A prompt to move the player to when the handler fires, instead of waiting for their answer again.
+    pub jump: Option<Path>,
+    #[serde(default)]
+    /// Whether the handler can fire again after the player stays idle for another period.
+    pub repeat: bool,
+}
+
+impl IdleHandler {
+    /// The filled idle period after which the handler fires.
+    pub fn after(&self, text_context: &TextContext) -> Result<Duration> {
+        Ok(Duration::from_millis(self.after.get_value(text_context)?))
+    }
+
+    /// Validates the handler's sequence script, jump, and text objects' sound keys, if any.
+    pub fn validate(&self, file: &str, stc: &StaticContext) -> Result<()> {
+        if let Some(sequence) = &self.sequence {
+            stc.resources
+                .scripts
+                .validate_reference(sequence)
+                .with_context(|| "Invalid sequence script")?;
+        }
+        if let Some(jump) = &self.jump {
+            Choice::validate_jump(jump, file, stc)?;
+        }
+        if let (Some(audio), Some(lines)) = (&stc.resources.audio, &self.text) {
+            Text::validate_all(lines, &audio.borrow())?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
 /// A prompt's overarching function based on its choices.
 pub enum PromptModel<'a> {
     /// Has one choice. This choice has an `input` field.
-    Input(String, Option<&'a TemplatableString>),
+    Input(String, &'a VariableInput),
+    /// Has one choice. This choice has a `select` field, letting the player pick several options for the variable.
+    Select(String, &'a Selection),
     /// A normal prompt-choice container model.
     Response,
     /// Has one choice. This choice lacks response or input; immediately jumps to another prompt.
@@ -58,6 +279,7 @@ impl<'a> PromptModel<'a> {
         use PromptModel::*;
         match self {
             Input(name, _) => format!("Input; takes user input for the variable '{name}'"),
+            Select(name, _) => format!("Select; picks several options for the variable '{name}'"),
             Response => "Response; standard prompt-choice model".to_owned(),
             Redirect(_) => "Redirect; jumps to another prompt without input".to_owned(),
             Ending(_) => "Ending; the game is forced to end".to_owned(),
@@ -69,6 +291,27 @@ pub type PromptFile = ContentFile<Prompt>;
 pub type Prompts = Contents<Prompt>;
 
 impl Prompt {
+    /// The tag marking a prompt as unfinished, so that it shouldn't be reachable in a released game.
+    pub const WIP_TAG: &'static str = "wip";
+
+    /// Indexes the tags of every prompt.
+    pub fn index_tags(prompts: &Prompts) -> PromptTags {
+        let mut tags = PromptTags::new();
+        for (file, prompt_file) in prompts {
+            for (name, prompt) in prompt_file {
+                for tag in &prompt.tags {
+                    tags.entry(tag.clone())
+                        .or_default()
+                        .push(PathLookup::new(file, name).into());
+                }
+            }
+        }
+        for paths in tags.values_mut() {
+            paths.sort_by_key(|path| path.to_string());
+        }
+        tags
+    }
+
     /// Finds a specific prompt file within a [`Prompts`] object.
     pub fn get_file<'a>(prompts: &'a Prompts, file: &str) -> Result<&'a PromptFile> {
         prompts
@@ -90,18 +333,84 @@ impl Prompt {
     }
 
     /// Validates this prompt's choices using [`Choice::validate`].
+    ///
+    /// At most one of the choices can have a `timeout`, and no two choices can share a shortcut `key`.
     pub fn validate(&self, file: &str, stc: &StaticContext) -> Result<()> {
         let has_company = self.choices.len() > 1;
+        let timed = self
+            .choices
+            .iter()
+            .filter(|choice| choice.timeout.is_some())
+            .count();
+        if timed > 1 {
+            return Err(anyhow!("{timed} choices have a `timeout` section, but only one can"));
+        }
+        let mut keys: HashMap<String, usize> = HashMap::new();
+        for (index, choice) in self.choices.iter().enumerate() {
+            let Some(key) = choice.key else {
+                continue;
+            };
+            if let Some(other) = keys.insert(key.to_lowercase().to_string(), index + 1) {
+                return Err(anyhow!(
+                    "Choices #{other} and #{} both use the shortcut key '{key}'",
+                    index + 1
+                ));
+            }
+        }
         // Validate all independent choices
         for (index, choice) in self.choices.iter().enumerate() {
             choice
                 .validate(file, has_company, stc)
                 .with_context(|| format!("Failed to validate choice #{}", index + 1))?;
         }
+        let scripts = [
+            ("sequence", &self.sequence),
+            ("on_enter", &self.on_enter),
+            ("on_exit", &self.on_exit),
+        ];
+        for (key, script) in scripts {
+            if let Some(script) = script {
+                stc.resources
+                    .scripts
+                    .validate_reference(script)
+                    .with_context(|| format!("Invalid {key} script"))?;
+            }
+        }
+        if let Some(idle) = &self.idle {
+            idle.validate(file, stc)
+                .with_context(|| "Failed to validate `idle` section")?;
+        }
+        if let Some(slideshow) = &self.slideshow {
+            slideshow
+                .validate(stc)
+                .with_context(|| "Failed to validate `slideshow` section")?;
+        }
+        if let Some(chapter) = &self.chapter {
+            for string in [Some(&chapter.title), chapter.subtitle.as_ref()]
+                .into_iter()
+                .flatten()
+            {
+                stc.resources.scripts.validate_segments(string)?;
+            }
+            if let (Some(audio), Some(sounds)) = (&stc.resources.audio, &chapter.sounds) {
+                SoundAction::validate_all(sounds, &audio.borrow())
+                    .with_context(|| "Failed to validate `chapter` section")?;
+            }
+        }
+        if let (Some(audio), Some(music)) = (&stc.resources.audio, &self.music) {
+            music
+                .action()
+                .validate(&audio.borrow())
+                .with_context(|| "Failed to validate `music` section")?;
+        }
+        for line in self.text.iter().flatten() {
+            line.content.validate_references(stc.config)?;
+            stc.resources.scripts.validate_segments(&line.content)?;
+        }
         // Validate text objects' sound keys, if any
         if let Some(audio) = &stc.resources.audio {
             if let Some(lines) = &self.text {
-                Text::validate_all(lines, audio)?;
+                Text::validate_all(lines, &audio.borrow())?;
             }
         }
         Ok(())
@@ -120,13 +429,67 @@ impl Prompt {
         Ok(())
     }
 
+    /// Validates every prompt like [`Prompt::validate_all`], but returns an error for each invalid prompt
+    /// instead of stopping at the first.
+    pub fn validate_each(stc: &StaticContext) -> Vec<anyhow::Error> {
+        let mut errors = Vec::new();
+        for (file_name, prompt_file) in &stc.resources.prompts {
+            for (name, prompt) in prompt_file {
+                let path: PathData = PathLookup::new(file_name, name).into();
+                if let Err(err) = prompt.validate(file_name, stc) {
+                    errors.push(err.context(format!("Failed to validate prompt {path}")));
+                }
+            }
+        }
+        errors
+    }
+
+    /// Checks every [`SoundAction`] in this prompt's text and choices using [`SoundAction::lint_all`].
+    pub fn lint_audio(&self, audio: &Audio) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let mut lint = |location: String, sounds: &Option<_>| {
+            if let Some(sounds) = sounds {
+                for warning in SoundAction::lint_all(sounds, audio) {
+                    warnings.push(format!("{location}: {warning}"));
+                }
+            }
+        };
+        for (index, line) in self.text.iter().flatten().enumerate() {
+            lint(format!("Text object #{}", index + 1), &line.sounds);
+        }
+        lint("Music".to_owned(), &self.music.as_ref().map(|music| vec![music.action()]));
+        for (index, choice) in self.choices.iter().enumerate() {
+            lint(format!("Choice #{}", index + 1), &choice.sounds);
+            if let Some(response) = &choice.response {
+                lint(format!("Choice #{} response", index + 1), &response.sounds);
+            }
+        }
+        warnings
+    }
+
+    /// Checks all prompts in a [`Prompts`] map using [`Prompt::lint_audio`].
+    pub fn lint_audio_all(prompts: &Prompts, audio: &Audio) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for (file_name, prompt_file) in prompts {
+            for (name, prompt) in prompt_file {
+                let path: PathData = PathLookup::new(file_name, name).into();
+                for warning in prompt.lint_audio(audio) {
+                    warnings.push(format!("Prompt {path}: {warning}"));
+                }
+            }
+        }
+        warnings
+    }
+
     /// Returns the [`PromptModel`] based on this prompt's choices. See the enum's fields for criteria.
     pub fn model(&self, text_context: &TextContext) -> Result<PromptModel> {
         use PromptModel::*;
         if self.choices.len() == 1 {
             let choice = &self.choices[0];
             if let Some(input) = &choice.input {
-                return Ok(Input(input.name.fill(text_context)?, input.text.as_ref()));
+                return Ok(Input(input.name.fill(text_context)?, input));
+            } else if let Some(selection) = &choice.select {
+                return Ok(Select(selection.name.fill(text_context)?, selection));
             } else if choice.response.is_none() {
                 if let Some(ending) = &choice.ending {
                     return Ok(Ending(ending));
@@ -137,38 +500,252 @@ impl Prompt {
         Ok(Response)
     }
 
-    /// Gathers all choices that a player can use based on the note context.
-    pub fn usable_choices(
+    /// Whether the choice at a 0-based `index` is a [one-time](Choice::single_use) choice in the `used` choice numbers.
+    fn is_used(&self, index: usize, used: &HashSet<usize>) -> bool {
+        self.choices[index].single_use && used.contains(&(index + 1))
+    }
+
+    /// Lists this prompt's response choices for its [`on_enter`](Self::on_enter) hook to arrange, in order.
+    ///
+    /// [One-time](Choice::single_use) choices whose numbers are `used` are left out. Each choice is listed with its filled
+    /// response text, whether the player can use it, and its jump's path if the jump isn't templated or weighted.
+    pub fn arrangement(
         &self,
+        file: &str,
+        used: &HashSet<usize>,
+        text_context: &TextContext,
+    ) -> Result<ChoiceArrangement> {
+        let prompts = &text_context.resources().prompts;
+        let mut result = Vec::new();
+        for (index, choice) in self.choices.iter().enumerate() {
+            let Some(response) = &choice.response else {
+                continue;
+            };
+            if self.is_used(index, used) {
+                continue;
+            }
+            let enabled = choice.can_player_use(&text_context.notes, text_context)?.0
+                && choice.shortfall(text_context)?.is_none();
+            result.push(ArrangedChoice {
+                number: index + 1,
+                text: Some(response.content.fill(text_context)?),
+                target: choice
+                    .jump
+                    .as_ref()
+                    .and_then(|jump| jump.static_data(file, prompts))
+                    .and_then(Result::ok)
+                    .map(|target| target.to_string()),
+                enabled,
+            });
+        }
+        Ok(result)
+    }
+
+    /// Validates an arrangement returned by this prompt's [`on_enter`](Self::on_enter) hook when the player is at `path`.
+    ///
+    /// Each listed choice must be an unused response choice of this prompt, listed once, and any target it's
+    /// given must be a prompt that exists. Only choices that already jump somewhere can be given a target.
+    pub fn validate_arrangement(
+        &self,
+        arrangement: &ChoiceArrangement,
+        path: &PathData,
+        used: &HashSet<usize>,
+        prompts: &Prompts,
+    ) -> Result<()> {
+        let mut listed = HashSet::new();
+        for arranged in arrangement {
+            let number = arranged.number;
+            let choice = number
+                .checked_sub(1)
+                .and_then(|index| self.choices.get(index))
+                .filter(|choice| choice.response.is_some())
+                .ok_or(anyhow!("Choice #{number} doesn't exist or has no response"))?;
+            if self.is_used(number - 1, used) {
+                return Err(anyhow!("Choice #{number} has already been used"));
+            }
+            if !listed.insert(number) {
+                return Err(anyhow!("Choice #{number} is listed more than once"));
+            }
+            if let Some(target) = &arranged.target {
+                if choice.jump.is_none() && choice.jumps.is_none() {
+                    return Err(anyhow!(
+                        "Choice #{number} is given a target but doesn't jump anywhere"
+                    ));
+                }
+                Self::get(prompts, &PathData::parse(target, &path.file)).with_context(|| {
+                    anyhow!("Choice #{number} is given an invalid target '{target}'")
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Pairs each choice with its 0-based index and how it's arranged, in the `arrangement`'s order if there is one.
+    ///
+    /// Choices without a response can't be arranged, so they're always kept after any that were.
+    fn arranged<'a>(
+        &'a self,
+        arrangement: Option<&'a ChoiceArrangement>,
+    ) -> Vec<(usize, &'a Choice, Option<&'a ArrangedChoice>)> {
+        let natural = self.choices.iter().enumerate();
+        let Some(arrangement) = arrangement else {
+            return natural
+                .map(|(index, choice)| (index, choice, None))
+                .collect();
+        };
+        arrangement
+            .iter()
+            .filter_map(|arranged| {
+                let index = arranged.number.checked_sub(1)?;
+                Some((index, self.choices.get(index)?, Some(arranged)))
+            })
+            .chain(
+                natural
+                    .filter(|(_, choice)| choice.response.is_none())
+                    .map(|(index, choice)| (index, choice, None)),
+            )
+            .collect()
+    }
+
+    /// Shuffles the order that the `arrangement` displays choices in, or that they're written in if there's none,
+    /// drawing from a `random` generator [forked](SeededRandom::fork) from the player's so that the order is reproducible.
+    ///
+    /// Hidden choices are left out before shuffling, and locked ones are kept after the shuffled usable ones in their own order.
+    /// Since the result is an arrangement itself, each displayed number still maps to the choice shown beside it.
+    pub fn shuffle_arrangement(
+        &self,
+        arrangement: Option<ChoiceArrangement>,
+        used: &HashSet<usize>,
+        random: &SeededRandom,
+        text_context: &TextContext,
+    ) -> Result<ChoiceArrangement> {
+        let usable: Vec<&Choice> = self
+            .usable_choices(&text_context.notes, used, arrangement.as_ref(), text_context)?
+            .into_iter()
+            .map(|(choice, ..)| choice)
+            .collect();
+        let displayed =
+            self.displayed_choices(&usable, used, arrangement.as_ref(), text_context)?;
+        let arranged = self.arranged(arrangement.as_ref());
+        let (mut shuffled, locked): (Vec<_>, Vec<_>) = displayed
+            .iter()
+            .filter(|(choice, ..)| choice.response.is_some())
+            .filter_map(|(choice, display, _)| {
+                let (index, _, arranged) = arranged
+                    .iter()
+                    .find(|(_, other, _)| std::ptr::eq(*choice, *other))?;
+                let arranged = arranged.cloned().unwrap_or(ArrangedChoice {
+                    number: index + 1,
+                    text: None,
+                    target: None,
+                    enabled: true,
+                });
+                Some((arranged, matches!(display, ChoiceDisplay::Usable)))
+            })
+            .partition(|(_, usable)| *usable);
+        random.shuffle(&mut shuffled);
+        shuffled.extend(locked);
+        Ok(shuffled.into_iter().map(|(arranged, _)| arranged).collect())
+    }
+
+    /// Gathers all choices that a player can use based on the note context and what they can afford.
+    ///
+    /// [One-time](Choice::single_use) choices whose numbers are `used` are left out, as are choices hidden or disabled by the `arrangement`.
+    pub fn usable_choices<'a>(
+        &'a self,
         notes: &Notes,
+        used: &HashSet<usize>,
+        arrangement: Option<&'a ChoiceArrangement>,
         text_context: &TextContext,
-    ) -> Result<UsableChoices> {
+    ) -> Result<UsableChoices<'a>> {
         let mut result = Vec::new();
-        for choice in &self.choices {
+        for (index, choice, arranged) in self.arranged(arrangement) {
+            if self.is_used(index, used) || arranged.is_some_and(|arranged| !arranged.enabled) {
+                continue;
+            }
             let (usable, once) = choice.can_player_use(notes, text_context)?;
-            if usable {
-                result.push((choice, once))
+            if usable && choice.shortfall(text_context)?.is_none() {
+                result.push((choice, once, arranged))
             }
         }
         Ok(result)
     }
 
-    /// Prints the prompt text, if any, and the choices display, if any are responses.
+    /// Pairs each choice to display with how it's [displayed](ChoiceDisplay) and the text the `arrangement` gives it, if any, in order.
+    ///
+    /// Unusable choices are only included if they are [shown locked](Choice::is_shown_locked), disabled by the arrangement,
+    /// or can't be afforded, in which case they are displayed with what they need. Used one-time choices are never included.
+    fn displayed_choices<'a>(
+        &'a self,
+        usable_choices: &[&Choice],
+        used: &HashSet<usize>,
+        arrangement: Option<&'a ChoiceArrangement>,
+        text_context: &TextContext,
+    ) -> Result<Vec<(&'a Choice, ChoiceDisplay, Option<&'a str>)>> {
+        let mut result = Vec::new();
+        for (index, choice, arranged) in self.arranged(arrangement) {
+            if self.is_used(index, used) {
+                continue;
+            }
+            let text = arranged.and_then(|arranged| arranged.text.as_deref());
+            if usable_choices
+                .iter()
+                .any(|usable| std::ptr::eq(*usable, choice))
+            {
+                result.push((choice, ChoiceDisplay::Usable, text));
+            } else if arranged.is_some_and(|arranged| !arranged.enabled)
+                || choice.is_shown_locked(text_context)?
+            {
+                result.push((choice, ChoiceDisplay::Locked(None), text));
+            } else if choice.can_player_use(&text_context.notes, text_context)?.0 {
+                let reason = choice.shortfall(text_context)?;
+                result.push((choice, ChoiceDisplay::Locked(reason), text));
+            }
+        }
+        Ok(result)
+    }
+
+    /// Prints the prompt text, if any, and the choices display, if any are responses, following the choice `arrangement` if there is one.
     pub fn print(
         &self,
         player: &Player,
         model: &PromptModel,
         display: bool,
         usable_choices: &Vec<&Choice>,
+        arrangement: Option<&ChoiceArrangement>,
         text_context: &TextContext,
     ) -> Result<()> {
         if display {
             if let Some(lines) = &self.text {
                 Text::print_lines_nl(lines, player, text_context)?;
             }
+            if let Some(slideshow) = &self.slideshow {
+                slideshow.play(player, text_context)?;
+            }
+        }
+        if let PromptModel::Select(_, selection) = model {
+            let mut selected = text_context.resources().selected.borrow_mut();
+            selected.clear();
+            let lines = selection.lines(&selected, text_context)?;
+            println!("{}\n{}\n", lines.join("\n"), selection.hint().dark_grey());
         }
         let result = if let PromptModel::Response = model {
-            println!("{}\n", Choice::display(usable_choices, text_context)?);
+            let used = player.used_choices_at(&player.latest_entry()?.path);
+            let choices =
+                self.displayed_choices(usable_choices, &used, arrangement, text_context)?;
+            let lines = Choice::display(&choices, text_context)?;
+            let text = lines
+                .iter()
+                .map(|(_, line)| match text_context.is_accessible() {
+                    true => typewriter::strip_escapes(line),
+                    false => line.clone(),
+                })
+                .collect::<Vec<_>>();
+            println!("{}\n", text.join("\n"));
+            if text_context.config().settings.mouse {
+                *text_context.resources().choice_layout.borrow_mut() =
+                    ChoiceLayout::measure(&lines);
+            }
         };
         Ok(result)
     }
@@ -176,11 +753,16 @@ impl Prompt {
     /// Returns the indices of any of this prompt's choices that jump to another prompt.
     ///
     /// Uses [`Choice::has_jump_to`].
-    pub fn get_jumps_to(&self, current_file: &str, other: &PathData) -> Vec<usize> {
+    pub fn get_jumps_to(
+        &self,
+        current_file: &str,
+        other: &PathData,
+        prompts: &Prompts,
+    ) -> Vec<usize> {
         self.choices
             .iter()
             .enumerate()
-            .filter(|(_, choice)| choice.has_jump_to(current_file, other))
+            .filter(|(_, choice)| choice.has_jump_to(current_file, other, prompts))
             .map(|(index, _)| index)
             .collect()
     }
@@ -200,7 +782,7 @@ impl Prompt {
                     .map(|(other_prompt_name, other_prompt)| {
                         let id =
                             format!("{}/{}", other_file_name.clone(), other_prompt_name.clone());
-                        (id, other_prompt.get_jumps_to(other_file_name, path))
+                        (id, other_prompt.get_jumps_to(other_file_name, path, prompts))
                     })
                     .filter(|(_, choices)| !choices.is_empty())
             })
@@ -215,11 +797,12 @@ impl Prompt {
         path: &PathData,
         prompts: &Prompts,
         notes: &Notes,
+        used: &HashSet<usize>,
         text_context: &TextContext,
     ) -> Result<String> {
         let model = self.model(text_context)?;
         let choices_amt = self.choices.len();
-        let usable_choices = self.usable_choices(notes, text_context)?.len();
+        let usable_choices = self.usable_choices(notes, used, None, text_context)?.len();
         let external_jumps: Vec<String> = Self::external_jumps(path, prompts)
             .iter()
             .map(|(other_id, choices)| {
@@ -0,0 +1,216 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use super::{
+    path::{PathData, PathLookup},
+    prompt::{Prompt, Prompts},
+};
+
+/// A choice target in the [`PromptGraph`].
+pub struct Edge {
+    /// The path of the prompt this edge leads to.
+    pub target: String,
+    /// The number of the choice this edge belongs to, starting at `1`,
+    /// or [`None`] if it belongs to the prompt's [idle handler](crate::core::prompt::IdleHandler).
+    pub choice: Option<usize>,
+    /// The choice's raw response text, if it has one.
+    pub label: Option<String>,
+    /// Whether the choice has a `requires` condition.
+    pub conditional: bool,
+    /// The target's weight, if it's picked from the choice's `jumps`.
+    pub weight: Option<u32>,
+}
+
+/// The prompt graph, which is made up of every prompt and the choice targets between them.
+///
+/// Every target of a choice counts as an edge, even if it's picked by weight or the choice has a `requires` condition.
+/// Templated targets can't be followed, so they're reported as warnings instead.
+pub struct PromptGraph {
+    /// The edges of each prompt's choices in order, keyed by the prompt's path.
+    pub edges: BTreeMap<String, Vec<Edge>>,
+    /// Jumps that couldn't be resolved to any prompt.
+    pub errors: Vec<String>,
+    /// Jumps that couldn't be followed because they're templated.
+    pub warnings: Vec<String>,
+}
+
+impl PromptGraph {
+    /// Builds the graph from every choice of every prompt.
+    pub fn build(prompts: &Prompts) -> Self {
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        let mut edges: BTreeMap<String, Vec<Edge>> = BTreeMap::new();
+        for (file, prompt_file) in prompts {
+            for (name, prompt) in prompt_file {
+                let path: PathData = PathLookup::new(file, name).into();
+                let targets = edges.entry(path.to_string()).or_default();
+                for (index, choice) in prompt.choices.iter().enumerate() {
+                    let jumps = choice.jump.iter().map(|jump| (jump, None)).chain(
+                        choice
+                            .jumps
+                            .iter()
+                            .flatten()
+                            .map(|weighted| (&weighted.path, Some(weighted.weight))),
+                    );
+                    for (jump, weight) in jumps {
+                        match jump.static_data(file, prompts) {
+                            Some(Ok(target)) => targets.push(Edge {
+                                target: target.to_string(),
+                                choice: Some(index + 1),
+                                label: choice
+                                    .response
+                                    .as_ref()
+                                    .map(|response| response.content.raw().to_owned()),
+                                conditional: choice.requires.is_some(),
+                                weight,
+                            }),
+                            Some(Err(err)) => errors.push(format!(
+                                "Choice #{} of prompt {path} has an unresolved jump: {err}",
+                                index + 1
+                            )),
+                            None => warnings.push(format!(
+                                "Choice #{} of prompt {path} jumps to a templated prompt, so the prompts it leads to can't be checked",
+                                index + 1
+                            )),
+                        }
+                    }
+                }
+                if let Some(jump) = prompt.idle.as_ref().and_then(|idle| idle.jump.as_ref()) {
+                    match jump.static_data(file, prompts) {
+                        Some(Ok(target)) => targets.push(Edge {
+                            target: target.to_string(),
+                            choice: None,
+                            label: None,
+                            conditional: false,
+                            weight: None,
+                        }),
+                        Some(Err(err)) => errors.push(format!(
+                            "The idle handler of prompt {path} has an unresolved jump: {err}"
+                        )),
+                        None => warnings.push(format!(
+                            "The idle handler of prompt {path} jumps to a templated prompt, so the prompts it leads to can't be checked"
+                        )),
+                    }
+                }
+            }
+        }
+        Self {
+            edges,
+            errors,
+            warnings,
+        }
+    }
+
+    /// Quotes a string for use as a DOT identifier or attribute.
+    fn quote(value: &str) -> String {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+
+    /// Writes the graph in the Graphviz DOT language, with the `entry` prompt in bold.
+    ///
+    /// Edges are labeled with their choice's number and response. Conditional edges are dashed,
+    /// weighted edges are labeled with their weight, and idle handler edges are dotted.
+    pub fn to_dot(&self, name: &str, entry: &PathData) -> String {
+        let mut result = format!("digraph {} {{\n", Self::quote(name));
+        let entry = entry.to_string();
+        for path in self.edges.keys() {
+            let style = match *path == entry {
+                true => " [style=bold]",
+                false => "",
+            };
+            result.push_str(&format!("    {}{style};\n", Self::quote(path)));
+        }
+        for (path, edges) in &self.edges {
+            for edge in edges {
+                let mut label = match edge.choice {
+                    Some(choice) => format!("#{choice}"),
+                    None => "idle".to_owned(),
+                };
+                if let Some(response) = &edge.label {
+                    label.push_str(&format!(": {response}"));
+                }
+                if let Some(weight) = edge.weight {
+                    label.push_str(&format!(" (weight {weight})"));
+                }
+                let mut attributes = vec![format!("label={}", Self::quote(&label))];
+                if edge.conditional {
+                    attributes.push("style=dashed".to_owned());
+                }
+                if edge.choice.is_none() {
+                    attributes.push("style=dotted".to_owned());
+                }
+                result.push_str(&format!(
+                    "    {} -> {} [{}];\n",
+                    Self::quote(path),
+                    Self::quote(&edge.target),
+                    attributes.join(", ")
+                ));
+            }
+        }
+        result.push_str("}\n");
+        result
+    }
+}
+
+/// The result of analyzing the [`PromptGraph`].
+pub struct Reachability {
+    /// Problems that stop a game from being played, such as prompts without any choices.
+    pub errors: Vec<String>,
+    /// Problems that are likely mistakes but don't stop the game, such as prompts that nothing jumps to.
+    pub warnings: Vec<String>,
+}
+
+impl Reachability {
+    /// Builds the prompt graph and walks it from the `entry` prompt.
+    pub fn analyze(prompts: &Prompts, entry: &PathData) -> Self {
+        let PromptGraph {
+            edges,
+            mut errors,
+            mut warnings,
+        } = PromptGraph::build(prompts);
+        let mut wip = Vec::new();
+        for (file, prompt_file) in prompts {
+            for (name, prompt) in prompt_file {
+                let path: PathData = PathLookup::new(file, name).into();
+                if prompt.tags.iter().any(|tag| tag == Prompt::WIP_TAG) {
+                    wip.push(path.to_string());
+                }
+                if prompt.choices.is_empty() {
+                    errors.push(format!(
+                        "Prompt {path} has no choices, so the game can't continue from it"
+                    ));
+                } else if prompt
+                    .choices
+                    .iter()
+                    .all(|choice| choice.requires.is_some() || choice.single_use)
+                {
+                    warnings.push(format!(
+                        "Every choice of prompt {path} has a `requires` condition or can only be used once, so it may be a dead end"
+                    ));
+                }
+            }
+        }
+        let entry = entry.to_string();
+        if !edges.contains_key(&entry) {
+            errors.push(format!("Entry prompt {entry} doesn't exist"));
+        }
+        let mut reached = BTreeSet::from([entry.clone()]);
+        let mut queue = VecDeque::from([entry]);
+        while let Some(path) = queue.pop_front() {
+            for edge in edges.get(&path).into_iter().flatten() {
+                if reached.insert(edge.target.clone()) {
+                    queue.push_back(edge.target.clone());
+                }
+            }
+        }
+        for path in edges.keys().filter(|path| !reached.contains(*path)) {
+            warnings.push(format!("Prompt {path} is unreachable from the entry prompt"));
+        }
+        for path in wip.iter().filter(|path| reached.contains(*path)) {
+            warnings.push(format!(
+                "Prompt {path} is tagged `{}` but is reachable from the entry prompt",
+                Prompt::WIP_TAG
+            ));
+        }
+        Self { errors, warnings }
+    }
+}
@@ -1,40 +1,240 @@
-use anyhow::Result;
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeSet,
+    io::{self, Write},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context, Result};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+    style::Stylize,
+    terminal,
+};
 
 use crate::loading::loader::Loader;
 
 use super::{
-    audio::{Audio, SoundActions},
+    audio::{Audio, AudioEvent, Music, SoundActionData, SoundActions},
+    choice::ChoiceLayout,
     context::{StaticContext, TextContext},
+    fast_forward::FastForward,
     manifest::Manifest,
-    player::Player,
-    prompt::{Prompt, Prompts},
+    pause::{PauseAction, PauseMenu},
+    player::{Bookmark, Player},
+    prompt::{Prompt, PromptTags, Prompts},
+    reachability::Reachability,
     scripts::Scripts,
     state::info::InfoPages,
     text::display::{TranslationFile, Translations},
+    transcript::Transcript,
 };
 
 pub struct Resources {
     pub prompts: Prompts,
+    /// The [tags](Prompt::tags) of every prompt.
+    pub tags: PromptTags,
     pub translations: Translations,
     pub info_pages: InfoPages,
     pub scripts: Scripts,
-    pub audio: Option<Audio>,
+    pub audio: Option<RefCell<Audio>>,
+    pub pause: PauseMenu,
+    pub fast_forward: FastForward,
+    pub transcript: Transcript,
+    /// The [bookmarks](Bookmark) the player has made this session, oldest first.
+    pub bookmarks: RefCell<Vec<Bookmark>>,
+    /// Where the current prompt's choices were printed, if [mouse](crate::core::manifest::Settings::mouse) input is on.
+    pub choice_layout: RefCell<Option<ChoiceLayout>>,
+    /// The numbers of the options picked so far at the current prompt, if it has a [selection](crate::core::state::selection::Selection).
+    pub selected: RefCell<BTreeSet<usize>>,
+    /// How many characters have been printed since the current prompt's [auto-advance](Self::start_auto_advance)
+    /// step started, if it hasn't been taken yet.
+    printed: Cell<Option<usize>>,
 }
 
 impl Resources {
+    /// How often [`await_audio`](Self::await_audio) polls the audio players.
+    const AWAIT_INTERVAL: Duration = Duration::from_millis(20);
+
     pub fn load(loader: &Loader, config: &Manifest) -> Result<Self> {
+        Self::load_with(loader, config, Audio::load(loader, config)?)
+    }
+
+    /// Loads every resource with a [headless](Audio::load_headless) [`Audio`] resource, for validation only.
+    pub fn load_headless(loader: &Loader, config: &Manifest) -> Result<Self> {
+        Self::load_with(loader, config, Audio::load_headless(loader, config)?)
+    }
+
+    fn load_with(loader: &Loader, config: &Manifest, audio: Option<Audio>) -> Result<Self> {
+        let prompts = loader.load_content("prompts")?;
         let result = Resources {
-            prompts: loader.load_content("prompts")?,
+            tags: Prompt::index_tags(&prompts),
+            prompts,
             translations: loader.load_content("lang")?,
             info_pages: loader.load_raw_content("info")?,
-            scripts: Scripts::load(loader)?,
-            audio: Audio::load(loader, config)?,
+            scripts: Scripts::load(loader, config.settings.script_sandbox)?,
+            audio: audio.map(RefCell::new),
+            pause: PauseMenu::new(&config.settings.keybindings),
+            fast_forward: FastForward::new(&config.settings.keybindings),
+            transcript: Transcript::default(),
+            bookmarks: RefCell::new(Vec::new()),
+            choice_layout: RefCell::new(None),
+            selected: RefCell::new(BTreeSet::new()),
+            printed: Cell::new(None),
         };
         Ok(result)
     }
 
+    #[cfg(test)]
+    /// Constructs a minimal manifest for tests, with a game `id` and the keys of its `settings` in YAML.
+    pub fn test_manifest(id: &str, settings: &str) -> Manifest {
+        serde_yaml::from_str(&format!(
+            "metadata: {{ name: Test, id: {id}, authors: [], version: 1.0.0 }}\n\
+             entry: {{ path: {{ file: main, prompt: start }} }}\n\
+             settings: {{ {settings} }}"
+        ))
+        .unwrap()
+    }
+
+    #[cfg(test)]
+    /// Constructs resources without any content or audio, for tests.
+    pub fn empty(config: &Manifest) -> Self {
+        Resources {
+            prompts: Prompts::new(),
+            tags: PromptTags::new(),
+            translations: Translations::new(),
+            info_pages: InfoPages::new(),
+            scripts: Scripts::new(Default::default(), true).unwrap(),
+            audio: None,
+            pause: PauseMenu::new(&config.settings.keybindings),
+            fast_forward: FastForward::new(&config.settings.keybindings),
+            transcript: Transcript::default(),
+            bookmarks: RefCell::new(Vec::new()),
+            choice_layout: RefCell::new(None),
+            selected: RefCell::new(BTreeSet::new()),
+            printed: Cell::new(None),
+        }
+    }
+
+    /// Validates all loaded resources, failing on the first invalid one.
+    ///
+    /// In debug mode, [`warnings`](Self::warnings) are also reported.
     pub fn validate(&self, stc: &StaticContext) -> Result<()> {
+        if let Some(audio) = &self.audio {
+            audio.borrow().validate_groups()?;
+            audio.borrow().validate_playlists()?;
+            audio.borrow().validate_defaults()?;
+        }
+        self.validate_hooks(stc.config)?;
         let _ = Prompt::validate_all(stc)?;
+        let reachability = Reachability::analyze(&self.prompts, &stc.config.entry.path);
+        if let Some(error) = reachability.errors.into_iter().next() {
+            return Err(anyhow!(error));
+        }
+        if stc.config.settings.debug {
+            for warning in self.warnings(stc) {
+                eprintln!("{} {warning}", "Warning:".yellow());
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates that the manifest's [hook scripts](crate::core::manifest::HookSettings) exist.
+    fn validate_hooks(&self, config: &Manifest) -> Result<()> {
+        if let Some(hook) = &config.settings.hooks.on_choice {
+            self.scripts
+                .validate_reference(hook)
+                .with_context(|| "Invalid `settings.hooks.on_choice` script")?;
+        }
+        Ok(())
+    }
+
+    /// Returns the problems with the loaded resources that don't stop the game from being played:
+    /// sound files dropped at load, [sound actions](crate::core::audio::SoundAction::lint) that can't be fully checked,
+    /// and [`Reachability`] warnings.
+    pub fn warnings(&self, stc: &StaticContext) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if let Some(audio) = &self.audio {
+            warnings.extend(audio.borrow().dropped_warnings());
+            warnings.extend(Prompt::lint_audio_all(&self.prompts, &audio.borrow()));
+        }
+        warnings.extend(Reachability::analyze(&self.prompts, &stc.config.entry.path).warnings);
+        warnings
+    }
+
+    /// Validates all loaded resources like [`Resources::validate`], but returns every problem found
+    /// instead of stopping at the first.
+    pub fn problems(&self, stc: &StaticContext) -> Vec<anyhow::Error> {
+        let mut problems = Vec::new();
+        if let Some(audio) = &self.audio {
+            if let Err(err) = audio.borrow().validate_groups() {
+                problems.push(err);
+            }
+            if let Err(err) = audio.borrow().validate_playlists() {
+                problems.push(err);
+            }
+            if let Err(err) = audio.borrow().validate_defaults() {
+                problems.push(err);
+            }
+        }
+        if let Err(err) = self.validate_hooks(stc.config) {
+            problems.push(err);
+        }
+        problems.extend(Prompt::validate_each(stc));
+        let reachability = Reachability::analyze(&self.prompts, &stc.config.entry.path);
+        problems.extend(reachability.errors.into_iter().map(|error| anyhow!(error)));
+        problems
+    }
+
+    /// Reloads every resource except [`Audio`], which keeps playing, the [`Transcript`], and the player's [bookmarks](Bookmark),
+    /// and validates the result.
+    ///
+    /// The player's current prompt must still exist. If anything fails, the current resources are kept.
+    pub fn reload(&mut self, loader: &Loader, config: &Manifest, player: &Player) -> Result<()> {
+        let prompts = loader.load_content("prompts")?;
+        let mut reloaded = Resources {
+            tags: Prompt::index_tags(&prompts),
+            prompts,
+            translations: loader.load_content("lang")?,
+            info_pages: loader.load_raw_content("info")?,
+            scripts: Scripts::load(loader, config.settings.script_sandbox)?,
+            audio: self.audio.take(),
+            pause: PauseMenu::new(&config.settings.keybindings),
+            fast_forward: FastForward::new(&config.settings.keybindings),
+            transcript: std::mem::take(&mut self.transcript),
+            bookmarks: RefCell::new(self.bookmarks.take()),
+            choice_layout: RefCell::new(None),
+            selected: RefCell::new(BTreeSet::new()),
+            printed: Cell::new(None),
+        };
+        let result = reloaded
+            .validate(&StaticContext::new(config, &reloaded))
+            .and_then(|_| {
+                Prompt::get(&reloaded.prompts, &player.latest_entry()?.path)?;
+                Ok(())
+            });
+        match result {
+            Ok(_) => *self = reloaded,
+            Err(_) => {
+                self.audio = reloaded.audio.take();
+                self.transcript = std::mem::take(&mut reloaded.transcript);
+                self.bookmarks = RefCell::new(reloaded.bookmarks.take());
+            }
+        }
+        result
+    }
+
+    /// Tries to [connect](Audio::connect) to the sound device again if the [`Audio`] resource couldn't be loaded.
+    ///
+    /// The player's channel settings are kept, and the sounds recorded on them are [resumed](Self::resume_audio)
+    /// once the game loop is re-entered.
+    pub fn reconnect_audio(&mut self, loader: &Loader, config: &Manifest) -> Result<()> {
+        if self.audio.is_some() {
+            return Ok(());
+        }
+        let audio = Audio::connect(loader, config)?.ok_or(anyhow!("No sound channels declared"))?;
+        self.audio = Some(RefCell::new(audio));
         Ok(())
     }
 
@@ -42,18 +242,387 @@ impl Resources {
         self.translations.get(lang)
     }
 
+    /// If the [`Audio`] resource exists, [ticks](Audio::tick) it and handles the resulting [`AudioEvent`]s.
+    pub fn tick_audio(&self) -> Result<()> {
+        if let Some(audio) = &self.audio {
+            audio.borrow_mut().tick()?;
+        }
+        self.handle_audio_events()
+    }
+
+    /// Runs or discards script callbacks based on the [`AudioEvent`]s recorded by the [`Audio`] resource.
+    ///
+    /// The audio resource is not borrowed while callbacks run so that they are free to read its state.
+    fn handle_audio_events(&self) -> Result<()> {
+        let Some(audio) = &self.audio else {
+            return Ok(());
+        };
+        let events = audio.borrow_mut().drain_events();
+        for event in events {
+            log::debug!("Audio event: {event:?}");
+            match event {
+                AudioEvent::Finished(channel) => self.scripts.fire_finish_callback(&channel)?,
+                AudioEvent::Interrupted(channel) => self.scripts.clear_finish_callback(&channel)?,
+            }
+        }
+        Ok(())
+    }
+
     /// If the [`Audio`] resource exists, submits a collection of [`SoundActions`] to it.
+    ///
+    /// Without one, such as when no sound device could be [opened](Audio::load), the actions are ignored
+    /// so that the game plays on without sound.
     pub fn submit_audio(
         &self,
         player: &Player,
         sounds: &SoundActions,
         text_context: &TextContext,
     ) -> Result<()> {
-        if let Some(audio) = &self.audio {
+        if self.audio.is_some() {
             for sound in sounds {
-                audio.accept(player, sound, text_context)?;
+                self.submit_action(player, &sound.fill(text_context)?, text_context)?;
             }
         }
+        self.handle_audio_events()?;
+        self.await_audio(text_context.config().settings.keybindings.advance)
+    }
+
+    /// Submits a single filled sound action to the [`Audio`] resource, announcing it in accessibility mode.
+    fn submit_action(
+        &self,
+        player: &Player,
+        action: &SoundActionData,
+        text_context: &TextContext,
+    ) -> Result<()> {
+        let Some(audio) = &self.audio else {
+            return Ok(());
+        };
+        log::debug!(
+            "Sound action {:?} for '{}' on channel '{}'",
+            action.mode,
+            action.name.as_deref().unwrap_or_default(),
+            audio
+                .borrow()
+                .channel_of(action)
+                .cloned()
+                .unwrap_or_default()
+        );
+        let announcement = text_context
+            .is_accessible()
+            .then(|| action.announcement(&audio.borrow(), player))
+            .flatten();
+        audio.borrow_mut().accept(player, action)?;
+        if let Some(announcement) = announcement {
+            println!("{announcement}");
+        }
         Ok(())
     }
+
+    /// Starts a prompt's [`Music`] unless it's already playing, so that prompts sharing a track don't restart it.
+    pub fn submit_music(
+        &self,
+        player: &Player,
+        music: &Music,
+        text_context: &TextContext,
+    ) -> Result<()> {
+        let Some(audio) = &self.audio else {
+            return Ok(());
+        };
+        let action = music.action().fill(text_context)?;
+        if audio.borrow_mut().is_music_playing(&action) {
+            return Ok(());
+        }
+        self.submit_action(player, &action, text_context)?;
+        self.handle_audio_events()
+    }
+
+    /// Blocks until the [`Audio`] resource has no [`Await`](crate::core::audio::SoundActionMode::Await)s left,
+    /// [ticking](Audio::tick) it all the while.
+    ///
+    /// If a `skip` key is provided, pressing it stops waiting early.
+    pub fn await_audio(&self, skip: Option<char>) -> Result<()> {
+        let Some(audio) = &self.audio else {
+            return Ok(());
+        };
+        if self.fast_forward.is_active() {
+            audio.borrow_mut().clear_awaits();
+            return Ok(());
+        }
+        if !audio.borrow_mut().is_awaiting() {
+            return Ok(());
+        }
+        let keys = skip.is_some() || self.pause.is_bound();
+        if keys {
+            terminal::enable_raw_mode()?;
+        }
+        let result = self.poll_audio(audio, skip);
+        if keys {
+            terminal::disable_raw_mode()?;
+        }
+        result
+    }
+
+    /// Applies the player's [`Preferences`](crate::core::state::preferences::Preferences) that the resources depend on,
+    /// such as the master volume.
+    pub fn apply_preferences(&self, player: &Player, config: &Manifest) -> Result<()> {
+        if let Some(audio) = &self.audio {
+            audio
+                .borrow_mut()
+                .set_master_volume(player.preferences.master_volume(config))?;
+        }
+        Ok(())
+    }
+
+    /// Records the sounds playing on each channel onto the [`Player`] so that they're saved along with it.
+    pub fn record_audio(&self, player: &mut Player) {
+        if let Some(audio) = &self.audio {
+            player.sounds = audio.borrow_mut().snapshot();
+        }
+    }
+
+    /// [Restores](Audio::restore) the sounds recorded on a loaded [`Player`], skipping disabled channels.
+    ///
+    /// The sounds are taken off the player so that they aren't restarted again when content is reloaded.
+    /// Without the [`Audio`] resource, they're left on the player in case it's [reconnected](Self::reconnect_audio).
+    pub fn resume_audio(&self, player: &mut Player) -> Result<()> {
+        let Some(audio) = &self.audio else {
+            return Ok(());
+        };
+        let sounds = std::mem::take(&mut player.sounds);
+        let mut audio = audio.borrow_mut();
+        for (channel, sound) in &sounds {
+            audio.restore(player, channel, sound)?;
+        }
+        Ok(())
+    }
+
+    /// Swaps the current [`Player`] for another one, such as a loaded save or a checkpoint.
+    ///
+    /// The new player's [preferences are applied](Self::apply_preferences), every sound of the previous
+    /// player is stopped, and the sounds recorded on the new player are [resumed](Self::resume_audio).
+    pub fn replace_player(
+        &self,
+        player: &mut Player,
+        replacement: Player,
+        config: &Manifest,
+    ) -> Result<()> {
+        *player = replacement;
+        self.apply_preferences(player, config)?;
+        if let Some(audio) = &self.audio {
+            audio.borrow_mut().stop_all(player, None)?;
+        }
+        self.resume_audio(player)
+    }
+
+    /// Shows the [`PauseMenu`] with all audio [suspended](Audio::suspend), resuming it once the menu is closed.
+    pub fn pause(&self) -> Result<PauseAction> {
+        let suspension = self
+            .audio
+            .as_ref()
+            .map(|audio| audio.borrow_mut().suspend());
+        let started = Instant::now();
+        let action = self.pause.show();
+        self.pause.add_paused(started.elapsed());
+        if let (Some(audio), Some(suspension)) = (&self.audio, suspension) {
+            audio.borrow_mut().resume(suspension);
+        }
+        action
+    }
+
+    /// If a key pressed while the game is blocked is the pause key, [pauses](Self::pause) the game
+    /// and [defers](PauseMenu::defer) the picked action until the game loop can carry it out.
+    pub fn pause_blocked(&self, event: &KeyEvent) -> Result<Option<PauseAction>> {
+        if event.kind != KeyEventKind::Press || !self.pause.is_pause_key(event) {
+            return Ok(None);
+        }
+        let action = self.pause()?;
+        self.pause.defer(action);
+        Ok(Some(action))
+    }
+
+    /// Starts counting the characters printed for the current prompt, used to scale its auto-advance delay.
+    pub fn start_auto_advance(&self) {
+        self.printed.set(Some(0));
+    }
+
+    /// Adds to the characters printed for the current prompt, if counting.
+    pub fn record_printed(&self, length: usize) {
+        if let Some(printed) = self.printed.get() {
+            self.printed.set(Some(printed + length));
+        }
+    }
+
+    /// Stops counting and returns the characters printed for the current prompt,
+    /// or [`None`] if the prompt has already auto-advanced or been canceled.
+    pub fn take_auto_advance(&self) -> Option<usize> {
+        self.printed.take()
+    }
+
+    /// Blocks for some time like [`wait`](Self::wait), stopping early if the player presses a key.
+    ///
+    /// Returns whether the whole time passed without a key press. The pause key still pauses the game,
+    /// but counts as a key press so that the picked action can be carried out.
+    pub fn wait_cancelable(&self, duration: Duration) -> Result<bool> {
+        terminal::enable_raw_mode()?;
+        let result = self.poll_key_until(Instant::now() + duration);
+        terminal::disable_raw_mode()?;
+        result
+    }
+
+    fn poll_key_until(&self, deadline: Instant) -> Result<bool> {
+        loop {
+            self.tick_audio()?;
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(true);
+            }
+            if !event::poll(Self::AWAIT_INTERVAL.min(remaining))? {
+                continue;
+            }
+            if let Event::Key(event) = event::read()? {
+                if event.kind == KeyEventKind::Press || self.pause_blocked(&event)?.is_some() {
+                    return Ok(false);
+                }
+            }
+        }
+    }
+
+    /// Blocks for a [slideshow](crate::core::prompt::Slideshow) page's delay, returning whether the slideshow should go on.
+    ///
+    /// Pressing any key pauses the wait, and pressing another resumes it with the time that was left.
+    /// Pressing the `skip` key or Escape, or quitting from the pause menu, ends the slideshow.
+    /// Fast-forwarding doesn't wait at all.
+    pub fn wait_page(&self, delay: Duration, skip: Option<char>) -> Result<bool> {
+        if self.fast_forward.is_active() {
+            return Ok(true);
+        }
+        terminal::enable_raw_mode()?;
+        let result = self.poll_page(delay, skip);
+        terminal::disable_raw_mode()?;
+        result
+    }
+
+    fn poll_page(&self, delay: Duration, skip: Option<char>) -> Result<bool> {
+        let mut remaining = delay;
+        let mut paused = false;
+        let mut last = Instant::now();
+        loop {
+            self.tick_audio()?;
+            if !paused {
+                remaining = remaining.saturating_sub(last.elapsed());
+                if remaining.is_zero() {
+                    return Ok(true);
+                }
+            }
+            last = Instant::now();
+            if !event::poll(Self::AWAIT_INTERVAL.min(remaining))? {
+                continue;
+            }
+            let Event::Key(event) = event::read()? else {
+                continue;
+            };
+            if self.pause_blocked(&event)?.is_some() {
+                if self.pause.is_quitting() {
+                    return Ok(false);
+                }
+                // Time spent in the pause menu doesn't count towards the delay
+                last = Instant::now();
+                continue;
+            }
+            if event.kind != KeyEventKind::Press {
+                continue;
+            }
+            if event.code == KeyCode::Esc
+                || skip.is_some_and(|key| event.code == KeyCode::Char(key))
+            {
+                return Ok(false);
+            }
+            paused = !paused;
+            if paused {
+                print!("{}\r\n", "Paused; press any key to resume".dark_grey());
+                io::stdout().flush()?;
+            }
+        }
+    }
+
+    /// Blocks for some time while [ticking](Audio::tick) the [`Audio`] resource.
+    pub fn wait(&self, duration: Duration) -> Result<()> {
+        let start = Instant::now();
+        while start.elapsed() < duration {
+            self.tick_audio()?;
+            thread::sleep(Self::AWAIT_INTERVAL.min(duration.saturating_sub(start.elapsed())));
+        }
+        Ok(())
+    }
+
+    /// Blocks until a channel has no sound left to play, [ticking](Audio::tick) the [`Audio`] resource all the while.
+    ///
+    /// Returns immediately if the channel doesn't exist.
+    pub fn await_channel(&self, channel: &str) -> Result<()> {
+        let Some(audio) = &self.audio else {
+            return Ok(());
+        };
+        loop {
+            self.tick_audio()?;
+            let playing = audio
+                .borrow()
+                .channel_states()
+                .iter()
+                .any(|(name, state)| *name == channel && state.has_sound);
+            if !playing {
+                return Ok(());
+            }
+            thread::sleep(Self::AWAIT_INTERVAL);
+        }
+    }
+
+    /// Blocks until the player presses a key, [ticking](Audio::tick) the [`Audio`] resource all the while.
+    pub fn await_key(&self) -> Result<()> {
+        terminal::enable_raw_mode()?;
+        let result = self.poll_key();
+        terminal::disable_raw_mode()?;
+        result
+    }
+
+    fn poll_key(&self) -> Result<()> {
+        loop {
+            self.tick_audio()?;
+            if !event::poll(Self::AWAIT_INTERVAL)? {
+                continue;
+            }
+            if let Event::Key(event) = event::read()? {
+                if self.pause_blocked(&event)?.is_some() {
+                    continue;
+                }
+                if event.kind == KeyEventKind::Press {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    fn poll_audio(&self, audio: &RefCell<Audio>, skip: Option<char>) -> Result<()> {
+        loop {
+            self.tick_audio()?;
+            if !audio.borrow_mut().is_awaiting() {
+                return Ok(());
+            }
+            if skip.is_none() && !self.pause.is_bound() {
+                thread::sleep(Self::AWAIT_INTERVAL);
+                continue;
+            }
+            if !event::poll(Self::AWAIT_INTERVAL)? {
+                continue;
+            }
+            let Event::Key(event) = event::read()? else {
+                continue;
+            };
+            let skipped = event.kind == KeyEventKind::Press
+                && skip.is_some_and(|key| event.code == KeyCode::Char(key));
+            if (self.pause_blocked(&event)?.is_some() && self.pause.is_quitting()) || skipped {
+                audio.borrow_mut().clear_awaits();
+                return Ok(());
+            }
+        }
+    }
 }
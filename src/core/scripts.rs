@@ -1,40 +1,269 @@
 use std::{
-    collections::HashMap,
-    time::{self, SystemTime},
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    time::{self, Duration, SystemTime},
 };
 
 use anyhow::{anyhow, Context as ContextTrait, Result};
-use rand::{thread_rng, Rng};
 use result::OptionResultExt;
-use rlua::{Chunk, Context, Function, Lua, Table};
+use rlua::{
+    Chunk, Context, FromLuaMulti, Function, Lua, StdLib, Table, Thread, ThreadStatus, ToLuaMulti,
+    Value,
+};
 
-use crate::loading::loader::{Loader, RawContents};
+use crate::loading::{
+    loader::{Loader, RawContents},
+    protection::SaveCodec,
+    saves::SaveManager,
+};
 
-use super::context::TextContext;
+use super::{
+    audio::SoundAction,
+    context::TextContext,
+    state::{
+        random::SeededRandom,
+        store::{Store, StoreValue},
+    },
+    text::templating::TemplatableString,
+};
+
+/// A request yielded by a [sequence](Scripts::start_sequence) for the game loop to carry out before resuming it.
+pub enum SequenceStep {
+    /// Prints a line of text.
+    Say(String),
+    /// Submits a sound action.
+    Sound(Box<SoundAction>),
+    /// Waits for some time.
+    Wait(Duration),
+    /// Waits until a channel has no sound left to play.
+    WaitSound(String),
+    /// Waits until the player presses a key.
+    WaitKey,
+}
 
 #[derive(Debug)]
 /// A container for script files and script running context.
 pub struct Scripts {
     pub files: RawContents,
     pub lua: Lua,
+    /// Whether the Lua object only has the [sandboxed libraries](Self::SANDBOX_LIBS).
+    sandbox: bool,
+    /// The [functions defined](Self::defined_functions) by each script checked so far, or why they couldn't be found.
+    defined: RefCell<HashMap<String, Result<HashSet<String>, String>>>,
 }
 
 impl Scripts {
-    /// Loads all scripts from the `scripts` directory and creates a new [`Lua`] object.
-    pub fn load(loader: &Loader) -> Result<Self> {
+    /// The name of the Lua registry table mapping channel names to their [`on_finish`](Self::add_audio_fns) callbacks.
+    const FINISH_CALLBACKS: &'static str = "nage_on_finish";
+    /// The name of the Lua registry value holding the coroutine of the running [sequence](Self::start_sequence).
+    const SEQUENCE: &'static str = "nage_sequence";
+    /// The Lua source of the `sequence` table available to sequences; each function yields a [`SequenceStep`].
+    const SEQUENCE_FNS: &'static str = r#"
+        local sequence = {}
+        function sequence.say(text) coroutine.yield({ kind = "say", text = tostring(text) }) end
+        function sequence.play(action) coroutine.yield({ kind = "sound", action = action }) end
+        function sequence.wait(ms) coroutine.yield({ kind = "wait", ms = ms }) end
+        function sequence.wait_sound(channel) coroutine.yield({ kind = "wait_sound", channel = channel }) end
+        function sequence.wait_key() coroutine.yield({ kind = "wait_key" }) end
+        return sequence
+    "#;
+    /// The name of the Lua registry sequence of achievement IDs passed to [`unlock`](Self::add_player_fns).
+    const UNLOCKS: &'static str = "nage_unlocks";
+    /// The name of the Lua registry table mapping store keys to the values passed to [`set_var`](Self::add_player_fns).
+    const STORE_WRITES: &'static str = "nage_store_writes";
+    /// The name of the Lua registry sequence of save slots passed to [`save`](Self::add_player_fns).
+    const SAVES: &'static str = "nage_saves";
+    /// The name of the Lua registry table mapping channel names to whether they were last [`enable`](Self::add_audio_fns)d or disabled.
+    const CHANNEL_TOGGLES: &'static str = "nage_channel_toggles";
+    /// The name of the Lua registry value holding the save slot passed to [`load`](Self::add_player_fns).
+    const LOAD: &'static str = "nage_load";
+    /// The name of the Lua registry value holding the prompt path passed to [`jump`](Self::add_player_fns).
+    const JUMP: &'static str = "nage_jump";
+    /// The name of the Lua registry value holding the [`SeededRandom`] state drawn from by [`random`](Self::add_random).
+    const RANDOM: &'static str = "nage_random";
+
+    /// The standard libraries available to sandboxed scripts.
+    ///
+    /// `io`, `os`, `package` (and with it `require`), and `debug` are left out, as are the `dofile` and `loadfile`
+    /// base functions, so that scripts can't touch the filesystem or spawn processes. This leaves the base functions,
+    /// `coroutine`, `table`, `string`, `utf8`, and `math`.
+    const SANDBOX_LIBS: StdLib = StdLib::BASE
+        .union(StdLib::COROUTINE)
+        .union(StdLib::TABLE)
+        .union(StdLib::STRING)
+        .union(StdLib::UTF8)
+        .union(StdLib::MATH);
+    /// The base functions removed from sandboxed scripts.
+    const SANDBOX_REMOVED: [&'static str; 2] = ["dofile", "loadfile"];
+    /// The globals the game adds before running a script, which are stubbed when [checking its functions](Self::defined_functions).
+    const GAME_GLOBALS: [&'static str; 9] = [
+        "notes",
+        "variables",
+        "inventory",
+        "nage",
+        "player",
+        "audio",
+        "rng",
+        "random",
+        "time",
+    ];
+    /// The globals that reach outside of a script, which are stubbed when [checking its functions](Self::defined_functions)
+    /// if the game's Lua object has them.
+    const EFFECT_GLOBALS: [&'static str; 8] = [
+        "io", "os", "package", "debug", "require", "dofile", "loadfile", "print",
+    ];
+    /// The Lua source of a stub that accepts any use and does nothing, standing in for globals when
+    /// [checking a script's functions](Self::defined_functions).
+    const STUB: &'static str = r#"
+        local stub = {}
+        local function get() return stub end
+        setmetatable(stub, {
+            __index = get, __newindex = function() end, __call = get,
+            __add = get, __sub = get, __mul = get, __div = get, __mod = get, __pow = get, __unm = get, __idiv = get,
+            __band = get, __bor = get, __bxor = get, __shl = get, __shr = get, __bnot = get,
+            __concat = function() return "" end, __len = function() return 0 end,
+            __lt = function() return false end, __le = function() return false end,
+        })
+        return stub
+    "#;
+
+    /// Loads all scripts from the `scripts` directory with [`Scripts::new`].
+    pub fn load(loader: &Loader, sandbox: bool) -> Result<Self> {
+        Self::new(loader.load_raw_content("scripts")?, sandbox)
+    }
+
+    /// Creates a new [`Lua`] object for the script `files`.
+    ///
+    /// If `sandbox` is set, only the [sandboxed libraries](Self::SANDBOX_LIBS) are loaded.
+    pub fn new(files: RawContents, sandbox: bool) -> Result<Self> {
         let result = Scripts {
-            files: loader.load_raw_content("scripts")?,
-            lua: Lua::new(),
+            files,
+            lua: Self::create_lua(sandbox)?,
+            sandbox,
+            defined: RefCell::new(HashMap::new()),
         };
         Ok(result)
     }
 
-    /// Modifies a Lua [`Context`] to ensure stateful randomness between different loaded contexts.
-    fn random_seed(&self, context: &Context) -> Result<(), rlua::Error> {
-        let fake_time: u32 = thread_rng().gen();
-        context
-            .load(&format!("math.randomseed({fake_time})"))
-            .exec()
+    fn create_lua(sandbox: bool) -> Result<Lua> {
+        if sandbox {
+            Self::sandboxed()
+        } else {
+            Ok(Lua::new())
+        }
+    }
+
+    /// Creates a [`Lua`] object with only the [sandboxed libraries](Self::SANDBOX_LIBS).
+    fn sandboxed() -> Result<Lua> {
+        let lua = Lua::new_with(Self::SANDBOX_LIBS);
+        lua.context(|lua_ctx| {
+            let globals = lua_ctx.globals();
+            for name in Self::SANDBOX_REMOVED {
+                globals.set(name, Value::Nil)?;
+            }
+            Ok(())
+        })
+        .map_err(|err: rlua::Error| anyhow!(err))?;
+        Ok(lua)
+    }
+
+    /// Draws a value like `math.random` would: a float in `[0, 1)` if there are no bounds,
+    /// an integer in `[1, m]` for `m`, or an integer in `[m, n]` for `m` and `n`.
+    fn draw<'a>(
+        random: &SeededRandom,
+        m: Option<i64>,
+        n: Option<i64>,
+    ) -> Result<Value<'a>, rlua::Error> {
+        let (low, high) = match (m, n) {
+            (None, _) => {
+                let value = (random.next() >> 11) as f64 / (1u64 << 53) as f64;
+                return Ok(Value::Number(value));
+            }
+            (Some(high), None) => (1, high),
+            (Some(low), Some(high)) => (low, high),
+        };
+        if low > high {
+            return Err(rlua::Error::external(anyhow!("interval [{low}, {high}] is empty")));
+        }
+        Ok(Value::Integer(low + random.below((high - low + 1) as usize) as i64))
+    }
+
+    /// The [`SeededRandom`] that scripts are currently drawing from.
+    fn script_random(context: &Context) -> Result<SeededRandom, rlua::Error> {
+        let state: i64 = context.named_registry_value(Self::RANDOM)?;
+        Ok(SeededRandom::new(state as u64))
+    }
+
+    /// Adds a `random` function and an `rng` table drawing from the player's [`SeededRandom`], replacing `math.random` as well.
+    ///
+    /// Like `math.random`, `random()` returns a float in `[0, 1)`, `random(m)` an integer in `[1, m]`,
+    /// and `random(m, n)` an integer in `[m, n]`. The generator state carries over between scripts
+    /// until it's [taken](Self::take_random_state) back to the player, so repeated calls keep drawing new values.
+    ///
+    /// The `rng` table holds the following functions:
+    /// - `seed(n)`: Reseeds the generator, which `math.randomseed` does as well. This affects all of the engine's randomness
+    ///   from then on, such as weighted jumps and sound groups, not just the script's.
+    /// - `int(m, n)`: Returns an integer in `[m, n]`, like `random(m, n)`.
+    /// - `peek(m, n)`: Returns what `random(m, n)` would without advancing the generator.
+    /// - `shuffle(t)`: Shuffles the sequence `t` in place and returns it.
+    fn add_random(&self, context: &Context, text_context: &TextContext) -> Result<(), rlua::Error> {
+        if context
+            .named_registry_value::<_, Option<i64>>(Self::RANDOM)?
+            .is_none()
+        {
+            context.set_named_registry_value(Self::RANDOM, text_context.random_state() as i64)?;
+        }
+        let random = context.create_function(|ctx, (m, n): (Option<i64>, Option<i64>)| {
+            let random = Self::script_random(&ctx)?;
+            let value = Self::draw(&random, m, n)?;
+            ctx.set_named_registry_value(Self::RANDOM, random.state() as i64)?;
+            Ok(value)
+        })?;
+        let seed = context
+            .create_function(|ctx, seed: i64| ctx.set_named_registry_value(Self::RANDOM, seed))?;
+        let int = context.create_function(|ctx, (m, n): (i64, i64)| {
+            let random = Self::script_random(&ctx)?;
+            let value = Self::draw(&random, Some(m), Some(n))?;
+            ctx.set_named_registry_value(Self::RANDOM, random.state() as i64)?;
+            Ok(value)
+        })?;
+        let peek = context.create_function(|ctx, (m, n): (Option<i64>, Option<i64>)| {
+            Self::draw(&Self::script_random(&ctx)?, m, n)
+        })?;
+        let shuffle = context.create_function(|ctx, table: Table| {
+            let random = Self::script_random(&ctx)?;
+            let mut values: Vec<Value> =
+                table.clone().sequence_values().collect::<Result<_, _>>()?;
+            random.shuffle(&mut values);
+            for (index, value) in values.into_iter().enumerate() {
+                table.set(index + 1, value)?;
+            }
+            ctx.set_named_registry_value(Self::RANDOM, random.state() as i64)?;
+            Ok(table)
+        })?;
+        let rng = context.create_table()?;
+        rng.set("seed", seed.clone())?;
+        rng.set("int", int)?;
+        rng.set("peek", peek)?;
+        rng.set("shuffle", shuffle)?;
+        let globals = context.globals();
+        if let Some(math) = globals.get::<_, Option<Table>>("math")? {
+            math.set("random", random.clone())?;
+            math.set("randomseed", seed)?;
+        }
+        globals.set("rng", rng)?;
+        globals.set("random", random)
+    }
+
+    /// Removes and returns the random generator state that scripts have advanced to since the last call, if any.
+    pub fn take_random_state(&self) -> Result<Option<u64>> {
+        self.lua
+            .context(|lua_ctx| {
+                let state = lua_ctx.named_registry_value::<_, Option<i64>>(Self::RANDOM)?;
+                lua_ctx.unset_named_registry_value(Self::RANDOM)?;
+                Ok(state.map(|state| state as u64))
+            })
+            .map_err(|err: rlua::Error| anyhow!(err))
     }
 
     /// Adds global values to the specified [`Context`] based on the text context.
@@ -42,8 +271,10 @@ impl Scripts {
     /// The following values are added:
     /// - A `notes` sequence based on the player [`Notes`]
     /// - A `variables` table based on the player [`Variables`]
+    /// - An `inventory` table based on the player [`Inventory`](crate::core::state::inventory::Inventory)
     /// - A `nage` globals table based on the global variables
-    /// - An `audio` table mapping channels to their data
+    /// - A `player` table containing the functions from [`add_player_fns`](Self::add_player_fns)
+    /// - An `audio` table mapping channels to their data, along with the functions from [`add_audio_fns`](Self::add_audio_fns)
     ///
     /// Player data values do not represent the data itself and are merely snapshots of the data.
     /// Scripts cannot modify data directly and must instead be used in other central systems.
@@ -62,13 +293,20 @@ impl Scripts {
         let vars_table = context.create_table_from(text_context.variables.clone())?;
         context.globals().set("notes", notes_seq)?;
         context.globals().set("variables", vars_table)?;
+        let inventory_table = context.create_table_from(text_context.inventory.clone())?;
+        context.globals().set("inventory", inventory_table)?;
         context
             .globals()
             .set("nage", text_context.create_variable_table(context)?)?;
+        let player_table = context.create_table()?;
+        self.add_player_fns(context, &player_table, text_context)?;
+        context.globals().set("player", player_table)?;
         if let Some(audio) = &text_context.resources().audio {
-            context
-                .globals()
-                .set("audio", audio.create_audio_table(context)?)?;
+            let audio = audio.borrow();
+            let table = audio.create_audio_table(context)?;
+            let channels = audio.channels.keys().cloned().collect();
+            self.add_audio_fns(context, &table, channels)?;
+            context.globals().set("audio", table)?;
         }
         Ok(())
     }
@@ -85,9 +323,384 @@ impl Scripts {
         context.globals().set("time", time)
     }
 
+    /// Adds functions to the `audio` table that let scripts react to playback.
+    ///
+    /// The following functions are added:
+    /// - `on_finish(channel, callback)`: Calls `callback` once the next time `channel` runs out of sounds to play.
+    ///   Registering another callback for the same channel replaces the first, and skipping or stopping the channel discards it.
+    /// - `enable(channel)`: Turns `channel` on for the player once the script finishes running, resuming it if it was paused by `disable`.
+    /// - `disable(channel)`: Turns `channel` off for the player once the script finishes running, pausing it where it is.
+    ///
+    /// `enable` and `disable` raise an error if `channel` isn't one of the loaded `channels`. Like the `channel` command,
+    /// the change is saved with the player, so a channel turned off stays off until something turns it back on.
+    fn add_audio_fns<'a>(
+        &self,
+        context: &Context<'a>,
+        table: &Table<'a>,
+        channels: HashSet<String>,
+    ) -> Result<(), rlua::Error> {
+        let on_finish =
+            context.create_function(|ctx, (channel, callback): (String, Function)| {
+                let callbacks = match ctx
+                    .named_registry_value::<_, Option<Table>>(Self::FINISH_CALLBACKS)?
+                {
+                    Some(callbacks) => callbacks,
+                    None => {
+                        let callbacks = ctx.create_table()?;
+                        ctx.set_named_registry_value(Self::FINISH_CALLBACKS, callbacks.clone())?;
+                        callbacks
+                    }
+                };
+                callbacks.set(channel, callback)
+            })?;
+        table.set("on_finish", on_finish)?;
+        let enabled = channels.clone();
+        let enable = context.create_function(move |ctx, channel: String| {
+            Self::toggle_channel(&ctx, &enabled, channel, true)
+        })?;
+        let disable = context.create_function(move |ctx, channel: String| {
+            Self::toggle_channel(&ctx, &channels, channel, false)
+        })?;
+        table.set("enable", enable)?;
+        table.set("disable", disable)
+    }
+
+    /// Records a request to turn a channel on or off, as long as it's one of the loaded `channels`.
+    fn toggle_channel(
+        context: &Context,
+        channels: &HashSet<String>,
+        channel: String,
+        enabled: bool,
+    ) -> Result<(), rlua::Error> {
+        if !channels.contains(&channel) {
+            return Err(rlua::Error::external(anyhow!("Invalid sound channel '{channel}'")));
+        }
+        let toggles =
+            match context.named_registry_value::<_, Option<Table>>(Self::CHANNEL_TOGGLES)? {
+                Some(toggles) => toggles,
+                None => {
+                    let toggles = context.create_table()?;
+                    context.set_named_registry_value(Self::CHANNEL_TOGGLES, toggles.clone())?;
+                    toggles
+                }
+            };
+        toggles.set(channel, enabled)
+    }
+
+    /// Adds functions to the `player` table that request changes to player data.
+    ///
+    /// The following functions are added:
+    /// - `unlock(id)`: Unlocks the achievement with the given ID once the script finishes running.
+    /// - `get_var(name)`: Returns the [`Store`] value for `name`, or `nil` if it's unset.
+    /// - `set_var(name, value)`: Sets a [`Store`] value to a boolean, integer, or string once the script finishes running.
+    ///   Later `get_var` calls see the new value right away.
+    /// - `save(slot)`: Writes the player to the save file `slot` once the script finishes running.
+    /// - `load(slot)`: Loads the save file `slot` once the script finishes running, leaving the current prompt.
+    ///   Refused while a [sequence](Self::start_sequence) is running.
+    /// - `jump(path)`: Jumps to the prompt at `path` once the script finishes running, which is relative to the current prompt's file
+    ///   unless it names one. Only carried out for [prompt hooks](crate::core::prompt::Prompt::on_enter); other scripts' jumps are ignored.
+    ///
+    /// `save` and `load` return `true` if the request was accepted, or `false` and an error message otherwise.
+    fn add_player_fns<'a>(
+        &self,
+        context: &Context<'a>,
+        table: &Table<'a>,
+        text_context: &TextContext,
+    ) -> Result<(), rlua::Error> {
+        let store = &text_context.store;
+        let unlock = context.create_function(|ctx, id: String| {
+            let unlocks = match ctx.named_registry_value::<_, Option<Table>>(Self::UNLOCKS)? {
+                Some(unlocks) => unlocks,
+                None => {
+                    let unlocks = ctx.create_table()?;
+                    ctx.set_named_registry_value(Self::UNLOCKS, unlocks.clone())?;
+                    unlocks
+                }
+            };
+            unlocks.set(unlocks.len()? + 1, id)
+        })?;
+        table.set("unlock", unlock)?;
+        let snapshot = store.clone();
+        let get_var = context.create_function(move |ctx, name: String| {
+            let written = ctx
+                .named_registry_value::<_, Option<Table>>(Self::STORE_WRITES)?
+                .map(|writes| writes.get::<_, Option<StoreValue>>(name.as_str()))
+                .transpose()?
+                .flatten();
+            Ok(written.or_else(|| snapshot.get(&name).cloned()))
+        })?;
+        table.set("get_var", get_var)?;
+        let set_var = context.create_function(|ctx, (name, value): (String, StoreValue)| {
+            let writes = match ctx.named_registry_value::<_, Option<Table>>(Self::STORE_WRITES)? {
+                Some(writes) => writes,
+                None => {
+                    let writes = ctx.create_table()?;
+                    ctx.set_named_registry_value(Self::STORE_WRITES, writes.clone())?;
+                    writes
+                }
+            };
+            writes.set(name, value)
+        })?;
+        table.set("set_var", set_var)?;
+        let save = context.create_function(|ctx, slot: String| {
+            if let Err(err) = SaveManager::slot_file(&slot) {
+                return Ok((false, Some(format!("{err:#}"))));
+            }
+            let saves = match ctx.named_registry_value::<_, Option<Table>>(Self::SAVES)? {
+                Some(saves) => saves,
+                None => {
+                    let saves = ctx.create_table()?;
+                    ctx.set_named_registry_value(Self::SAVES, saves.clone())?;
+                    saves
+                }
+            };
+            saves.set(saves.len()? + 1, slot)?;
+            Ok((true, None))
+        })?;
+        table.set("save", save)?;
+        let dir = SaveManager::game_dir(text_context.config()).map_err(rlua::Error::external)?;
+        let codec = SaveCodec::new(text_context.config());
+        let load = context.create_function(move |ctx, slot: String| {
+            if ctx
+                .named_registry_value::<_, Option<Thread>>(Self::SEQUENCE)?
+                .is_some()
+            {
+                return Ok((false, Some("can't load while a sequence is running".to_owned())));
+            }
+            if let Err(err) = SaveManager::check_slot(&dir, &slot, &codec) {
+                return Ok((false, Some(format!("{err:#}"))));
+            }
+            ctx.set_named_registry_value(Self::LOAD, slot)?;
+            Ok((true, None))
+        })?;
+        table.set("load", load)?;
+        let jump = context
+            .create_function(|ctx, path: String| ctx.set_named_registry_value(Self::JUMP, path))?;
+        table.set("jump", jump)
+    }
+
+    /// Removes and returns the save slots that scripts have requested to [`save`](Self::add_player_fns) to since the last call.
+    pub fn take_saves(&self) -> Result<Vec<String>> {
+        self.lua
+            .context(|lua_ctx| {
+                let Some(saves) = lua_ctx.named_registry_value::<_, Option<Table>>(Self::SAVES)?
+                else {
+                    return Ok(Vec::new());
+                };
+                lua_ctx.unset_named_registry_value(Self::SAVES)?;
+                saves.sequence_values().collect()
+            })
+            .map_err(|err: rlua::Error| anyhow!(err))
+    }
+
+    /// Removes and returns the save slot that scripts last requested to [`load`](Self::add_player_fns), if any.
+    pub fn take_load(&self) -> Result<Option<String>> {
+        self.lua
+            .context(|lua_ctx| {
+                let slot = lua_ctx.named_registry_value::<_, Option<String>>(Self::LOAD)?;
+                lua_ctx.unset_named_registry_value(Self::LOAD)?;
+                Ok(slot)
+            })
+            .map_err(|err: rlua::Error| anyhow!(err))
+    }
+
+    /// Removes and returns the prompt path that scripts last requested to [`jump`](Self::add_player_fns) to, if any.
+    pub fn take_jump(&self) -> Result<Option<String>> {
+        self.lua
+            .context(|lua_ctx| {
+                let path = lua_ctx.named_registry_value::<_, Option<String>>(Self::JUMP)?;
+                lua_ctx.unset_named_registry_value(Self::JUMP)?;
+                Ok(path)
+            })
+            .map_err(|err: rlua::Error| anyhow!(err))
+    }
+
+    /// Removes and returns the [`Store`] values that scripts have [`set_var`](Self::add_player_fns)ed since the last call.
+    pub fn take_store_writes(&self) -> Result<Store> {
+        self.lua
+            .context(|lua_ctx| {
+                let Some(writes) =
+                    lua_ctx.named_registry_value::<_, Option<Table>>(Self::STORE_WRITES)?
+                else {
+                    return Ok(Store::new());
+                };
+                lua_ctx.unset_named_registry_value(Self::STORE_WRITES)?;
+                writes.pairs().collect()
+            })
+            .map_err(|err: rlua::Error| anyhow!(err))
+    }
+
+    /// Removes and returns the channels that scripts have [`enable`](Self::add_audio_fns)d or disabled since the last call,
+    /// mapped to whether they were last enabled.
+    pub fn take_channel_toggles(&self) -> Result<HashMap<String, bool>> {
+        self.lua
+            .context(|lua_ctx| {
+                let Some(toggles) =
+                    lua_ctx.named_registry_value::<_, Option<Table>>(Self::CHANNEL_TOGGLES)?
+                else {
+                    return Ok(HashMap::new());
+                };
+                lua_ctx.unset_named_registry_value(Self::CHANNEL_TOGGLES)?;
+                toggles.pairs().collect()
+            })
+            .map_err(|err: rlua::Error| anyhow!(err))
+    }
+
+    /// Removes and returns the achievement IDs that scripts have [`unlock`](Self::add_player_fns)ed since the last call.
+    pub fn take_unlocks(&self) -> Result<Vec<String>> {
+        self.lua
+            .context(|lua_ctx| {
+                let Some(unlocks) =
+                    lua_ctx.named_registry_value::<_, Option<Table>>(Self::UNLOCKS)?
+                else {
+                    return Ok(Vec::new());
+                };
+                lua_ctx.unset_named_registry_value(Self::UNLOCKS)?;
+                unlocks.sequence_values().collect()
+            })
+            .map_err(|err: rlua::Error| anyhow!(err))
+    }
+
+    /// Removes and returns the [`on_finish`](Self::add_audio_fns) callback registered for a channel, if any.
+    fn take_finish_callback<'a>(
+        context: &Context<'a>,
+        channel: &str,
+    ) -> Result<Option<Function<'a>>, rlua::Error> {
+        let Some(callbacks) =
+            context.named_registry_value::<_, Option<Table>>(Self::FINISH_CALLBACKS)?
+        else {
+            return Ok(None);
+        };
+        let callback = callbacks.get(channel)?;
+        callbacks.set(channel, Value::Nil)?;
+        Ok(callback)
+    }
+
+    /// Calls and discards the [`on_finish`](Self::add_audio_fns) callback registered for a channel, if any.
+    pub fn fire_finish_callback(&self, channel: &str) -> Result<()> {
+        self.lua
+            .context(|lua_ctx| match Self::take_finish_callback(&lua_ctx, channel)? {
+                Some(callback) => callback.call(()),
+                None => Ok(()),
+            })
+            .with_context(|| anyhow!("failed to run finish callback for channel '{channel}'"))
+            .map_err(|err| {
+                log::error!("Script error: {err:?}");
+                err
+            })
+    }
+
+    /// Discards the [`on_finish`](Self::add_audio_fns) callback registered for a channel, if any.
+    pub fn clear_finish_callback(&self, channel: &str) -> Result<()> {
+        self.lua
+            .context(|lua_ctx| Self::take_finish_callback(&lua_ctx, channel).map(|_| ()))
+            .map_err(|err| anyhow!(err))
+    }
+
+    /// Starts a script as a sequence: a coroutine that can pause to let the game loop play sounds, print text,
+    /// or wait, via the functions of a global `sequence` table:
+    /// - `say(text)`: Prints a line of text.
+    /// - `play(action)`: Submits a sound action, given as a table with the same keys as in prompt files.
+    /// - `wait(ms)`: Waits for some milliseconds.
+    /// - `wait_sound(channel)`: Waits until `channel` has no sound left to play.
+    /// - `wait_key()`: Waits until the player presses a key.
+    ///
+    /// Each function yields a [`SequenceStep`]; the sequence is then driven by [`resume_sequence`](Self::resume_sequence).
+    /// Starting a sequence replaces any that is already running.
+    pub fn start_sequence(&self, file: &str, text_context: &TextContext) -> Result<()> {
+        let (name, func) = Self::file_components(file);
+        let script = self
+            .files
+            .get(name)
+            .ok_or(anyhow!("Invalid script '{name}'"))?;
+        self.lua
+            .context(|lua_ctx| {
+                self.add_random(&lua_ctx, text_context)?;
+                self.add_globals(&lua_ctx, text_context)?;
+                self.add_fns(&lua_ctx)?;
+                let sequence: Table = lua_ctx.load(Self::SEQUENCE_FNS).eval()?;
+                lua_ctx.globals().set("sequence", sequence)?;
+                let loaded = lua_ctx.load(script);
+                let function: Function = match func {
+                    Some(func) => loaded.eval::<Table>()?.get(func)?,
+                    None => loaded.into_function()?,
+                };
+                let thread = lua_ctx.create_thread(function)?;
+                lua_ctx.set_named_registry_value(Self::SEQUENCE, thread)
+            })
+            .with_context(|| anyhow!("failed to start script sequence {file}"))
+    }
+
+    /// Resumes the running sequence until it yields its next [`SequenceStep`].
+    ///
+    /// Returns [`None`] once the sequence has finished or if none is running. If the sequence errors, it's discarded.
+    pub fn resume_sequence(&self) -> Result<Option<SequenceStep>> {
+        self.lua.context(|lua_ctx| {
+            let Some(thread) = lua_ctx.named_registry_value::<_, Option<Thread>>(Self::SEQUENCE)?
+            else {
+                return Ok(None);
+            };
+            let resumed = match thread.status() {
+                ThreadStatus::Resumable => thread.resume::<_, Value>(()),
+                _ => Ok(Value::Nil),
+            };
+            if let Err(err) = &resumed {
+                log::error!("Script sequence error: {err}");
+            }
+            if resumed.is_err() || thread.status() != ThreadStatus::Resumable {
+                lua_ctx.unset_named_registry_value(Self::SEQUENCE)?;
+            }
+            let value = resumed?;
+            if thread.status() != ThreadStatus::Resumable {
+                return Ok(None);
+            }
+            Self::sequence_step(value).map(Some)
+        })
+    }
+
+    /// Parses a value yielded by a sequence into a [`SequenceStep`].
+    fn sequence_step(value: Value) -> Result<SequenceStep> {
+        let Value::Table(table) = value else {
+            return Err(anyhow!("sequences can only yield through the `sequence` functions"));
+        };
+        let kind: String = table.get("kind")?;
+        let step = match kind.as_str() {
+            "say" => SequenceStep::Say(table.get("text")?),
+            "sound" => SequenceStep::Sound(Box::new(Self::sound_action(table.get("action")?)?)),
+            "wait" => SequenceStep::Wait(Duration::from_millis(table.get("ms")?)),
+            "wait_sound" => SequenceStep::WaitSound(table.get("channel")?),
+            "wait_key" => SequenceStep::WaitKey,
+            other => return Err(anyhow!("unknown sequence step '{other}'")),
+        };
+        Ok(step)
+    }
+
+    /// Converts a Lua table into a [`SoundAction`], deserializing it as if it were written in a prompt file.
+    fn sound_action(table: Table) -> Result<SoundAction> {
+        let mut mapping = serde_yaml::Mapping::new();
+        for pair in table.pairs::<String, Value>() {
+            let (key, value) = pair?;
+            let value = match value {
+                Value::Boolean(value) => value.to_string(),
+                Value::Integer(value) => value.to_string(),
+                Value::Number(value) => value.to_string(),
+                Value::String(value) => value.to_str()?.to_owned(),
+                other => {
+                    return Err(anyhow!(
+                        "sound action key '{key}' can't be a {}",
+                        other.type_name()
+                    ))
+                }
+            };
+            mapping.insert(key.into(), value.into());
+        }
+        serde_yaml::from_value(serde_yaml::Value::Mapping(mapping))
+            .with_context(|| anyhow!("invalid sound action"))
+    }
+
     /// Given a file string, splits it based on the function delimiter character `:`.
     /// If there is no function delimiter, returns only the file name.
-    fn file_components(file: &str) -> (&str, Option<&str>) {
+    pub fn file_components(file: &str) -> (&str, Option<&str>) {
         let components = file.split_once(":");
         match components {
             Some((f, func)) => (f, Some(func)),
@@ -95,6 +708,88 @@ impl Scripts {
         }
     }
 
+    /// Validates a script reference, such as a prompt's `sequence`: the file must exist, and any function it names
+    /// must be [defined](Self::validate_function).
+    pub fn validate_reference(&self, reference: &str) -> Result<()> {
+        let (name, func) = Self::file_components(reference);
+        if !self.files.contains_key(name) {
+            return Err(anyhow!("Invalid script '{name}'"));
+        }
+        match func {
+            Some(func) => self.validate_function(name, func),
+            None => Ok(()),
+        }
+    }
+
+    /// Validates every `(file:function)` script segment in a [`TemplatableString`] whose file exists,
+    /// since other parentheses may just be part of the text.
+    pub fn validate_segments(&self, string: &TemplatableString) -> Result<()> {
+        for segment in string.script_segments() {
+            if let (name, Some(func)) = Self::file_components(segment) {
+                if self.files.contains_key(name) {
+                    self.validate_function(name, func)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates that the script `name` returns a table [defining](Self::defined_functions) the function `func`.
+    fn validate_function(&self, name: &str, func: &str) -> Result<()> {
+        if !self.defined_functions(name)?.contains(func) {
+            return Err(anyhow!("Script '{name}' doesn't define a function '{func}'"));
+        }
+        Ok(())
+    }
+
+    /// Finds the names of the functions in the table that the script `name` returns.
+    ///
+    /// The script is evaluated in a separate [`Lua`] object with the same libraries as the game's, where the
+    /// [game's globals](Self::GAME_GLOBALS) and any [outside-reaching ones](Self::EFFECT_GLOBALS) are [stubs](Self::STUB),
+    /// so that its top-level code has no effects. Functions aren't called. Each script is only evaluated once.
+    fn defined_functions(&self, name: &str) -> Result<HashSet<String>> {
+        if let Some(defined) = self.defined.borrow().get(name) {
+            return defined.clone().map_err(|err| anyhow!(err));
+        }
+        let defined = self
+            .find_functions(name)
+            .with_context(|| anyhow!("Failed to evaluate script '{name}'"))
+            .map_err(|err| format!("{err:#}"));
+        self.defined
+            .borrow_mut()
+            .insert(name.to_owned(), defined.clone());
+        defined.map_err(|err| anyhow!(err))
+    }
+
+    fn find_functions(&self, name: &str) -> Result<HashSet<String>> {
+        let script = self
+            .files
+            .get(name)
+            .ok_or(anyhow!("Invalid script '{name}'"))?;
+        Self::create_lua(self.sandbox)?
+            .context(|lua_ctx| {
+                let stub: Table = lua_ctx.load(Self::STUB).eval()?;
+                let globals = lua_ctx.globals();
+                for global in Self::EFFECT_GLOBALS {
+                    if !matches!(globals.get(global)?, Value::Nil) {
+                        globals.set(global, stub.clone())?;
+                    }
+                }
+                for global in Self::GAME_GLOBALS {
+                    globals.set(global, stub.clone())?;
+                }
+                let table: Table = lua_ctx.load(script).eval()?;
+                let mut defined = HashSet::new();
+                for pair in table.pairs::<Value, Value>() {
+                    if let (Value::String(key), Value::Function(_)) = pair? {
+                        defined.insert(key.to_str()?.to_owned());
+                    }
+                }
+                Ok(defined)
+            })
+            .map_err(|err: rlua::Error| anyhow!(err))
+    }
+
     /// Given a loaded Lua chunk, and an optional function name, evaluates the result.
     fn eval(loaded: Chunk, func: Option<&str>) -> Result<String, rlua::Error> {
         match func {
@@ -107,19 +802,104 @@ impl Scripts {
         }
     }
 
+    /// Runs a script resource for its side effects, such as requesting changes to player data,
+    /// given a filename, the arguments to pass it, and text context.
+    ///
+    /// With a function name, the script should return a table containing that function, which is called with `args`.
+    /// Otherwise, the script itself receives them as `...`. Either way, what it returns is converted to `R`.
+    pub fn run<A, R>(&self, file: &str, args: A, text_context: &TextContext) -> Result<R>
+    where
+        A: for<'lua> ToLuaMulti<'lua>,
+        R: for<'lua> FromLuaMulti<'lua>,
+    {
+        let (name, func) = Self::file_components(file);
+        let script = self
+            .files
+            .get(name)
+            .ok_or(anyhow!("Invalid script '{name}'"))?;
+        self.lua
+            .context(|lua_ctx| {
+                self.add_random(&lua_ctx, text_context)?;
+                self.add_globals(&lua_ctx, text_context)?;
+                self.add_fns(&lua_ctx)?;
+                let loaded = lua_ctx.load(script);
+                let function: Function = match func {
+                    Some(func) => loaded.eval::<Table>()?.get(func)?,
+                    None => loaded.into_function()?,
+                };
+                function.call(args)
+            })
+            .with_context(|| anyhow!("failed to run script {file}"))
+            .map_err(|err| {
+                log::error!("Script error: {err:?}");
+                err
+            })
+    }
+
     /// Evaluates a script resource given a filename and text context.
     pub fn get(&self, file: &str, text_context: &TextContext) -> Result<Option<String>> {
         let components = Self::file_components(file);
         let result = self.files.get(components.0).map(|script| {
             self.lua.context(|lua_ctx| {
-                self.random_seed(&lua_ctx)?;
+                self.add_random(&lua_ctx, text_context)?;
                 self.add_globals(&lua_ctx, text_context)?;
                 self.add_fns(&lua_ctx)?;
                 let loaded = lua_ctx.load(script);
                 Self::eval(loaded, components.1)
                     .with_context(|| anyhow!("failed to evaluate script component {file}"))
+                    .map_err(|err| {
+                        log::error!("Script error: {err:?}");
+                        err
+                    })
             })
         });
         Ok(result.invert()?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scripts(source: &str, sandbox: bool) -> Scripts {
+        let files = RawContents::from([("main".to_owned(), source.to_owned())]);
+        Scripts::new(files, sandbox).unwrap()
+    }
+
+    #[test]
+    fn finds_defined_functions() {
+        let scripts = scripts("local M = {}\nfunction M.greet() end\nM.name = 'x'\nreturn M", true);
+        assert!(scripts.validate_reference("main:greet").is_ok());
+        let err = scripts.validate_reference("main:name").unwrap_err();
+        assert!(err.to_string().contains("doesn't define"), "{err}");
+        assert!(scripts.validate_reference("missing:greet").is_err());
+    }
+
+    #[test]
+    fn stubs_game_globals() {
+        let source = "local greeting = 'Hi, ' .. variables.name\n\
+                      if notes.met then player.unlock('a') end\n\
+                      return { greet = function() return greeting end }";
+        assert!(scripts(source, true)
+            .validate_reference("main:greet")
+            .is_ok());
+    }
+
+    #[test]
+    fn keeps_effects_out_of_validation() {
+        let path = std::env::temp_dir().join("nage_validation_effect");
+        let _ = std::fs::remove_file(&path);
+        let source = format!(
+            "io.open({:?}, 'w'):write('x')\nos.exit(1)\nreturn {{ run = function() end }}",
+            path.display().to_string()
+        );
+        assert!(scripts(&source, false)
+            .validate_reference("main:run")
+            .is_ok());
+        assert!(!path.exists());
+        // Sandboxed games don't have `io` at all, so neither does validation
+        assert!(scripts(&source, true)
+            .validate_reference("main:run")
+            .is_err());
+    }
+}
@@ -1,3 +1,9 @@
+pub mod achievements;
 pub mod info;
+pub mod inventory;
 pub mod notes;
+pub mod preferences;
+pub mod random;
+pub mod selection;
+pub mod store;
 pub mod variables;
@@ -0,0 +1,92 @@
+use std::collections::HashSet;
+
+use anyhow::{anyhow, Result};
+use crossterm::style::Stylize;
+use serde::Deserialize;
+
+use crate::core::{
+    context::{StaticContext, TextContext},
+    manifest::EntrypointSoundAction,
+    player::Player,
+};
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+/// An achievement defined in the manifest that scripts can unlock.
+pub struct Achievement {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    /// Whether the achievement is left out of the achievement list until it's unlocked.
+    pub hidden: bool,
+    /// The sounds to play when the achievement is unlocked.
+    sounds: Option<Vec<EntrypointSoundAction>>,
+}
+
+pub type Achievements = Vec<Achievement>;
+pub type UnlockedAchievements = HashSet<String>;
+
+impl Achievement {
+    /// Finds an achievement by its ID.
+    pub fn get<'a>(achievements: &'a Achievements, id: &str) -> Result<&'a Achievement> {
+        achievements
+            .iter()
+            .find(|achievement| achievement.id == id)
+            .ok_or(anyhow!("Invalid achievement '{id}'"))
+    }
+
+    /// The line displayed for this achievement in the achievement list.
+    fn line(&self) -> String {
+        match &self.description {
+            Some(description) => format!("{}: {description}", self.name),
+            None => self.name.clone(),
+        }
+    }
+
+    /// Prints a notification for this achievement being unlocked and plays its sounds, if any.
+    pub fn notify(
+        &self,
+        player: &Player,
+        stc: &StaticContext,
+        text_context: &TextContext,
+    ) -> Result<()> {
+        println!("{} {}\n", "Achievement unlocked:".green().bold(), self.name);
+        if let Some(sounds) = self.sounds.clone() {
+            let into: Vec<_> = sounds.into_iter().map(Into::into).collect();
+            stc.resources.submit_audio(player, &into, text_context)?;
+        }
+        Ok(())
+    }
+
+    /// Formats the achievement list, marking unlocked achievements and greying out locked ones.
+    ///
+    /// Locked hidden achievements are only counted.
+    pub fn list(achievements: &Achievements, unlocked: &UnlockedAchievements) -> String {
+        let mut lines: Vec<String> = Vec::new();
+        let mut hidden = 0;
+        for achievement in achievements {
+            if unlocked.contains(&achievement.id) {
+                lines.push(format!("+ {}", achievement.line()));
+            } else if achievement.hidden {
+                hidden += 1;
+            } else {
+                lines.push(format!("- {}", achievement.line()).dark_grey().to_string());
+            }
+        }
+        if hidden > 0 {
+            lines.push(format!("{hidden} hidden").dark_grey().to_string());
+        }
+        lines.join("\n")
+    }
+
+    pub fn validate_all(achievements: &Achievements) -> Result<()> {
+        let mut ids = HashSet::new();
+        for achievement in achievements {
+            if !ids.insert(&achievement.id) {
+                return Err(anyhow!("Duplicate achievement '{}'", achievement.id));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::core::{context::TextContext, text::templating::TemplatableValue};
+
+/// Item and currency names mapped to how many of each a player holds.
+pub type Inventory = HashMap<String, u64>;
+
+/// Item names mapped to the filled amounts consumed by a [`Cost`].
+pub type CostEntries = BTreeMap<String, u64>;
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(transparent)]
+/// Item names mapped to the amounts a [`Choice`](crate::core::choice::Choice) consumes when it's made.
+pub struct Cost(HashMap<String, TemplatableValue<u64>>);
+
+impl Cost {
+    /// Fills in each item amount.
+    pub fn fill(&self, text_context: &TextContext) -> Result<CostEntries> {
+        self.0
+            .iter()
+            .map(|(item, amount)| Ok((item.clone(), amount.get_value(text_context)?)))
+            .collect()
+    }
+}
+
+/// Describes the items that an [`Inventory`] lacks to pay for some [`CostEntries`], if any.
+///
+/// ### Examples
+///
+/// - `needs 10 gold`
+/// - `needs 2 key, 5 rope`
+pub fn shortfall(inventory: &Inventory, cost: &CostEntries) -> Option<String> {
+    let missing: Vec<String> = cost
+        .iter()
+        .filter(|(item, amount)| inventory.get(*item).copied().unwrap_or(0) < **amount)
+        .map(|(item, amount)| format!("{amount} {item}"))
+        .collect();
+    if missing.is_empty() {
+        return None;
+    }
+    Some(format!("needs {}", missing.join(", ")))
+}
+
+/// Removes the items in some [`CostEntries`] from an [`Inventory`], or adds them back if `reverse` is `true`.
+///
+/// The inventory is expected to afford the cost; see [`shortfall`].
+/// Items that run out are removed from the inventory entirely.
+pub fn apply_cost(inventory: &mut Inventory, cost: &CostEntries, reverse: bool) {
+    for (item, amount) in cost {
+        let held = inventory.get(item).copied().unwrap_or(0);
+        let held = if reverse {
+            held + amount
+        } else {
+            held.saturating_sub(*amount)
+        };
+        if held == 0 {
+            inventory.remove(item);
+        } else {
+            inventory.insert(item.clone(), held);
+        }
+    }
+}
@@ -150,7 +150,7 @@ impl NoteActions {
 /// A list of string symbols tracked on a player.
 pub type Notes = HashSet<String>;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct NoteEntry {
     pub value: String,
     pub take: bool,
@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::manifest::Manifest;
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default)]
+/// Settings the player has changed from within the game, overriding the manifest's.
+///
+/// Unset values follow the manifest, so that updating a game only changes what the player hasn't overridden.
+pub struct Preferences {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The master volume, from `0.0` to `1.0`.
+    pub master_volume: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// A factor that text is printed faster by, where `2.0` is twice as fast.
+    pub text_speed: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether prompts with a single choice are continued past on their own after a delay.
+    pub auto_advance: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether output is kept screen-reader-friendly; see [`Settings::accessibility`](crate::core::manifest::Settings::accessibility).
+    pub accessibility: Option<bool>,
+}
+
+impl Preferences {
+    /// The text speed factors to choose from.
+    pub const TEXT_SPEEDS: [f32; 6] = [0.5, 0.75, 1.0, 1.5, 2.0, 4.0];
+
+    /// Whether no setting has been changed.
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// The master volume, falling back to the manifest's.
+    pub fn master_volume(&self, config: &Manifest) -> f32 {
+        self.master_volume.unwrap_or(config.settings.master_volume)
+    }
+
+    /// Whether auto-advance is on, falling back to the manifest's.
+    pub fn auto_advance(&self, config: &Manifest) -> bool {
+        self.auto_advance
+            .unwrap_or(config.settings.text.auto_advance.enabled)
+    }
+
+    /// Whether accessibility mode is on, falling back to the manifest's.
+    pub fn accessibility(&self, config: &Manifest) -> bool {
+        self.accessibility.unwrap_or(config.settings.accessibility)
+    }
+
+    /// The text speed factor, defaulting to `1.0`.
+    pub fn text_speed(&self) -> f32 {
+        self.text_speed.unwrap_or(1.0)
+    }
+}
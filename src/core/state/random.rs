@@ -0,0 +1,192 @@
+use std::cell::Cell;
+
+use rand::{thread_rng, Rng, RngCore};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+/// A small, serializable pseudorandom number generator stored on the [`Player`](crate::core::player::Player).
+///
+/// Its state is saved alongside the rest of the player data, so random outcomes play out the same way
+/// after a save is loaded. The state is held in a [`Cell`] so that read-only game systems can still draw from it.
+pub struct SeededRandom {
+    /// The seed the generator started from.
+    seed: u64,
+    /// The current generator state.
+    state: Cell<u64>,
+}
+
+impl Default for SeededRandom {
+    fn default() -> Self {
+        Self::new(thread_rng().gen())
+    }
+}
+
+impl SeededRandom {
+    /// Constructs a generator from a seed.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            state: Cell::new(seed),
+        }
+    }
+
+    /// The current generator state, which can be [restored](Self::restore) to replay the values drawn since.
+    pub fn state(&self) -> u64 {
+        self.state.get()
+    }
+
+    /// Rewinds or fast-forwards the generator to a previously recorded [state](Self::state).
+    pub fn restore(&self, state: u64) {
+        self.state.set(state);
+    }
+
+    /// Constructs a generator from this one's current state mixed with a `salt`,
+    /// which can be drawn from without advancing this one.
+    pub fn fork(&self, salt: u64) -> Self {
+        Self::new(self.state() ^ salt.wrapping_mul(0x9E3779B97F4A7C15))
+    }
+
+    /// Advances the generator and returns the next value using the SplitMix64 algorithm.
+    pub fn next(&self) -> u64 {
+        let state = self.state.get().wrapping_add(0x9E3779B97F4A7C15);
+        self.state.set(state);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value in the range `0..bound`, or `0` if `bound` is `0`.
+    pub fn below(&self, bound: usize) -> usize {
+        if bound == 0 {
+            return 0;
+        }
+        (self.next() % bound as u64) as usize
+    }
+
+    /// Picks an index from a slice of weights, where each index is as likely as its weight is large.
+    ///
+    /// Returns [`None`] if the weights sum to `0`.
+    pub fn pick_weighted(&self, weights: &[u32]) -> Option<usize> {
+        let total: u64 = weights.iter().map(|&weight| weight as u64).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut roll = self.next() % total;
+        for (index, &weight) in weights.iter().enumerate() {
+            if roll < weight as u64 {
+                return Some(index);
+            }
+            roll -= weight as u64;
+        }
+        None
+    }
+
+    /// Picks an element from a slice, if it isn't empty.
+    pub fn pick<'a, T>(&self, items: &'a [T]) -> Option<&'a T> {
+        items.get(self.below(items.len()))
+    }
+
+    /// Shuffles a slice in place with the Fisher-Yates algorithm.
+    pub fn shuffle<T>(&self, items: &mut [T]) {
+        for index in (1..items.len()).rev() {
+            items.swap(index, self.below(index + 1));
+        }
+    }
+}
+
+impl RngCore for SeededRandom {
+    fn next_u32(&mut self) -> u32 {
+        (SeededRandom::next(self) >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        SeededRandom::next(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = SeededRandom::next(self).to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use super::*;
+
+    /// Draws a mix of values, including from a fork, the way game systems do.
+    fn draws(random: &SeededRandom) -> Vec<u64> {
+        let mut values = vec![random.next()];
+        let fork = random.fork(7);
+        values.extend((0..4).map(|_| fork.below(100) as u64));
+        values.extend((1..=8).map(|bound| random.below(bound) as u64));
+        values.push(random.pick_weighted(&[1, 0, 3]).unwrap() as u64);
+        values
+    }
+
+    #[test]
+    fn same_seed_draws_the_same() {
+        assert_eq!(draws(&SeededRandom::new(42)), draws(&SeededRandom::new(42)));
+        assert_ne!(draws(&SeededRandom::new(42)), draws(&SeededRandom::new(43)));
+    }
+
+    #[test]
+    fn forks_dont_advance_the_parent() {
+        let random = SeededRandom::new(42);
+        let state = random.state();
+        let fork = random.fork(1);
+        fork.next();
+        assert_eq!(random.state(), state);
+        assert_ne!(fork.state(), random.fork(2).state());
+    }
+
+    #[test]
+    fn restoring_replays_draws() {
+        let random = SeededRandom::new(42);
+        random.next();
+        let state = random.state();
+        let first = draws(&random);
+        random.restore(state);
+        assert_eq!(draws(&random), first);
+    }
+
+    #[test]
+    fn survives_a_save_round_trip() {
+        let random = SeededRandom::new(42);
+        random.next();
+        let loaded: SeededRandom =
+            serde_yaml::from_str(&serde_yaml::to_string(&random).unwrap()).unwrap();
+        assert_eq!(draws(&loaded), draws(&random));
+    }
+
+    #[test]
+    fn shuffles_the_same_way_from_the_same_state() {
+        let shuffled = |seed| {
+            let mut items: Vec<u32> = (0..10).collect();
+            SeededRandom::new(seed).shuffle(&mut items);
+            items
+        };
+        let items = shuffled(42);
+        assert_eq!(items, shuffled(42));
+        assert_eq!(items.iter().copied().sorted().collect::<Vec<_>>(), (0..10).collect::<Vec<_>>());
+        assert_ne!(items, (0..10).collect::<Vec<_>>());
+        let mut empty: [u32; 0] = [];
+        SeededRandom::new(42).shuffle(&mut empty);
+    }
+
+    #[test]
+    fn below_stays_in_bounds() {
+        let random = SeededRandom::new(42);
+        assert_eq!(random.below(0), 0);
+        assert!((0..1000).all(|_| random.below(3) < 3));
+    }
+}
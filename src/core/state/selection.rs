@@ -0,0 +1,169 @@
+use std::collections::BTreeSet;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::core::{context::TextContext, text::templating::TemplatableString};
+
+use super::inventory::CostEntries;
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(untagged)]
+/// An option of a [`Selection`], either just its text or its text along with the value saved when it's selected.
+pub enum SelectOption {
+    Text(TemplatableString),
+    Valued {
+        text: TemplatableString,
+        value: TemplatableString,
+    },
+}
+
+impl SelectOption {
+    /// The text displayed for this option.
+    pub fn text(&self) -> &TemplatableString {
+        match self {
+            SelectOption::Text(text) => text,
+            SelectOption::Valued { text, .. } => text,
+        }
+    }
+
+    /// The value saved when this option is selected, which defaults to its text.
+    pub fn value(&self) -> &TemplatableString {
+        match self {
+            SelectOption::Text(text) => text,
+            SelectOption::Valued { value, .. } => value,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
+/// A container letting the player pick several options before continuing, such as "choose three traits".
+///
+/// Options are toggled by entering their numbers and confirmed together with an empty line.
+/// The values of the selected options are saved to a variable in order, separated by commas.
+pub struct Selection {
+    #[serde(rename = "variable")]
+    /// The variable name to save the selected values to.
+    pub name: TemplatableString,
+    /// The options to pick from, in order.
+    pub options: Vec<SelectOption>,
+    #[serde(default)]
+    /// The fewest options that can be confirmed.
+    pub min: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The most options that can be selected at once, if there's a limit.
+    pub max: Option<usize>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    /// Whether one of each selected value is also added to the player's inventory as an item.
+    pub inventory: bool,
+}
+
+impl Selection {
+    /// The separator between selected values when they're saved to the variable.
+    pub const SEPARATOR: &'static str = ", ";
+
+    /// Validates that there are options to pick and that `min` and `max` can both be met.
+    pub fn validate(&self) -> Result<()> {
+        if self.options.is_empty() {
+            return Err(anyhow!("`options` is empty"));
+        }
+        let count = self.options.len();
+        match self.max {
+            Some(0) => Err(anyhow!("`max` must be non-zero")),
+            Some(max) if max > count => Err(anyhow!("`max` is greater than the number of options")),
+            Some(max) if self.min > max => Err(anyhow!("`min` is greater than `max`")),
+            _ if self.min > count => Err(anyhow!("`min` is greater than the number of options")),
+            _ => Ok(()),
+        }
+    }
+
+    /// Describes how many options can be picked, along with how to pick them.
+    ///
+    /// ### Examples
+    ///
+    /// - `Pick 3; enter numbers to toggle them, or nothing to confirm`
+    /// - `Pick up to 2; enter numbers to toggle them, or nothing to confirm`
+    pub fn hint(&self) -> String {
+        let count = match (self.min, self.max) {
+            (min, Some(max)) if min == max => format!("Pick {min}"),
+            (0, Some(max)) => format!("Pick up to {max}"),
+            (0, None) => "Pick any".to_owned(),
+            (min, Some(max)) => format!("Pick {min} to {max}"),
+            (min, None) => format!("Pick at least {min}"),
+        };
+        format!("{count}; enter numbers to toggle them, or nothing to confirm")
+    }
+
+    /// Constructs the lines of the options, each marked with whether its 1-based number is `selected`.
+    ///
+    /// ### Examples
+    ///
+    /// - `1) [x] Brave`
+    /// - `2) [ ] Clever`
+    pub fn lines(
+        &self,
+        selected: &BTreeSet<usize>,
+        text_context: &TextContext,
+    ) -> Result<Vec<String>> {
+        self.options
+            .iter()
+            .enumerate()
+            .map(|(index, option)| {
+                let mark = match selected.contains(&(index + 1)) {
+                    true => 'x',
+                    false => ' ',
+                };
+                Ok(format!("{}) [{mark}] {}", index + 1, option.text().fill(text_context)?))
+            })
+            .collect()
+    }
+
+    /// Toggles the options with the given 1-based numbers in turn, refusing to select more than `max`.
+    ///
+    /// If a toggle is refused, the ones before it are kept.
+    pub fn toggle(&self, selected: &mut BTreeSet<usize>, numbers: &[usize]) -> Result<()> {
+        for number in numbers {
+            if !selected.remove(number) {
+                if self.max.is_some_and(|max| selected.len() >= max) {
+                    return Err(anyhow!("You can pick at most {}", self.max.unwrap_or_default()));
+                }
+                selected.insert(*number);
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that enough options are `selected` to confirm them.
+    pub fn check(&self, selected: &BTreeSet<usize>) -> Result<()> {
+        if selected.len() < self.min {
+            return Err(anyhow!("You must pick at least {}", self.min));
+        }
+        Ok(())
+    }
+
+    /// Fills the values of the `selected` options, in order.
+    pub fn values(
+        &self,
+        selected: &BTreeSet<usize>,
+        text_context: &TextContext,
+    ) -> Result<Vec<String>> {
+        selected
+            .iter()
+            .filter_map(|number| self.options.get(number - 1))
+            .map(|option| option.value().fill(text_context))
+            .collect()
+    }
+
+    /// The items gained from selecting some `values`, if this selection adds them to the [`inventory`](Self::inventory).
+    pub fn items(&self, values: &[String]) -> Option<CostEntries> {
+        if !self.inventory || values.is_empty() {
+            return None;
+        }
+        let mut items = CostEntries::new();
+        for value in values {
+            *items.entry(value.clone()).or_default() += 1;
+        }
+        Some(items)
+    }
+}
@@ -0,0 +1,57 @@
+use std::{collections::HashMap, fmt::Display};
+
+use rlua::{Context, FromLua, ToLua, Value};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+/// A typed value in the player's [`Store`].
+pub enum StoreValue {
+    Bool(bool),
+    Int(i64),
+    String(String),
+}
+
+/// A map of flags, counters, and strings that scripts can both read and write.
+///
+/// Unlike [`Variables`](super::variables::Variables), store values keep their types across saves.
+pub type Store = HashMap<String, StoreValue>;
+
+impl Display for StoreValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bool(value) => write!(f, "{value}"),
+            Self::Int(value) => write!(f, "{value}"),
+            Self::String(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+impl<'lua> ToLua<'lua> for StoreValue {
+    fn to_lua(self, lua: Context<'lua>) -> rlua::Result<Value<'lua>> {
+        match self {
+            Self::Bool(value) => value.to_lua(lua),
+            Self::Int(value) => value.to_lua(lua),
+            Self::String(value) => value.to_lua(lua),
+        }
+    }
+}
+
+impl<'lua> FromLua<'lua> for StoreValue {
+    fn from_lua(value: Value<'lua>, lua: Context<'lua>) -> rlua::Result<Self> {
+        let result = match value {
+            Value::Boolean(value) => Self::Bool(value),
+            Value::Integer(value) => Self::Int(value),
+            Value::Number(value) if value.fract() == 0.0 => Self::Int(value as i64),
+            Value::String(_) => Self::String(String::from_lua(value, lua)?),
+            other => {
+                return Err(rlua::Error::FromLuaConversionError {
+                    from: other.type_name(),
+                    to: "store value",
+                    message: Some("expected a boolean, integer, or string".to_owned()),
+                })
+            }
+        };
+        Ok(result)
+    }
+}
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
+use result::OptionResultExt;
 use serde::{
     de::{
         value::{MapAccessDeserializer, SeqAccessDeserializer},
@@ -9,7 +10,10 @@ use serde::{
     Deserialize, Serialize,
 };
 
-use crate::core::{context::TextContext, text::templating::TemplatableString};
+use crate::core::{
+    context::TextContext,
+    text::templating::{TemplatableString, TemplatableValue},
+};
 
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(deny_unknown_fields)]
@@ -21,6 +25,135 @@ pub struct VariableInput {
     #[serde(rename = "variable")]
     /// The variable name to save the user input to.
     pub name: TemplatableString,
+    #[serde(default, alias = "candidates", skip_serializing_if = "Option::is_none")]
+    /// Known answers that the player can tab-complete the input to.
+    pub completions: Option<Vec<TemplatableString>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Bounds that make this input only accept numbers.
+    pub range: Option<NumberRange>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    /// Whether the input is shown as asterisks while it's typed, such as for passwords.
+    ///
+    /// Masked input is still saved to the variable, but it's left out of the transcript and the input history.
+    pub masked: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
+/// The numbers a [`VariableInput`] accepts, each bound being optional.
+pub struct NumberRange {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The smallest number accepted.
+    pub min: Option<TemplatableValue<f64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The largest number accepted.
+    pub max: Option<TemplatableValue<f64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The interval between accepted numbers, counting from `min` or zero. Must be positive.
+    pub step: Option<TemplatableValue<f64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The number used if the input is left empty. If there isn't one, empty input is rejected.
+    pub default: Option<TemplatableValue<f64>>,
+}
+
+/// A [`NumberRange`] with its bounds filled, used to check input as it's entered.
+#[derive(Debug, Clone, Default)]
+pub struct NumberBounds {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub step: Option<f64>,
+    pub default: Option<f64>,
+}
+
+impl NumberRange {
+    /// Fills each of the range's bounds.
+    pub fn bounds(&self, text_context: &TextContext) -> Result<NumberBounds> {
+        let fill = |bound: &Option<TemplatableValue<f64>>| {
+            bound
+                .as_ref()
+                .map(|bound| bound.get_value(text_context))
+                .invert()
+        };
+        Ok(NumberBounds {
+            min: fill(&self.min)?,
+            max: fill(&self.max)?,
+            step: fill(&self.step)?,
+            default: fill(&self.default)?,
+        })
+    }
+
+    /// Validates the bounds that aren't templated.
+    ///
+    /// `min` can't be greater than `max`, `step` must be positive, and `default` must be accepted by the other bounds.
+    pub fn validate(&self) -> Result<()> {
+        let value = |bound: &Option<TemplatableValue<f64>>| bound.as_ref().and_then(|b| b.value);
+        let bounds = NumberBounds {
+            min: value(&self.min),
+            max: value(&self.max),
+            step: value(&self.step),
+            default: None,
+        };
+        if let (Some(min), Some(max)) = (bounds.min, bounds.max) {
+            if min > max {
+                return Err(anyhow!("`min` is greater than `max`"));
+            }
+        }
+        if bounds
+            .step
+            .is_some_and(|step| step <= 0.0 || !step.is_finite())
+        {
+            return Err(anyhow!("`step` must be positive"));
+        }
+        if let Some(default) = value(&self.default) {
+            bounds
+                .accept(&default.to_string())
+                .with_context(|| "`default` is outside of the range")?;
+        }
+        Ok(())
+    }
+}
+
+impl NumberBounds {
+    /// How far off a multiple of `step` an input can be, to account for floating-point error.
+    const STEP_TOLERANCE: f64 = 1e-9;
+
+    /// Checks a line of input against the bounds, returning the number to save if it's accepted.
+    ///
+    /// An empty line is replaced with the `default`, if any. Numbers are saved in their shortest form,
+    /// such as `5` for `5.0`.
+    pub fn accept(&self, line: &str) -> Result<String> {
+        if line.is_empty() {
+            return self
+                .default
+                .map(|default| default.to_string())
+                .ok_or(anyhow!("Input cannot be empty"));
+        }
+        let value = line
+            .parse::<f64>()
+            .ok()
+            .filter(|value| value.is_finite())
+            .ok_or(anyhow!("Input must be a number"))?;
+        match (self.min, self.max) {
+            (Some(min), Some(max)) if value < min || value > max => {
+                return Err(anyhow!("Input must be between {min} and {max}"))
+            }
+            (Some(min), _) if value < min => return Err(anyhow!("Input must be at least {min}")),
+            (_, Some(max)) if value > max => return Err(anyhow!("Input must be at most {max}")),
+            _ => {}
+        }
+        if let Some(step) = self.step {
+            let steps = (value - self.min.unwrap_or(0.0)) / step;
+            if (steps - steps.round()).abs() > Self::STEP_TOLERANCE {
+                return Err(match self.min {
+                    Some(min) if min != 0.0 => {
+                        anyhow!("Input must be {min} plus a multiple of {step}")
+                    }
+                    _ => anyhow!("Input must be a multiple of {step}"),
+                });
+            }
+        }
+        Ok(value.to_string())
+    }
 }
 
 /// A map of display variables wherein the key is the variable name and the value is the variable's display.
@@ -118,7 +251,7 @@ impl VariableApplications {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 /// A single variable value recording.
 pub struct VariableEntry {
     /// The new variable value.
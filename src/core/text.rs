@@ -1,2 +1,5 @@
 pub mod display;
+pub mod markup;
+pub mod plural;
 pub mod templating;
+pub mod typewriter;
@@ -7,7 +7,6 @@ use anyhow::{Context, Result};
 use crossterm::style::Stylize;
 use result::OptionResultExt;
 use serde::{de, Deserialize, Deserializer, Serialize};
-use snailshell::{snailprint_d, snailprint_s};
 use strum::{Display, EnumIter, EnumString};
 
 use crate::{
@@ -19,7 +18,11 @@ use crate::{
     loading::loader::{ContentFile, Contents},
 };
 
-use super::templating::{TemplatableString, TemplatableValue};
+use super::{
+    markup,
+    templating::{TemplatableString, TemplatableValue},
+    typewriter,
+};
 
 #[derive(Deserialize, Serialize, Display, Debug, PartialEq, Clone, EnumString, EnumIter)]
 #[serde(rename_all = "snake_case")]
@@ -56,6 +59,8 @@ impl TextMode {
 }
 
 /// The speed at which text should be printed.
+///
+/// A delay or duration of `0`, or a rate of `0` or less, prints text instantly.
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum TextSpeed {
@@ -74,39 +79,64 @@ impl Default for TextSpeed {
 }
 
 impl TextSpeed {
-    /// Calculates or returns the rate in charatcers per second
-    /// to be used in [`snailprint_s`].
+    /// Calculates the delay between each character of printed content that is `length` characters long.
+    ///
+    /// If this object is [`Rate`](TextSpeed::Rate), the delay is the inverse of the rate.
+    /// If it is [`Duration`](TextSpeed::Duration), the duration is split evenly between each character.
     ///
-    /// If this object is [`Rate`](TextSpeed::Rate), returns the contained value.
-    /// If it is [`Delay`](TextSpeed::Delay), calculates the rate with `(1.0 / delay) * 1000.0`.
-    pub fn rate(&self, context: &TextContext) -> Result<f32> {
-        use TextSpeed::*;
+    /// The delay is then scaled by the player's [text speed](TextContext::text_speed).
+    pub fn delay(&self, length: usize, context: &TextContext) -> Result<Duration> {
         let result = match &self {
-            Rate(rate) => rate.get_value(context)?,
-            Delay(delay) => 1.0 / delay.get_value(context)? as f32 * 1000.0,
-            _ => unreachable!(),
+            TextSpeed::Rate(rate) => {
+                let rate = rate.get_value(context)?;
+                if rate > 0.0 {
+                    Duration::from_secs_f32(1.0 / rate)
+                } else {
+                    Duration::ZERO
+                }
+            }
+            TextSpeed::Delay(delay) => Duration::from_millis(delay.get_value(context)? as u64),
+            TextSpeed::Duration(duration) => {
+                Duration::from_millis(duration.get_value(context)? as u64) / length.max(1) as u32
+            }
         };
-        Ok(result)
+        match context.text_speed() {
+            speed if speed > 0.0 => Ok(result.div_f32(speed)),
+            _ => Ok(result),
+        }
     }
 
-    /// Snailprints some content.
+    /// Prints some content character by character using [`typewriter::typewrite`]
+    /// with the delay returned from [`TextSpeed::delay`].
     ///
-    /// If the object is [`Rate`](TextSpeed::Rate) or [`Delay`](TextSpeed::Delay), uses [`snailprint_s`]
-    /// with the rate returned from [`TextSpeed::rate`].
+    /// The content is first [wrapped](typewriter::wrap) to the [terminal width](crate::core::manifest::TextSettings::wrap_width).
     ///
-    /// Otherwise, if the object is [`Duration`](TextSpeed::Duration), uses [`snailprint_d`] with the
-    /// specified length of time.
+    /// Any keypress while the content is printing reveals the rest of it, except for the
+    /// [pause key](crate::core::resources::Resources::pause_blocked). The content is printed
+    /// all at once while [fast-forwarding](crate::core::fast_forward::FastForward), and pressing
+    /// the fast-forward key starts it. In [accessibility mode](TextContext::is_accessible),
+    /// the content is printed all at once without being wrapped.
     pub fn print<T>(&self, content: &T, context: &TextContext) -> Result<()>
     where
         T: Display,
     {
-        let result = match &self {
-            TextSpeed::Duration(duration) => {
-                snailprint_d(content, duration.get_value(context)? as f32 / 1000.0)
+        let content = match context.is_accessible() {
+            true => content.to_string(),
+            false => {
+                typewriter::wrap(&content.to_string(), context.config().settings.text.wrap_width())
             }
-            _ => snailprint_s(content, self.rate(context)?),
         };
-        Ok(result)
+        let resources = context.resources();
+        let length = typewriter::visible_len(&content);
+        resources.record_printed(length);
+        let delay = match resources.fast_forward.is_active() || context.is_accessible() {
+            true => Duration::ZERO,
+            false => self.delay(length, context)?,
+        };
+        typewriter::typewrite(&content, delay, |key| {
+            resources.fast_forward.start_blocked(key);
+            resources.pause_blocked(key)
+        })
     }
 }
 
@@ -157,17 +187,57 @@ where
     Ok(opt)
 }
 
+/// Formats a duration as hours and minutes, such as `2h 13m` or `45m`.
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{minutes}m"),
+        (hours, minutes) => format!("{hours}h {minutes}m"),
+    }
+}
+
 pub type TranslationFile = ContentFile<String>;
 pub type Translations = Contents<String>;
 
 impl Text {
     /// Retrieves text content with [`TemplatableString::fill`] and formats it based on the [`TextMode`].
+    ///
+    /// Markdown emphasis such as `**bold**` and [markup tags](markup::render) such as `{red}` are then rendered,
+    /// or removed if color is disabled or unsupported, or in [accessibility mode](TextContext::is_accessible).
     pub fn get(&self, context: &TextContext) -> Result<String> {
+        Ok(Self::render(&self.format(context)?, context))
+    }
+
+    /// Retrieves text content like [`Text::get`], but with markup tags removed and Markdown left as-is.
+    pub fn get_plain(&self, context: &TextContext) -> Result<String> {
+        Ok(markup::render(&self.format(context)?, false))
+    }
+
+    /// Retrieves text like [`Text::get`], but with some already-filled `content` in place of this text's own.
+    pub fn get_as(&self, content: &str, context: &TextContext) -> Result<String> {
+        let string = self.mode.get_value(context)?.format(content);
+        Ok(Self::render(&string, context))
+    }
+
+    /// Retrieves text like [`Text::get_plain`], but with some already-filled `content` in place of this text's own.
+    pub fn get_plain_as(&self, content: &str, context: &TextContext) -> Result<String> {
+        let string = self.mode.get_value(context)?.format(content);
+        Ok(markup::render(&string, false))
+    }
+
+    /// Renders the Markdown and markup tags of some formatted text.
+    fn render(string: &str, context: &TextContext) -> String {
+        let color =
+            markup::is_supported(context.config().settings.text.color) && !context.is_accessible();
+        markup::render(&termimad::inline(string).to_string(), color)
+    }
+
+    fn format(&self, context: &TextContext) -> Result<String> {
         let string = self
             .mode
             .get_value(context)?
             .format(&self.content.fill(context)?);
-        Ok(termimad::inline(&string).to_string())
+        Ok(string)
     }
 
     fn wait(&self, context: &TextContext) -> Result<Option<u64>> {
@@ -180,7 +250,7 @@ impl Text {
         Ok(result)
     }
 
-    /// Formats and snailprints text based on its [`TextSpeed`].
+    /// Formats and prints text based on its [`TextSpeed`].
     ///
     /// If the text object does not contain a `speed` field, defaults to the provided config settings.
     pub fn print(&self, player: &Player, context: &TextContext) -> Result<()> {
@@ -191,6 +261,10 @@ impl Text {
             .speed
             .as_ref()
             .unwrap_or(&context.config().settings.text.speed);
+        context
+            .resources()
+            .transcript
+            .record_text(&self.get_plain(context)?);
         speed.print(&self.get(context)?, context)?;
         if let &Some(wait) = &self.wait(context)? {
             std::thread::sleep(Duration::from_millis(wait));
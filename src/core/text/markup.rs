@@ -0,0 +1,129 @@
+use std::{
+    env,
+    io::{self, IsTerminal},
+};
+
+use crossterm::style::{Attribute, Color, SetAttribute, SetForegroundColor};
+
+use super::typewriter;
+
+/// A style that can be opened with a `{name}` tag and closed with a `{/name}` tag.
+#[derive(Clone, Copy, PartialEq)]
+enum Style {
+    Color(Color),
+    Attribute(Attribute),
+}
+
+impl Style {
+    /// Parses the name of a tag, such as `red` or `bold`.
+    fn parse(name: &str) -> Option<Self> {
+        use Color::*;
+        let color = match name {
+            "black" => Black,
+            "red" => Red,
+            "green" => Green,
+            "yellow" => Yellow,
+            "blue" => Blue,
+            "magenta" => Magenta,
+            "cyan" => Cyan,
+            "white" => White,
+            "grey" | "gray" => Grey,
+            "dark_red" => DarkRed,
+            "dark_green" => DarkGreen,
+            "dark_yellow" => DarkYellow,
+            "dark_blue" => DarkBlue,
+            "dark_magenta" => DarkMagenta,
+            "dark_cyan" => DarkCyan,
+            "dark_grey" | "dark_gray" => DarkGrey,
+            _ => {
+                let attribute = match name {
+                    "bold" => Attribute::Bold,
+                    "dim" => Attribute::Dim,
+                    "italic" => Attribute::Italic,
+                    "underline" => Attribute::Underlined,
+                    _ => return None,
+                };
+                return Some(Style::Attribute(attribute));
+            }
+        };
+        Some(Style::Color(color))
+    }
+
+    fn apply(&self, result: &mut String) {
+        match self {
+            Style::Color(color) => result.push_str(&SetForegroundColor(*color).to_string()),
+            Style::Attribute(attribute) => result.push_str(&SetAttribute(*attribute).to_string()),
+        }
+    }
+}
+
+/// The escape sequence that resets every style, as written by other formatters such as `termimad`.
+const RESET: &str = "\x1b[0m";
+
+/// Whether styled output should be written at all.
+///
+/// This is `false` if the manifest disables color, the `NO_COLOR` environment variable is set,
+/// or the output isn't a terminal.
+pub fn is_supported(enabled: bool) -> bool {
+    enabled && env::var_os("NO_COLOR").is_none() && io::stdout().is_terminal()
+}
+
+/// Replaces style tags in some content with the escape sequences they stand for.
+///
+/// Tags such as `{red}` open a style, and `{/red}` closes the most recent matching one. Unknown tags are kept as-is.
+/// Since closing a style resets every style, the ones still open are applied again afterward, which is also done
+/// after any reset already in the content.
+///
+/// If `color` is `false`, tags are removed along with any escape sequences already in the content.
+pub fn render(content: &str, color: bool) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut open: Vec<Style> = Vec::new();
+    let mut rest = content;
+    while let Some(start) = rest.find(['{', '\x1b']) {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(after) = rest.strip_prefix(RESET) {
+            result.push_str(RESET);
+            open.iter().for_each(|style| style.apply(&mut result));
+            rest = after;
+            continue;
+        }
+        let tag = rest[1..]
+            .find('}')
+            .map(|end| &rest[1..end + 1])
+            .filter(|_| rest.starts_with('{'));
+        let Some(tag) = tag else {
+            result.push_str(&rest[..1]);
+            rest = &rest[1..];
+            continue;
+        };
+        let (closing, name) = match tag.strip_prefix('/') {
+            Some(name) => (true, name),
+            None => (false, tag),
+        };
+        let Some(style) = Style::parse(name) else {
+            result.push('{');
+            rest = &rest[1..];
+            continue;
+        };
+        if closing {
+            if let Some(index) = open.iter().rposition(|other| *other == style) {
+                open.remove(index);
+            }
+            result.push_str(RESET);
+            open.iter().for_each(|style| style.apply(&mut result));
+        } else {
+            style.apply(&mut result);
+            open.push(style);
+        }
+        rest = &rest[tag.len() + 2..];
+    }
+    result.push_str(rest);
+    if !open.is_empty() {
+        result.push_str(RESET);
+    }
+    match color {
+        true => result,
+        false => typewriter::strip_escapes(&result),
+    }
+}
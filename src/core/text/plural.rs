@@ -0,0 +1,147 @@
+/// How a language picks between the plural forms of a word based on a count.
+///
+/// Forms are given in the order the rule lists them. Rules only cover whole numbers, and languages
+/// that aren't known follow [`PluralRule::One`].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PluralRule {
+    /// Two forms, where only `1` is singular, as in English or German.
+    One,
+    /// Two forms, where both `0` and `1` are singular, as in French or Brazilian Portuguese.
+    ZeroOne,
+    /// Three forms for numbers ending in one, in two to four, and in anything else, as in Russian or Ukrainian.
+    EastSlavic,
+    /// Three forms for one, for numbers ending in two to four, and for anything else, as in Polish.
+    Polish,
+    /// A single form for every count, as in Japanese or Chinese.
+    Invariant,
+}
+
+impl PluralRule {
+    /// Finds the rule for a language key such as `en_us` based on its language code.
+    pub fn for_lang(lang: &str) -> Self {
+        use PluralRule::*;
+        let code = lang.split(['_', '-']).next().unwrap_or(lang);
+        match code.to_lowercase().as_str() {
+            "fr" => ZeroOne,
+            "pt" if lang.to_lowercase().ends_with("br") => ZeroOne,
+            "ru" | "uk" | "be" => EastSlavic,
+            "pl" => Polish,
+            "ja" | "zh" | "ko" | "vi" | "th" | "id" => Invariant,
+            _ => One,
+        }
+    }
+
+    /// The index of the form to use for `count`, if the rule's forms are all given.
+    fn index(&self, count: i64) -> usize {
+        use PluralRule::*;
+        let count = count.unsigned_abs();
+        let (ones, tens) = (count % 10, count % 100);
+        let few = (2..=4).contains(&ones) && !(12..=14).contains(&tens);
+        match self {
+            One => (count != 1) as usize,
+            ZeroOne => (count > 1) as usize,
+            EastSlavic if ones == 1 && tens != 11 => 0,
+            Polish if count == 1 => 0,
+            EastSlavic | Polish if few => 1,
+            EastSlavic | Polish => 2,
+            Invariant => 0,
+        }
+    }
+
+    /// Picks the form to use for `count`.
+    ///
+    /// If fewer forms are given than the rule lists, the last one is used in place of the missing ones.
+    pub fn select<'a>(&self, count: i64, forms: &[&'a str]) -> &'a str {
+        let index = self.index(count).min(forms.len().saturating_sub(1));
+        forms.get(index).copied().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn select_all(lang: &str, counts: &[i64], forms: &[&str]) -> Vec<String> {
+        let rule = PluralRule::for_lang(lang);
+        counts
+            .iter()
+            .map(|count| rule.select(*count, forms).to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn finds_rules_by_language_code() {
+        assert_eq!(PluralRule::for_lang("en_us"), PluralRule::One);
+        assert_eq!(PluralRule::for_lang("fr_fr"), PluralRule::ZeroOne);
+        assert_eq!(PluralRule::for_lang("pt_br"), PluralRule::ZeroOne);
+        assert_eq!(PluralRule::for_lang("pt_pt"), PluralRule::One);
+        assert_eq!(PluralRule::for_lang("ru_ru"), PluralRule::EastSlavic);
+        assert_eq!(PluralRule::for_lang("pl-PL"), PluralRule::Polish);
+        assert_eq!(PluralRule::for_lang("ja_jp"), PluralRule::Invariant);
+        assert_eq!(PluralRule::for_lang("xx"), PluralRule::One);
+    }
+
+    #[test]
+    fn english() {
+        let forms = ["apple", "apples"];
+        assert_eq!(
+            select_all("en_us", &[0, 1, 2, 11, 21, -1], &forms),
+            ["apples", "apple", "apples", "apples", "apples", "apple"]
+        );
+    }
+
+    #[test]
+    fn russian() {
+        let forms = ["яблоко", "яблока", "яблок"];
+        assert_eq!(
+            select_all("ru_ru", &[0, 1, 2, 4, 5, 11, 12, 13, 14], &forms),
+            [
+                "яблок",
+                "яблоко",
+                "яблока",
+                "яблока",
+                "яблок",
+                "яблок",
+                "яблок",
+                "яблок",
+                "яблок"
+            ]
+        );
+        assert_eq!(
+            select_all("ru_ru", &[21, 22, 23, 24, 25, 101, 111], &forms),
+            [
+                "яблоко",
+                "яблока",
+                "яблока",
+                "яблока",
+                "яблок",
+                "яблоко",
+                "яблок"
+            ]
+        );
+    }
+
+    #[test]
+    fn polish() {
+        let forms = ["jabłko", "jabłka", "jabłek"];
+        assert_eq!(
+            select_all("pl_pl", &[0, 1, 2, 4, 5, 11, 12, 13, 14], &forms),
+            [
+                "jabłek", "jabłko", "jabłka", "jabłka", "jabłek", "jabłek", "jabłek", "jabłek",
+                "jabłek"
+            ]
+        );
+        assert_eq!(
+            select_all("pl_pl", &[21, 22, 23, 24, 25, 102, 112], &forms),
+            ["jabłek", "jabłka", "jabłka", "jabłka", "jabłek", "jabłka", "jabłek"]
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_last_form() {
+        let rule = PluralRule::EastSlavic;
+        assert_eq!(rule.select(5, &["one", "few"]), "few");
+        assert_eq!(rule.select(5, &[]), "");
+        assert_eq!(PluralRule::Invariant.select(5, &["items"]), "items");
+    }
+}
@@ -1,4 +1,4 @@
-use std::{fmt::Display, str::FromStr};
+use std::{collections::HashMap, fmt::Display, str::FromStr};
 
 use anyhow::{anyhow, Context, Result};
 use serde::{
@@ -6,9 +6,14 @@ use serde::{
     Deserialize, Deserializer, Serialize,
 };
 
-use crate::core::{context::TextContext, state::variables::Variables};
-
-use super::display::TranslationFile;
+use crate::core::{
+    context::TextContext,
+    manifest::Manifest,
+    state::{
+        store::{Store, StoreValue},
+        variables::Variables,
+    },
+};
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(transparent)]
@@ -28,10 +33,37 @@ impl TemplatableString {
     /// The default value for an undefined interpolation component.
     pub const DEFAULT_VALUE: &'static str = "UNDEFINED";
 
+    /// The opening of a conditional segment; see [`TemplatableString::conditionals`].
+    const CONDITIONAL: &'static str = "{if ";
+
+    /// The opening of a store value reference; see [`TemplatableString::fill_store`].
+    const STORE: &'static str = "{var.";
+
+    /// The opening of a stat reference; see [`TemplatableString::fill_stats`].
+    const STATS: &'static str = "{stats.";
+
+    /// The opening of a plural segment; see [`TemplatableString::fill_plurals`].
+    const PLURAL: &'static str = "{plural:";
+
+    /// The opening of a snippet reference; see [`TemplatableString::fill_snippets`].
+    const SNIPPET: &'static str = "{snippet.";
+
+    /// How deeply snippets can reference other snippets before expansion stops, which catches snippets that reference themselves.
+    const SNIPPET_DEPTH: usize = 8;
+
+    /// The characters that can be escaped with a backslash within conditional segments.
+    const ESCAPABLE: [char; 5] = ['{', '}', '|', ':', '\\'];
+
     /// Whether this string's content can be **templated** by variables or scripts.
     /// This does not check for language file matching.
     pub fn is_str_templatable(content: &str) -> bool {
-        content.contains('(') || content.contains('<')
+        content.contains('(')
+            || content.contains('<')
+            || content.contains(Self::CONDITIONAL)
+            || content.contains(Self::STORE)
+            || content.contains(Self::STATS)
+            || content.contains(Self::PLURAL)
+            || content.contains(Self::SNIPPET)
     }
 
     /// Whether this [`TemplatableString`] is actually templatable determined by [`is_str_templatable`](TemplatableString::is_str_templatable).
@@ -52,6 +84,20 @@ impl TemplatableString {
         None
     }
 
+    /// Returns the contents of each script segment, such as `file:function` in `(file:function)`.
+    pub fn script_segments(&self) -> impl Iterator<Item = &str> {
+        self.content
+            .split('(')
+            .skip(1)
+            .filter_map(|rest| rest.split_once(')'))
+            .map(|(segment, _)| segment)
+    }
+
+    /// Returns the raw internal string, including any templates it contains.
+    pub fn raw(&self) -> &str {
+        &self.content
+    }
+
     /// Fills a templatable string based on the input delimiter characters and a filler function.
     ///
     /// If the filler function returns [`None`], yields [`TemplatableString::DEFAULT_VARIABLE`].
@@ -84,16 +130,26 @@ impl TemplatableString {
         Ok(result)
     }
 
-    /// Attempts to retrieve a content string from the passed-in lang file.
+    /// Attempts to retrieve a content string from the context's lang files.
     ///
     /// Prior to formatting, the text content may represent a language key such as `some.key.here`.
     /// It bears no difference to actual text content, but if it can be found within a lang file, that value will be used.
     /// Thus, it is vital that the value is retrieved before any formatting is performed on the content.
-    fn lang_file_content<'a>(&'a self, lang_file: Option<&'a TranslationFile>) -> &'a String {
-        lang_file
-            .map(|file| file.get(&self.content))
-            .flatten()
-            .unwrap_or(&self.content)
+    fn lang_file_content<'a>(&'a self, context: &'a TextContext) -> &'a String {
+        context.translate(&self.content).unwrap_or(&self.content)
+    }
+
+    /// Fills a `lang:` reference with its translation.
+    ///
+    /// If the key can't be found in any lang file, it's used as-is, and a warning is logged in debug mode.
+    fn fill_translation(key: &str, context: &TextContext) -> String {
+        if let Some(translation) = context.translate(key) {
+            return translation.clone();
+        }
+        if context.config().settings.debug {
+            log::warn!("Missing translation for '{key}'");
+        }
+        key.to_owned()
     }
 
     fn fill_variable<'a>(
@@ -101,20 +157,351 @@ impl TemplatableString {
         variables: &'a Variables,
         context: &TextContext,
     ) -> Option<String> {
+        if let Some(key) = var.strip_prefix("lang:") {
+            return Some(Self::fill_translation(key, context));
+        }
         context.global_variable(var).or(variables.get(var).cloned())
     }
 
+    /// Whether a conditional segment's condition holds.
+    ///
+    /// A condition is a note name that must be present, a [visited reference](TextContext::visited_variable) to a prompt
+    /// that must have been visited, or a boolean [audio reference](TextContext::audio_variable) that must be `true`;
+    /// any is negated if prefixed with `!`. Missing notes are simply absent, so they never cause an error.
+    fn condition(condition: &str, context: &TextContext) -> Result<bool> {
+        let (negated, name) = match condition.trim().strip_prefix('!') {
+            Some(name) => (true, name.trim()),
+            None => (false, condition.trim()),
+        };
+        if let Some(visited) = context.visited_variable(name) {
+            return Ok(visited != negated);
+        }
+        let holds = match context.audio_variable(name) {
+            Some(value) => match value?.as_str() {
+                "true" => true,
+                "false" => false,
+                value => {
+                    return Err(anyhow!("Condition '{name}' must be a boolean, but it's '{value}'"))
+                }
+            },
+            None => context.notes.contains(name),
+        };
+        Ok(holds != negated)
+    }
+
+    /// Parses content up until, but not including, any of the `stops` characters, evaluating conditional segments along the way.
+    fn parse_conditionals(
+        chars: &[char],
+        index: &mut usize,
+        stops: &[char],
+        context: &TextContext,
+    ) -> Result<String> {
+        let mut result = String::new();
+        while let Some(&c) = chars.get(*index) {
+            let next = chars.get(*index + 1).copied();
+            if c == '\\' && next.is_some_and(|next| Self::ESCAPABLE.contains(&next)) {
+                result.push(next.unwrap());
+                *index += 2;
+                continue;
+            }
+            if stops.contains(&c) {
+                return Ok(result);
+            }
+            let opens = chars[*index..]
+                .iter()
+                .take(Self::CONDITIONAL.len())
+                .copied()
+                .eq(Self::CONDITIONAL.chars());
+            if !opens {
+                result.push(c);
+                *index += 1;
+                continue;
+            }
+            *index += Self::CONDITIONAL.len();
+            let condition = Self::parse_conditionals(chars, index, &[':'], context)?;
+            Self::expect(chars, index, ':')?;
+            let truthy = Self::parse_conditionals(chars, index, &['|', '}'], context)?;
+            let (truthy, falsy) = if chars.get(*index) == Some(&'|') {
+                *index += 1;
+                let falsy = Self::parse_conditionals(chars, index, &['}'], context)?;
+                (truthy.trim().to_owned(), falsy.trim_start().to_owned())
+            } else {
+                (truthy, String::new())
+            };
+            Self::expect(chars, index, '}')?;
+            let branch = if Self::condition(&condition, context)? {
+                truthy
+            } else {
+                falsy
+            };
+            result.push_str(&branch);
+        }
+        Ok(result)
+    }
+
+    /// Consumes an expected character while parsing a conditional segment.
+    fn expect(chars: &[char], index: &mut usize, expected: char) -> Result<()> {
+        if chars.get(*index) != Some(&expected) {
+            return Err(anyhow!("Conditional segment is missing a '{expected}' character"));
+        }
+        *index += 1;
+        Ok(())
+    }
+
+    /// Evaluates every conditional segment in some content against the player's notes and audio state.
+    ///
+    /// A conditional segment takes the form `{if condition: A | B}`, yielding `A` if the [condition](Self::condition)
+    /// holds and `B` otherwise. The `| B` part is optional. With it, the whitespace next to the `:` and `|` is trimmed;
+    /// without it, `A` is kept as written, so `You wave{if met: and smile}.` keeps the space before `and`.
+    /// Segments can be nested within either branch, and the characters `{`, `}`, `|`, `:`, and `\` can be
+    /// escaped with a backslash. Braces that don't open with `{if ` are left as-is.
+    pub fn conditionals(content: &str, context: &TextContext) -> Result<String> {
+        if !content.contains(Self::CONDITIONAL) {
+            return Ok(content.to_owned());
+        }
+        let chars: Vec<char> = content.chars().collect();
+        Self::parse_conditionals(&chars, &mut 0, &[], context)
+    }
+
+    /// Replaces every `{var.name}` reference in some content with the player's [`Store`] value for `name`.
+    ///
+    /// Unset values are replaced with nothing, so they never cause an error.
+    pub fn fill_store(content: &str, store: &Store) -> String {
+        let mut result = String::with_capacity(content.len());
+        let mut rest = content;
+        while let Some(start) = rest.find(Self::STORE) {
+            let after = &rest[start + Self::STORE.len()..];
+            let Some(end) = after.find('}') else {
+                break;
+            };
+            result.push_str(&rest[..start]);
+            if let Some(value) = store.get(after[..end].trim()) {
+                result.push_str(&value.to_string());
+            }
+            rest = &after[end + 1..];
+        }
+        result.push_str(rest);
+        result
+    }
+
+    /// Replaces every `{stats.name}` reference in some content with the player's [stat](TextContext::stat) for `name`.
+    ///
+    /// Unknown stats are replaced with nothing, so they never cause an error.
+    pub fn fill_stats(content: &str, context: &TextContext) -> String {
+        let mut result = String::with_capacity(content.len());
+        let mut rest = content;
+        while let Some(start) = rest.find(Self::STATS) {
+            let after = &rest[start + Self::STATS.len()..];
+            let Some(end) = after.find('}') else {
+                break;
+            };
+            result.push_str(&rest[..start]);
+            if let Some(value) = context.stat(after[..end].trim()) {
+                result.push_str(&value);
+            }
+            rest = &after[end + 1..];
+        }
+        result.push_str(rest);
+        result
+    }
+
+    /// Splits the content of a snippet reference into its name and arguments, along with the content after it.
+    ///
+    /// The reference ends at its matching closing brace, so arguments can contain other braced segments.
+    /// Returns [`None`] if the reference is never closed.
+    fn snippet_reference(after: &str) -> Option<(&str, Vec<&str>, &str)> {
+        let mut depth = 0;
+        let mut parts = Vec::new();
+        let mut part_start = 0;
+        for (index, c) in after.char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' if depth == 0 => {
+                    parts.push(&after[part_start..index]);
+                    let name = parts.remove(0).trim();
+                    return Some((name, parts, &after[index + 1..]));
+                }
+                '}' => depth -= 1,
+                ':' if depth == 0 => {
+                    parts.push(&after[part_start..index]);
+                    part_start = index + 1;
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Replaces every snippet reference in some content with the manifest's [snippet](Manifest::snippets) of that name.
+    ///
+    /// A snippet reference takes the form `{snippet.name}`, or `{snippet.name:first:second}` to pass it arguments,
+    /// which replace `$1`, `$2`, and so on in the snippet. Snippets are expanded before anything else is templated,
+    /// so they can contain variables, conditional segments, and references to other snippets.
+    ///
+    /// Returns an error if a snippet doesn't exist or snippets are nested more than [`SNIPPET_DEPTH`](Self::SNIPPET_DEPTH) deep.
+    pub fn fill_snippets(content: &str, snippets: &HashMap<String, String>) -> Result<String> {
+        Self::expand_snippets(content, snippets, 0)
+    }
+
+    fn expand_snippets(
+        content: &str,
+        snippets: &HashMap<String, String>,
+        depth: usize,
+    ) -> Result<String> {
+        if !content.contains(Self::SNIPPET) {
+            return Ok(content.to_owned());
+        }
+        if depth == Self::SNIPPET_DEPTH {
+            return Err(anyhow!(
+                "Snippets are nested more than {} deep; does a snippet reference itself?",
+                Self::SNIPPET_DEPTH
+            ));
+        }
+        let mut result = String::with_capacity(content.len());
+        let mut rest = content;
+        while let Some(start) = rest.find(Self::SNIPPET) {
+            let Some((name, args, after)) =
+                Self::snippet_reference(&rest[start + Self::SNIPPET.len()..])
+            else {
+                break;
+            };
+            result.push_str(&rest[..start]);
+            let mut snippet = snippets
+                .get(name)
+                .ok_or(anyhow!("Unknown snippet '{name}'"))?
+                .clone();
+            // Replace later parameters first so that `$1` doesn't match the start of `$10`
+            for (index, arg) in args.iter().enumerate().rev() {
+                snippet = snippet.replace(&format!("${}", index + 1), arg);
+            }
+            result.push_str(&Self::expand_snippets(&snippet, snippets, depth + 1)?);
+            rest = after;
+        }
+        result.push_str(rest);
+        Ok(result)
+    }
+
+    /// Resolves the count of a plural segment, which is a variable, a [`Store`] integer, a [stat](TextContext::stat)
+    /// such as `stats.choices_made`, or a literal number.
+    fn plural_count(count: &str, context: &TextContext) -> Option<i64> {
+        let count = count.trim();
+        if let Some(StoreValue::Int(value)) = context.store.get(count) {
+            return Some(*value);
+        }
+        context
+            .global_variable(count)
+            .or_else(|| {
+                count
+                    .strip_prefix("stats.")
+                    .and_then(|stat| context.stat(stat))
+            })
+            .or(context.variables.get(count).cloned())
+            .as_deref()
+            .unwrap_or(count)
+            .trim()
+            .parse()
+            .ok()
+    }
+
+    /// Replaces every plural segment in some content with the form matching its count.
+    ///
+    /// A plural segment takes the form `{plural:count:singular:plural}`, with as many forms as the language's
+    /// [rule](crate::core::text::plural::PluralRule) needs, and any `#` in the chosen form is replaced with the count.
+    /// If the count can't be resolved to a whole number, the last form is used, and a warning is logged in debug mode.
+    pub fn fill_plurals(content: &str, context: &TextContext) -> String {
+        let mut result = String::with_capacity(content.len());
+        let mut rest = content;
+        while let Some(start) = rest.find(Self::PLURAL) {
+            let after = &rest[start + Self::PLURAL.len()..];
+            let Some(end) = after.find('}') else {
+                break;
+            };
+            result.push_str(&rest[..start]);
+            let mut parts = after[..end].split(':');
+            let count = parts.next().unwrap_or_default();
+            let forms: Vec<&str> = parts.collect();
+            match Self::plural_count(count, context) {
+                Some(value) => {
+                    let form = context.plural(value, &forms);
+                    result.push_str(&form.replace('#', &value.to_string()));
+                }
+                None => {
+                    if context.config().settings.debug {
+                        log::warn!("Plural count '{count}' isn't a whole number");
+                    }
+                    result.push_str(forms.last().copied().unwrap_or_default());
+                }
+            }
+            rest = &after[end + 1..];
+        }
+        result.push_str(rest);
+        result
+    }
+
     /// Fills all templating areas with the proper context values provided by the [`TextContext`].
+    ///
+    /// [Snippets](Self::fill_snippets) are expanded first, then [conditional segments](Self::conditionals) are evaluated,
+    /// then [store values](Self::fill_store), then [stats](Self::fill_stats), then [plural segments](Self::fill_plurals),
+    /// then scripts, then variables.
     pub fn fill(&self, context: &TextContext) -> Result<String> {
-        let content = self.lang_file_content(context.lang_file());
-        let scripted = Self::template(content, '(', ')', move |var| {
+        let content =
+            Self::fill_snippets(self.lang_file_content(context), &context.config().snippets)?;
+        let content = Self::conditionals(&content, context)?;
+        let content = Self::fill_store(&content, &context.store);
+        let content = Self::fill_stats(&content, context);
+        let content = Self::fill_plurals(&content, context);
+        let scripted = Self::template(&content, '(', ')', move |var| {
             context.resources().scripts.get(var, context)
         })?;
         Self::template(&scripted, '<', '>', move |var| {
+            if let Some(value) = context.audio_variable(var) {
+                return value.map(Some);
+            }
+            if let Some(visited) = context.visited_variable(var) {
+                return Ok(Some(visited.to_string()));
+            }
             let filled = Self::fill_variable(var, &context.variables, &context).map(|s| s.clone());
             Ok(filled)
         })
     }
+
+    /// Checks every [audio reference](TextContext::audio_variable) in this string's variables and conditions
+    /// with [`TextContext::validate_audio_variable`]. Also checks that every [stat reference](Self::fill_stats)
+    /// names a [stat](TextContext::STATS) that exists and that every [snippet reference](Self::fill_snippets) names a snippet that exists.
+    pub fn validate_references(&self, config: &Manifest) -> Result<()> {
+        for (start, _) in self.content.match_indices(Self::SNIPPET) {
+            let rest = &self.content[start + Self::SNIPPET.len()..];
+            if let Some((name, _, _)) = Self::snippet_reference(rest) {
+                if !config.snippets.contains_key(name) {
+                    return Err(anyhow!("Unknown snippet '{name}'"));
+                }
+            }
+        }
+        for rest in self.content.split(Self::STATS).skip(1) {
+            let Some((stat, _)) = rest.split_once('}') else {
+                continue;
+            };
+            if !TextContext::STATS.contains(&stat.trim()) {
+                return Err(anyhow!("Unknown stat '{}'", stat.trim()));
+            }
+        }
+        let variables = self
+            .content
+            .split('<')
+            .skip(1)
+            .filter_map(|rest| rest.split_once('>'))
+            .map(|(var, _)| (var, false));
+        let conditions = self
+            .content
+            .split(Self::CONDITIONAL)
+            .skip(1)
+            .filter_map(|rest| rest.split_once(':'))
+            .map(|(condition, _)| (condition.trim().trim_start_matches('!').trim(), true));
+        for (var, condition) in variables.chain(conditions) {
+            TextContext::validate_audio_variable(var, condition)?;
+        }
+        Ok(())
+    }
 }
 
 impl PartialEq<String> for TemplatableString {
@@ -205,6 +592,11 @@ impl<T> TemplatableValue<T> {
         }
     }
 
+    /// Returns the templatable string this value is filled from, if it isn't provided as-is.
+    pub fn templated(&self) -> Option<&TemplatableString> {
+        self.template.as_ref()
+    }
+
     /// Gets the value of type `T` from the templatable value.
     ///
     /// If the value is provided as-is, returns a clone of that value.
@@ -230,3 +622,141 @@ impl<T> TemplatableValue<T> {
         unreachable!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{context::StaticContext, resources::Resources};
+
+    /// Runs `f` with a text context for a player with the given notes, playtime in seconds, and choices made.
+    fn with_context<T>(
+        notes: &[&str],
+        playtime: u64,
+        choices_made: u64,
+        f: impl FnOnce(&TextContext) -> T,
+    ) -> T {
+        let config = Resources::test_manifest("test", "");
+        let resources = Resources::empty(&config);
+        let stc = StaticContext::new(&config, &resources);
+        let notes = notes.iter().map(|note| note.to_string()).collect();
+        let context = TextContext::new(
+            &stc,
+            "en_us".to_owned(),
+            notes,
+            Variables::new(),
+            Default::default(),
+            Store::new(),
+            playtime,
+        )
+        .with_choices_made(choices_made);
+        f(&context)
+    }
+
+    /// Evaluates the conditional segments in `content` with the given notes.
+    fn conditionals(content: &str, notes: &[&str]) -> Result<String> {
+        with_context(notes, 0, 0, |context| TemplatableString::conditionals(content, context))
+    }
+
+    /// Fills `content` entirely for a player with the given playtime in seconds and choices made.
+    fn fill(content: &str, playtime: u64, choices_made: u64) -> String {
+        with_context(&[], playtime, choices_made, |context| {
+            TemplatableString::from(content.to_owned())
+                .fill(context)
+                .unwrap()
+        })
+    }
+
+    #[test]
+    fn picks_branches() {
+        let content = "{if met: Hello again. | Nice to meet you.}";
+        assert_eq!(conditionals(content, &["met"]).unwrap(), "Hello again.");
+        assert_eq!(conditionals(content, &[]).unwrap(), "Nice to meet you.");
+        let content = "{if !met: Nice to meet you. | Hello again.}";
+        assert_eq!(conditionals(content, &["met"]).unwrap(), "Hello again.");
+        assert_eq!(conditionals(content, &[]).unwrap(), "Nice to meet you.");
+    }
+
+    #[test]
+    fn missing_false_branch_is_empty() {
+        let content = "You wave{if met: and smile}.";
+        assert_eq!(conditionals(content, &["met"]).unwrap(), "You wave and smile.");
+        assert_eq!(conditionals(content, &[]).unwrap(), "You wave.");
+    }
+
+    #[test]
+    fn nests_segments() {
+        let content = "{if a: {if b: both | only a} | {if !b: neither | only b}}";
+        assert_eq!(conditionals(content, &["a", "b"]).unwrap(), "both");
+        assert_eq!(conditionals(content, &["a"]).unwrap(), "only a");
+        assert_eq!(conditionals(content, &["b"]).unwrap(), "only b");
+        assert_eq!(conditionals(content, &[]).unwrap(), "neither");
+    }
+
+    #[test]
+    fn unescapes_delimiters() {
+        let content = r"\{if a: b\} {if a: x\|y\: z | no} \\";
+        assert_eq!(conditionals(content, &["a"]).unwrap(), r"{if a: b} x|y: z \");
+    }
+
+    #[test]
+    fn keeps_spaces_outside_delimiters() {
+        let content = "You {if met: warmly | coldly} wave.";
+        assert_eq!(conditionals(content, &["met"]).unwrap(), "You warmly wave.");
+        assert_eq!(conditionals(content, &[]).unwrap(), "You coldly wave.");
+    }
+
+    #[test]
+    fn leaves_backslashes_without_segments() {
+        let content = r"C:\Games\nage and a \{brace\}";
+        assert_eq!(conditionals(content, &[]).unwrap(), content);
+    }
+
+    #[test]
+    fn leaves_other_braces() {
+        let content = "{red}Danger{/red} and {var.gold} gold";
+        assert_eq!(conditionals(content, &[]).unwrap(), content);
+    }
+
+    #[test]
+    fn rejects_unclosed_segments() {
+        let err = conditionals("{if met Hello}", &["met"]).unwrap_err();
+        assert!(err.to_string().contains("missing a ':'"), "{err}");
+        let err = conditionals("{if met: Hello | Goodbye", &["met"]).unwrap_err();
+        assert!(err.to_string().contains("missing a '}'"), "{err}");
+    }
+
+    #[test]
+    fn validates_audio_fields_on_any_channel() {
+        let config = Resources::test_manifest("test", "");
+        let validate = |content: &str| {
+            TemplatableString::from(content.to_owned()).validate_references(&config)
+        };
+        assert!(validate("<audio.ambience.volume> {if audio.ambience.is_playing: on}").is_ok());
+        let err = validate("<audio.music.loudness>").unwrap_err();
+        assert!(err.to_string().contains("no field 'loudness'"), "{err}");
+        let err = validate("{if audio.music.volume: loud}").unwrap_err();
+        assert!(err.to_string().contains("isn't a boolean"), "{err}");
+    }
+
+    #[test]
+    fn formats_stats() {
+        assert_eq!(fill("{stats.playtime}", 0, 0), "0m");
+        assert_eq!(fill("{stats.playtime}", 59, 0), "0m");
+        assert_eq!(fill("{stats.playtime}", 45 * 60, 0), "45m");
+        assert_eq!(fill("{stats.playtime}", 2 * 3600 + 13 * 60 + 30, 0), "2h 13m");
+        assert_eq!(fill("{stats.playtime}", 3600, 0), "1h 0m");
+        assert_eq!(fill("{stats.choices_made}", 0, 0), "0");
+        assert_eq!(fill("Choices: {stats.choices_made}", 0, 12), "Choices: 12");
+    }
+
+    #[test]
+    fn fills_stats_as_variables() {
+        assert_eq!(fill("<nage:playtime> / <nage:choices_made>", 3 * 60, 4), "3m / 4");
+    }
+
+    #[test]
+    fn unknown_stats_are_empty() {
+        assert_eq!(fill("[{stats.gold}]", 0, 0), "[]");
+        assert_eq!(fill("{stats.playtime", 0, 0), "{stats.playtime");
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use crossterm::{
+    event::{self, Event, KeyEvent, KeyEventKind},
+    terminal,
+};
+
+use crate::core::pause::PauseAction;
+
+/// The longest time to wait between checking for a skipping keypress.
+const FRAME: Duration = Duration::from_millis(16);
+
+/// A piece of content that is revealed at once.
+struct Unit<'a> {
+    content: &'a str,
+    /// Whether this unit takes up space on the terminal, as opposed to being an escape sequence.
+    visible: bool,
+}
+
+/// Splits content into [`Unit`]s; one for each character and one for each whole ANSI escape sequence.
+///
+/// Escape sequences are kept whole so that formatting is never split by a reveal.
+fn units(content: &str) -> Vec<Unit<'_>> {
+    let mut result = Vec::new();
+    let mut chars = content.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let mut end = start + c.len_utf8();
+        let visible = c != '\x1b';
+        if !visible {
+            match chars.next() {
+                // Control sequences end with a byte in the range '@'..='~'
+                Some((_, '[')) => {
+                    for (index, c) in chars.by_ref() {
+                        end = index + c.len_utf8();
+                        if ('@'..='~').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                Some((index, c)) => end = index + c.len_utf8(),
+                None => {}
+            }
+        }
+        result.push(Unit {
+            content: &content[start..end],
+            visible,
+        });
+    }
+    result
+}
+
+/// The amount of characters in some content that take up space on the terminal, ignoring escape sequences.
+pub fn visible_len(content: &str) -> usize {
+    units(content).iter().filter(|unit| unit.visible).count()
+}
+
+/// Wraps some content on word boundaries so that no line is more than `width` characters wide.
+///
+/// Existing newlines are kept, and escape sequences don't count towards the width. A word that's wider than
+/// `width` on its own is left on its own line rather than split. Since the content is wrapped before it's
+/// [revealed](typewrite), a word is never moved to the next line after it has started printing.
+pub fn wrap(content: &str, width: usize) -> String {
+    let mut result = String::with_capacity(content.len());
+    for (index, line) in content.split('\n').enumerate() {
+        if index > 0 {
+            result.push('\n');
+        }
+        let mut column = 0;
+        for (index, word) in line.split(' ').enumerate() {
+            let length = visible_len(word);
+            if index > 0 {
+                if column > 0 && column + 1 + length > width {
+                    result.push('\n');
+                    column = 0;
+                } else {
+                    result.push(' ');
+                    column += 1;
+                }
+            }
+            result.push_str(word);
+            column += length;
+        }
+    }
+    result
+}
+
+/// Removes every escape sequence from some content, leaving only what takes up space on the terminal.
+pub fn strip_escapes(content: &str) -> String {
+    units(content)
+        .iter()
+        .filter(|unit| unit.visible)
+        .map(|unit| unit.content)
+        .collect()
+}
+
+/// Writes a unit while the terminal is in raw mode, where newlines don't return the cursor.
+fn write_unit(stdout: &mut impl Write, unit: &Unit) -> Result<()> {
+    if unit.content == "\n" {
+        write!(stdout, "\r\n")?;
+    } else {
+        write!(stdout, "{}", unit.content)?;
+    }
+    Ok(())
+}
+
+/// Reveals each unit `delay` apart, revealing the rest at once if any key is pressed.
+///
+/// Each key press is first given to `pause`, which returns the [`PauseAction`] picked if it paused the game.
+/// Revealing is frozen while paused, and the content revealed so far is written again afterward.
+fn reveal(
+    units: &[Unit],
+    delay: Duration,
+    mut pause: impl FnMut(&KeyEvent) -> Result<Option<PauseAction>>,
+) -> Result<()> {
+    let mut stdout = io::stdout();
+    let mut start = Instant::now();
+    let mut index = 0;
+    let mut shown = 0;
+    while index < units.len() {
+        let target = (start.elapsed().as_secs_f64() / delay.as_secs_f64()) as usize + 1;
+        while index < units.len() && (shown < target || !units[index].visible) {
+            write_unit(&mut stdout, &units[index])?;
+            shown += units[index].visible as usize;
+            index += 1;
+        }
+        stdout.flush()?;
+        if index == units.len() || !event::poll(FRAME.min(delay))? {
+            continue;
+        }
+        if let Event::Key(key) = event::read()? {
+            let paused = Instant::now();
+            match pause(&key)? {
+                Some(PauseAction::Quit) => {}
+                Some(_) => {
+                    start += paused.elapsed();
+                    for unit in &units[..index] {
+                        write_unit(&mut stdout, unit)?;
+                    }
+                    stdout.flush()?;
+                    continue;
+                }
+                None if key.kind != KeyEventKind::Press => continue,
+                None => {}
+            }
+            for unit in &units[index..] {
+                write_unit(&mut stdout, unit)?;
+            }
+            stdout.flush()?;
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Prints content character by character, `delay` apart, followed by a newline.
+///
+/// Pressing any key while the content is being printed reveals the rest of it immediately, unless `pause` handles it;
+/// see [`reveal`]. If `delay` is zero or the terminal doesn't support reading keys, the content is printed all at once.
+pub fn typewrite(
+    content: &str,
+    delay: Duration,
+    pause: impl FnMut(&KeyEvent) -> Result<Option<PauseAction>>,
+) -> Result<()> {
+    if delay.is_zero() || terminal::enable_raw_mode().is_err() {
+        println!("{content}");
+        return Ok(());
+    }
+    let result = reveal(&units(content), delay, pause);
+    terminal::disable_raw_mode()?;
+    println!();
+    result
+}
@@ -0,0 +1,68 @@
+use std::cell::{Cell, RefCell};
+
+use anyhow::Result;
+use camino::Utf8PathBuf;
+
+use crate::loading::saves::SaveManager;
+
+use super::manifest::Manifest;
+
+#[derive(Debug)]
+/// A readable record of the text shown and the choices taken during a session, exported as Markdown.
+///
+/// Text is recorded after it's templated but without any styling. Prompts can opt out of being recorded
+/// with their `transcript` key, such as for spoilers or debugging scaffolding.
+pub struct Transcript {
+    entries: RefCell<Vec<String>>,
+    /// Whether the current prompt is recorded.
+    recording: Cell<bool>,
+}
+
+impl Default for Transcript {
+    fn default() -> Self {
+        Self {
+            entries: RefCell::new(Vec::new()),
+            recording: Cell::new(true),
+        }
+    }
+}
+
+impl Transcript {
+    /// Starts recording a new prompt, or stops recording until the next one if it has opted out.
+    pub fn enter(&self, recorded: bool) {
+        self.recording.set(recorded);
+    }
+
+    fn push(&self, entry: String) {
+        if self.recording.get() {
+            self.entries.borrow_mut().push(entry);
+        }
+    }
+
+    /// Records some printed text, which should already be [plain](crate::core::text::display::Text::get_plain).
+    pub fn record_text(&self, text: &str) {
+        self.push(text.to_owned());
+    }
+
+    /// Records the response of a choice the player took, or what they entered at an input prompt.
+    pub fn record_choice(&self, response: &str) {
+        self.push(format!("> **{}**", response.trim()));
+    }
+
+    /// Formats the transcript as a Markdown document titled with the game's name.
+    pub fn to_markdown(&self, config: &Manifest) -> String {
+        let entries = self.entries.borrow();
+        format!("# {}\n\n{}\n", config.metadata.name, entries.join("\n\n"))
+    }
+
+    /// Writes the transcript to a new file in the game's data directory, returning its path.
+    pub fn export(&self, config: &Manifest) -> Result<Utf8PathBuf> {
+        let dir = SaveManager::generic_dir()?
+            .join(config.metadata.game_id())
+            .join("transcripts");
+        std::fs::create_dir_all(&dir)?;
+        let file = dir.join(format!("{}.md", chrono::Local::now().format("%Y-%m-%d_%H-%M-%S")));
+        std::fs::write(&file, self.to_markdown(config))?;
+        Ok(file)
+    }
+}
@@ -1,3 +1,6 @@
+#[cfg(feature = "gamepad")]
+pub mod gamepad;
 pub mod gloop;
 pub mod input;
 pub mod main;
+pub mod overlay;
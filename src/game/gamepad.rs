@@ -0,0 +1,153 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::Read,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::core::manifest::GamepadBindings;
+
+/// An action triggered by a gamepad at a choice prompt.
+pub enum GamepadAction {
+    /// Moves the selection to the previous choice.
+    Up,
+    /// Moves the selection to the next choice.
+    Down,
+    /// Submits the selected choice.
+    Confirm,
+    Back,
+    Pause,
+}
+
+/// An event read from the joystick device; see the Linux joystick API.
+#[derive(Debug)]
+struct JoystickEvent {
+    value: i16,
+    kind: u8,
+    number: u8,
+}
+
+impl JoystickEvent {
+    const BUTTON: u8 = 0x01;
+    const AXIS: u8 = 0x02;
+    /// Flags events describing the initial state of the device rather than a change to it.
+    const INIT: u8 = 0x80;
+
+    fn parse(bytes: [u8; 8]) -> Self {
+        Self {
+            value: i16::from_le_bytes([bytes[4], bytes[5]]),
+            kind: bytes[6],
+            number: bytes[7],
+        }
+    }
+}
+
+/// Reads a gamepad through the Linux joystick device, mapping its buttons and axes to [`GamepadAction`]s.
+///
+/// The device is read on a separate thread so that polling never blocks. If no gamepad is connected, or it's
+/// unplugged, the device is looked for again every so often so that one can be plugged in mid-session.
+#[derive(Debug)]
+pub struct Gamepad {
+    bindings: GamepadBindings,
+    events: Option<Receiver<JoystickEvent>>,
+    /// When the device was last looked for, if it has been.
+    scanned: Option<Instant>,
+    /// The direction each axis is held in, so that holding a stick only moves the selection once.
+    held: HashMap<u8, i8>,
+}
+
+impl Gamepad {
+    const DEVICE: &'static str = "/dev/input/js0";
+
+    /// How often the device is looked for while no gamepad is connected.
+    const SCAN_INTERVAL: Duration = Duration::from_secs(1);
+
+    pub fn new(bindings: &GamepadBindings) -> Self {
+        Self {
+            bindings: bindings.clone(),
+            events: None,
+            scanned: None,
+            held: HashMap::new(),
+        }
+    }
+
+    /// Opens the device if it isn't already and it hasn't been looked for recently.
+    fn connect(&mut self) {
+        if self.events.is_some()
+            || self
+                .scanned
+                .is_some_and(|scanned| scanned.elapsed() < Self::SCAN_INTERVAL)
+        {
+            return;
+        }
+        self.scanned = Some(Instant::now());
+        let Ok(mut device) = File::open(Self::DEVICE) else {
+            return;
+        };
+        log::info!("Gamepad connected");
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut bytes = [0; 8];
+            while device.read_exact(&mut bytes).is_ok() {
+                if sender.send(JoystickEvent::parse(bytes)).is_err() {
+                    break;
+                }
+            }
+        });
+        self.held.clear();
+        self.events = Some(receiver);
+    }
+
+    /// Maps an event to an action, if it's bound to one.
+    fn action(&mut self, event: JoystickEvent) -> Option<GamepadAction> {
+        use GamepadAction::*;
+        if event.kind & JoystickEvent::INIT != 0 {
+            return None;
+        }
+        let bindings = &self.bindings;
+        match event.kind {
+            JoystickEvent::BUTTON if event.value == 1 => match Some(event.number) {
+                number if number == bindings.confirm => Some(Confirm),
+                number if number == bindings.back => Some(Back),
+                number if number == bindings.pause => Some(Pause),
+                _ => None,
+            },
+            JoystickEvent::AXIS if bindings.vertical.contains(&event.number) => {
+                let direction = match event.value {
+                    value if value <= -bindings.threshold => -1,
+                    value if value >= bindings.threshold => 1,
+                    _ => 0,
+                };
+                let previous = self.held.insert(event.number, direction);
+                match direction {
+                    _ if previous == Some(direction) => None,
+                    -1 => Some(Up),
+                    1 => Some(Down),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the next action triggered since the last poll, if any, without blocking.
+    pub fn poll(&mut self) -> Option<GamepadAction> {
+        self.connect();
+        loop {
+            let event = match self.events.as_ref()?.try_recv() {
+                Ok(event) => event,
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => {
+                    log::info!("Gamepad disconnected");
+                    self.events = None;
+                    return None;
+                }
+            };
+            if let Some(action) = self.action(event) {
+                return Some(action);
+            }
+        }
+    }
+}
@@ -1,22 +1,31 @@
-use anyhow::Result;
+use std::{
+    sync::atomic::Ordering,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context, Result};
 use result::OptionResultExt;
 
 use crate::{
-    cmd::runtime::CommandResult,
+    cmd::runtime::{CommandResult, RuntimeCommand},
     core::{
         choice::UsableChoices,
         context::{StaticContext, TextContext},
         discord::RichPresence,
+        path::PathData,
+        pause::PauseAction,
         player::Player,
-        prompt::PromptModel,
-        state::variables::NamedVariableEntry,
+        prompt::{ChoiceArrangement, IdleHandler, Prompt, PromptModel},
+        scripts::SequenceStep,
+        state::{preferences::Preferences, selection::Selection, variables::NamedVariableEntry},
         text::display::Text,
     },
     game::input::{InputContext, InputResult},
     loading::saves::SaveManager,
+    text_context,
 };
 
-use super::input::InputController;
+use super::{input::InputController, overlay::DebugOverlay};
 
 pub fn next_input_context(
     model: &PromptModel,
@@ -25,11 +34,26 @@ pub fn next_input_context(
 ) -> Result<Option<InputContext>> {
     use PromptModel::*;
     let result = match &model {
-        Response => Some(InputContext::Choices(choices.len())),
-        &Input(name, prompt) => Some(InputContext::Variable(
+        Response => {
+            Some(InputContext::Choices(choices.iter().map(|(choice, ..)| choice.key).collect()))
+        }
+        &Input(name, input) => Some(InputContext::Variable(
             name.clone(),
-            prompt.map(|s| s.fill(text_context)).invert()?,
+            input.text.as_ref().map(|s| s.fill(text_context)).invert()?,
+            input
+                .completions
+                .iter()
+                .flatten()
+                .map(|completion| completion.fill(text_context))
+                .try_collect()?,
+            input
+                .range
+                .as_ref()
+                .map(|range| range.bounds(text_context))
+                .invert()?,
+            input.masked,
         )),
+        &Select(_, selection) => Some(InputContext::Selection(selection.options.len())),
         _ => None,
     };
     Ok(result)
@@ -39,6 +63,10 @@ pub enum GameLoopResult {
     Retry(bool),
     Continue,
     Shutdown(bool),
+    /// Reloads the game resources and re-enters the current prompt.
+    Reload,
+    /// Tries to open the sound device again and re-enters the current prompt.
+    ReconnectAudio,
 }
 
 pub fn handle_quit(shutdown: bool) -> GameLoopResult {
@@ -51,11 +79,130 @@ pub fn handle_quit(shutdown: bool) -> GameLoopResult {
     }
 }
 
-pub fn take_input(
-    input: &mut InputController,
-    context: &InputContext,
+/// Carries out the saves and loads that scripts have requested since the last call.
+///
+/// Saves are written first. Returns `true` if the player was replaced by a loaded save, in which case the
+/// current prompt should be left and the loop re-entered at the loaded position.
+pub fn apply_save_requests(
+    player: &mut Player,
+    saves: &SaveManager,
+    stc: &StaticContext,
+) -> Result<bool> {
+    let scripts = &stc.resources.scripts;
+    let saving = scripts.take_saves()?;
+    if !saving.is_empty() {
+        stc.resources.record_audio(player);
+    }
+    for slot in saving {
+        if let Err(err) = saves.write_slot(&slot, player) {
+            println!("Failed to save to '{slot}': {err:#}");
+        }
+    }
+    let Some(slot) = scripts.take_load()? else {
+        return Ok(false);
+    };
+    let loaded = saves
+        .load_slot(&slot, stc.config)
+        .with_context(|| anyhow!("Failed to load save slot '{slot}'"))?;
+    stc.resources.replace_player(player, loaded, stc.config)?;
+    println!();
+    Ok(true)
+}
+
+/// The outcome of [running a hook](run_hook).
+pub enum HookResult {
+    /// The hook moved the player elsewhere.
+    Moved,
+    /// The player stayed, with the choice arrangement an `on_enter` hook returned, if any.
+    Stayed(Option<ChoiceArrangement>),
+}
+
+/// Runs a prompt's [`on_enter`](Prompt::on_enter) or [`on_exit`](Prompt::on_exit) hook, then applies the changes it requested.
+///
+/// A hook moves the player if it loads a save, or if it jumps to another prompt, given relative to the file of the hook's
+/// prompt at `path`. A jump [redirects](Player::redirect) the latest history entry rather than adding one, so:
+/// - A jump from `on_enter` leaves the prompt without displaying it or running its `on_exit` hook, and going back skips it.
+/// - A jump from `on_exit` replaces the destination of the choice that was made, whose `on_enter` hook never runs.
+///
+/// If `arranging` a prompt, the hook is passed the prompt's [arrangement](Prompt::arrangement), and any it returns is
+/// [validated](Prompt::validate_arrangement) before it's used.
+pub fn run_hook(
+    script: &str,
+    path: &PathData,
+    arranging: Option<&Prompt>,
     player: &mut Player,
     saves: &SaveManager,
+    stc: &StaticContext,
+) -> Result<HookResult> {
+    let scripts = &stc.resources.scripts;
+    // Jumps requested by scripts other than hooks are ignored
+    scripts.take_jump()?;
+    let text_context = text_context!(stc, player);
+    let used = player.used_choices_at(path);
+    let arrangement: Option<ChoiceArrangement> = match arranging {
+        Some(prompt) => {
+            let arrangement = prompt.arrangement(&path.file, &used, &text_context)?;
+            scripts.run(script, arrangement, &text_context)?
+        }
+        None => scripts.run(script, (), &text_context)?,
+    };
+    player.apply_script_changes(stc, &text_context)?;
+    if apply_save_requests(player, saves, stc)? {
+        return Ok(HookResult::Moved);
+    }
+    if let Some(jump) = scripts.take_jump()? {
+        let target = PathData::parse(&jump, &path.file);
+        Prompt::get(&stc.resources.prompts, &target)
+            .with_context(|| anyhow!("Hook script {script} jumped to an invalid prompt"))?;
+        log::info!("Hook script {script} jumped to '{target}'");
+        player.redirect(&target)?;
+        return Ok(HookResult::Moved);
+    }
+    if let (Some(prompt), Some(arrangement)) = (arranging, &arrangement) {
+        prompt
+            .validate_arrangement(arrangement, path, &used, &stc.resources.prompts)
+            .with_context(|| {
+                anyhow!("Hook script {script} arranged the choices of '{path}' invalidly")
+            })?;
+    }
+    Ok(HookResult::Stayed(arrangement))
+}
+
+/// Runs a script [sequence](crate::core::scripts::Scripts::start_sequence) to completion, carrying out each step it yields.
+///
+/// Waiting steps are skipped while [fast-forwarding](crate::core::fast_forward::FastForward).
+pub fn run_sequence(
+    file: &str,
+    player: &Player,
+    stc: &StaticContext,
+    text_context: &TextContext,
+) -> Result<()> {
+    let resources = stc.resources;
+    let fast_forward = &resources.fast_forward;
+    resources.scripts.start_sequence(file, text_context)?;
+    while let Some(step) = resources
+        .scripts
+        .resume_sequence()
+        .with_context(|| anyhow!("Script sequence '{file}' failed"))?
+    {
+        match step {
+            SequenceStep::Say(text) => stc.config.settings.text.speed.print(&text, text_context)?,
+            SequenceStep::Sound(action) => {
+                resources.submit_audio(player, &vec![*action], text_context)?
+            }
+            _ if fast_forward.is_active() => {}
+            SequenceStep::Wait(duration) => resources.wait(duration)?,
+            SequenceStep::WaitSound(channel) => resources.await_channel(&channel)?,
+            SequenceStep::WaitKey => resources.await_key()?,
+        }
+    }
+    Ok(())
+}
+
+/// Makes the usable choice at `index` and prints its ending, if any.
+fn use_choice(
+    index: usize,
+    player: &mut Player,
     drpc: &mut Option<RichPresence>,
     model: &PromptModel,
     choices: &UsableChoices,
@@ -63,32 +210,366 @@ pub fn take_input(
     text_context: &TextContext,
 ) -> Result<GameLoopResult> {
     use GameLoopResult::*;
-    let result = match input.take(context) {
+    let (choice, once, arranged) = &choices[index];
+    let file = player.latest_entry()?.path.file.clone();
+    log::info!("Took choice #{} at '{}'", index + 1, player.latest_entry()?.path);
+    if let Some(response) = &choice.response {
+        let text = match arranged.and_then(|arranged| arranged.text.as_deref()) {
+            Some(text) => response.get_plain_as(text, text_context)?,
+            None => response.get_plain(text_context)?,
+        };
+        stc.resources.transcript.record_choice(&text);
+    }
+    player.choose(choice, once, None, model, stc, text_context)?;
+    if let Some(target) = arranged.and_then(|arranged| arranged.target.as_deref()) {
+        player.redirect(&PathData::parse(target, &file))?;
+    }
+    player.after_choice(choice, stc, drpc)?;
+
+    let result = match &choice.ending {
+        Some(ending) => {
+            println!();
+            Text::print_lines(ending, player, text_context)?;
+            Shutdown(true)
+        }
+        None => Continue,
+    };
+    Ok(result)
+}
+
+/// Carries out an action picked from the [pause menu](crate::core::pause::PauseMenu).
+fn use_pause_action(
+    action: PauseAction,
+    player: &mut Player,
+    saves: &SaveManager,
+    stc: &StaticContext,
+    text_context: &TextContext,
+) -> Result<GameLoopResult> {
+    use GameLoopResult::*;
+    let started = Instant::now();
+    let result = match action {
+        PauseAction::Resume => Retry(false),
+        PauseAction::Save => {
+            stc.resources.record_audio(player);
+            saves.write(player)?;
+            println!("Saving... {}", player.playtime_display());
+            Retry(true)
+        }
+        PauseAction::Settings => pause_settings(player, saves, stc, text_context)?,
+        PauseAction::Quit => Shutdown(false),
+    };
+    stc.resources.pause.add_paused(started.elapsed());
+    Ok(result)
+}
+
+/// Shows the settings reachable from the pause menu until the player goes back.
+///
+/// Volume, text speed, and auto-advance changes are saved to the player's [`Preferences`] and take effect right away;
+/// the other settings run the matching [`RuntimeCommand`]s.
+fn pause_settings(
+    player: &mut Player,
+    saves: &SaveManager,
+    stc: &StaticContext,
+    text_context: &TextContext,
+) -> Result<GameLoopResult> {
+    loop {
+        let preferences = &player.preferences;
+        let volume = (preferences.master_volume(stc.config) * 100.0).round();
+        let choices = [
+            format!("Master volume ({volume}%)"),
+            format!("Text speed ({}x)", preferences.text_speed()),
+            format!(
+                "Auto-advance ({})",
+                match preferences.auto_advance(stc.config) {
+                    true => "on",
+                    false => "off",
+                }
+            ),
+            format!(
+                "Accessibility mode ({})",
+                match preferences.accessibility(stc.config) {
+                    true => "on",
+                    false => "off",
+                }
+            ),
+            format!("Forget seen text ({} prompts)", saves.seen_count()),
+            "Sound channels".to_owned(),
+            "Language".to_owned(),
+            "Back".to_owned(),
+        ];
+        println!();
+        let question = requestty::Question::select("Settings")
+            .choices(choices)
+            .build();
+        let index = requestty::prompt_one(question)?
+            .as_list_item()
+            .unwrap()
+            .index;
+        let command = match index {
+            0 => {
+                let question = requestty::Question::int("volume")
+                    .message("Master volume (0-100)")
+                    .default(volume as i64)
+                    .validate(|volume, _| match volume {
+                        0..=100 => Ok(()),
+                        _ => Err("Volume must be between 0 and 100".to_owned()),
+                    })
+                    .build();
+                let volume = requestty::prompt_one(question)?.as_int().unwrap();
+                player.preferences.master_volume = Some(volume as f32 / 100.0);
+                stc.resources.apply_preferences(player, stc.config)?;
+                continue;
+            }
+            1 => {
+                let question = requestty::Question::select("Text speed")
+                    .choices(Preferences::TEXT_SPEEDS.map(|speed| format!("{speed}x")))
+                    .build();
+                let index = requestty::prompt_one(question)?
+                    .as_list_item()
+                    .unwrap()
+                    .index;
+                player.preferences.text_speed = Some(Preferences::TEXT_SPEEDS[index]);
+                continue;
+            }
+            2 => {
+                let enabled = player.preferences.auto_advance(stc.config);
+                player.preferences.auto_advance = Some(!enabled);
+                continue;
+            }
+            3 => {
+                let enabled = player.preferences.accessibility(stc.config);
+                player.preferences.accessibility = Some(!enabled);
+                continue;
+            }
+            4 => {
+                let question = requestty::Question::confirm("forget")
+                    .message("Forget every prompt you've seen? Fast-forwarding will stop at all of them again")
+                    .default(false)
+                    .build();
+                if requestty::prompt_one(question)?.as_bool().unwrap_or(false) {
+                    saves.forget_seen();
+                }
+                continue;
+            }
+            5 => RuntimeCommand::Sound,
+            6 => RuntimeCommand::Lang,
+            _ => return Ok(GameLoopResult::Retry(true)),
+        };
+        match command.run(player, saves, stc, text_context) {
+            Err(err) => println!("Error: {err}"),
+            Ok(CommandResult::Submit(result)) => return Ok(result),
+            Ok(CommandResult::Output(output)) => println!("{output}"),
+        }
+    }
+}
+
+/// Fires an [`IdleHandler`], printing its text and running its sequence before jumping, if it does.
+fn use_idle_handler(
+    handler: &IdleHandler,
+    player: &mut Player,
+    stc: &StaticContext,
+    text_context: &TextContext,
+) -> Result<GameLoopResult> {
+    log::info!("Idle handler fired at '{}'", player.latest_entry()?.path);
+    if let Some(lines) = &handler.text {
+        Text::print_lines(lines, player, text_context)?;
+    }
+    if let Some(sequence) = &handler.sequence {
+        run_sequence(sequence, player, stc, text_context)?;
+    }
+    let Some(jump) = &handler.jump else {
+        return Ok(GameLoopResult::Retry(true));
+    };
+    let target = jump.fill(&player.latest_entry()?.path, text_context)?;
+    player.jump(&target, stc.config);
+    Ok(GameLoopResult::Continue)
+}
+
+/// The state of a prompt that input is being taken at, which is carried over between retries.
+pub struct InputState<'a> {
+    pub model: &'a PromptModel<'a>,
+    pub choices: &'a UsableChoices<'a>,
+    /// The index of the [timed](crate::core::choice::Choice::find_timed) choice and its remaining time, if any.
+    pub timed: Option<(usize, Duration)>,
+    /// The prompt's idle handler and its period, if it hasn't fired for the last time yet.
+    pub idle: Option<(&'a IdleHandler, Duration)>,
+}
+
+/// Takes input at a prompt and applies it to the player, resolving the [`InputContext`] from the prompt's `state`.
+///
+/// If the state has a `timed` choice, a countdown is shown while reading input, and the choice is made once it runs out.
+/// The remaining time is carried over between retries.
+///
+/// If the state has an `idle` handler, the handler fires once the player goes its period without pressing a key.
+/// Unless it repeats, it's then removed so that it fires at most once.
+///
+/// While [fast-forwarding](crate::core::fast_forward::FastForward), a prompt with a single untimed choice
+/// is advanced through without reading input. With auto-advance on, such a prompt is advanced through after
+/// a [delay](crate::core::manifest::AutoAdvanceSettings::delay) instead, unless the player presses a key first.
+pub fn take_input(
+    input: &mut InputController,
+    state: &mut InputState,
+    player: &mut Player,
+    saves: &SaveManager,
+    drpc: &mut Option<RichPresence>,
+    stc: &StaticContext,
+    text_context: &TextContext,
+) -> Result<GameLoopResult> {
+    use GameLoopResult::*;
+    let (model, choices) = (state.model, state.choices);
+    let context = &next_input_context(model, choices, text_context)?
+        .ok_or(anyhow!("Could not resolve input context"))?;
+    if let Some(action) = stc.resources.pause.take_pending() {
+        return use_pause_action(action, player, saves, stc, text_context);
+    }
+    let fast_forward = &stc.resources.fast_forward;
+    // Choices asking for confirmation are never made without the player
+    let single = state.timed.is_none()
+        && matches!(model, PromptModel::Response)
+        && choices.len() == 1
+        && choices[0].0.confirmation(text_context)?.is_none();
+    if single && fast_forward.is_active() {
+        return use_choice(0, player, drpc, model, choices, stc, text_context);
+    }
+    if let (true, Some(printed)) = (single, stc.resources.take_auto_advance()) {
+        let settings = &stc.config.settings.text.auto_advance;
+        if player.preferences.auto_advance(stc.config)
+            && stc
+                .resources
+                .wait_cancelable(settings.delay(printed, text_context))?
+        {
+            return use_choice(0, player, drpc, model, choices, stc, text_context);
+        }
+        if let Some(action) = stc.resources.pause.take_pending() {
+            return use_pause_action(action, player, saves, stc, text_context);
+        }
+    }
+    input.set_accessible(text_context.is_accessible());
+    let overlay_shown = input.overlay_flag();
+    let mut overlay = DebugOverlay::new();
+    // Ticks run many times a second, so a failure that keeps happening is only logged once
+    let mut tick_error = None;
+    let tick = || {
+        if let Err(err) = stc.resources.tick_audio() {
+            let message = format!("{err:#}");
+            if tick_error.as_ref() != Some(&message) {
+                log::warn!("Failed to update audio: {message}");
+                tick_error = Some(message);
+            }
+        }
+        let _ = overlay.update(overlay_shown.load(Ordering::Relaxed), player, stc);
+    };
+    let idle_period = state.idle.as_ref().map(|(_, period)| *period);
+    let read = {
+        let layout = stc.resources.choice_layout.borrow();
+        let layout = layout
+            .as_ref()
+            .filter(|_| input.is_mouse_enabled() && matches!(context, InputContext::Choices(_)));
+        match (state.timed.as_mut(), idle_period, layout) {
+            (None, None, None) if !input.uses_gamepad(context) && !context.is_masked() => {
+                input.take(context, tick)
+            }
+            (timed, idle_period, layout) => input.take_timed(
+                context,
+                timed.map(|(_, remaining)| remaining),
+                idle_period,
+                layout,
+                tick,
+            ),
+        }
+    };
+    let result = match read {
         Err(err) => {
             println!("{err}");
             Retry(true)
         }
         Ok(result) => match result {
             InputResult::Quit(shutdown) => handle_quit(shutdown),
+            InputResult::Pause => {
+                let action = stc.resources.pause()?;
+                use_pause_action(action, player, saves, stc, text_context)?
+            }
+            InputResult::FastForward => {
+                match fast_forward.toggle() {
+                    true => println!("Fast-forwarding through seen text..."),
+                    false => println!("Stopped fast-forwarding"),
+                }
+                Retry(false)
+            }
             InputResult::Choice(i) => {
-                let (choice, once) = &choices[i - 1];
-                player.choose_full(choice, once, None, drpc, model, stc, text_context)?;
-
-                match &choice.ending {
-                    Some(ending) => {
-                        println!();
-                        Text::print_lines(ending, player, text_context)?;
-                        Shutdown(true)
+                let (choice, ..) = &choices[i - 1];
+                match choice.confirmation(text_context)? {
+                    Some(question) if !input.confirm(&question)? => {
+                        println!("Cancelled");
+                        Retry(true)
                     }
-                    None => Continue,
+                    _ => use_choice(i - 1, player, drpc, model, choices, stc, text_context)?,
                 }
             }
+            InputResult::Timeout => match state.timed {
+                Some((index, _)) => {
+                    use_choice(index, player, drpc, model, choices, stc, text_context)?
+                }
+                None => Retry(false),
+            },
+            InputResult::Idle => match state.idle.take() {
+                Some((handler, period)) => {
+                    if handler.repeat {
+                        state.idle = Some((handler, period));
+                    }
+                    use_idle_handler(handler, player, stc, text_context)?
+                }
+                None => Retry(false),
+            },
             InputResult::Variable { name, value } => {
                 // Modify variables after the choose call since history entries are sensitive to this order
                 let entry = NamedVariableEntry::new(name.clone(), value.clone(), &player.variables);
-                let (choice, once) = &choices[0];
+                let recorded = match context.is_masked() {
+                    true => InputContext::MASKED,
+                    false => &value,
+                };
+                stc.resources.transcript.record_choice(recorded);
+                let (choice, once, _) = &choices[0];
                 player.choose(choice, once, Some(entry), model, stc, text_context)?;
                 player.variables.insert(name, value);
+                if stc.config.settings.input_history.save {
+                    player.input_history = input.text_history();
+                }
+                player.after_choice(choice, stc, drpc)?;
+                Continue
+            }
+            InputResult::Toggle(numbers) => {
+                let PromptModel::Select(_, selection) = model else {
+                    return Ok(Retry(false));
+                };
+                let mut selected = stc.resources.selected.borrow_mut();
+                if let Err(err) = selection.toggle(&mut selected, &numbers) {
+                    println!("{err}");
+                }
+                println!("{}\n", selection.lines(&selected, text_context)?.join("\n"));
+                Retry(false)
+            }
+            InputResult::Confirm => {
+                let PromptModel::Select(name, selection) = model else {
+                    return Ok(Retry(false));
+                };
+                let selected = stc.resources.selected.take();
+                if let Err(err) = selection.check(&selected) {
+                    println!("{err}");
+                    stc.resources.selected.replace(selected);
+                    return Ok(Retry(true));
+                }
+                let values = selection.values(&selected, text_context)?;
+                let value = values.join(Selection::SEPARATOR);
+                stc.resources.transcript.record_choice(&value);
+                // Modify variables after the choose call since history entries are sensitive to this order
+                let entry = NamedVariableEntry::new(name.clone(), value.clone(), &player.variables);
+                let (choice, once, _) = &choices[0];
+                player.choose(choice, once, Some(entry), model, stc, text_context)?;
+                player.variables.insert(name.clone(), value);
+                if let Some(items) = selection.items(&values) {
+                    player.gain_items(items);
+                }
                 player.after_choice(choice, stc, drpc)?;
                 Continue
             }
@@ -97,7 +578,10 @@ pub fn take_input(
                     Err(err) => println!("\n{err}"), // Clap error
                     Ok(command) => {
                         match command.run(player, saves, stc, text_context) {
-                            Err(err) => println!("Error: {err}"), // Command runtime error
+                            Err(err) => {
+                                log::warn!("Command failed: {err:?}");
+                                println!("Error: {err}") // Command runtime error
+                            }
                             Ok(result) => match result {
                                 CommandResult::Submit(loop_result) => return Ok(loop_result),
                                 CommandResult::Output(output) => println!("{output}"),
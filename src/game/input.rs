@@ -1,28 +1,185 @@
+#[cfg(feature = "gamepad")]
+use std::cell::RefCell;
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    io::{self, IsTerminal, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
 use anyhow::{anyhow, Result};
 use clap::Parser;
-use rustyline::DefaultEditor;
+use crossterm::{
+    cursor::{self, MoveToColumn},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers,
+        MouseButton, MouseEvent, MouseEventKind,
+    },
+    execute, queue,
+    style::Stylize,
+    terminal::{self, Clear, ClearType},
+};
+use rustyline::{
+    completion::Completer, highlight::Highlighter, hint::Hinter, history::DefaultHistory,
+    validate::Validator, Cmd, CompletionType, ConditionalEventHandler, Config,
+    Context as LineContext, Editor, Event as LineEvent, EventContext, EventHandler, Helper,
+    KeyEvent, RepeatCount, Result as LineResult,
+};
 
-use crate::cmd::runtime::RuntimeCommand;
+#[cfg(feature = "gamepad")]
+use super::gamepad::{Gamepad, GamepadAction};
+use crate::{
+    cmd::runtime::RuntimeCommand,
+    core::{
+        choice::ChoiceLayout,
+        manifest::{ChoiceAction, KeyBindings, Settings},
+        state::variables::NumberBounds,
+    },
+};
 
 #[derive(Debug)]
 pub struct InputController {
-    rl: DefaultEditor,
+    rl: Editor<CompletionHelper, DefaultHistory>,
     quit: bool,
+    bindings: KeyBindings,
+    /// Lines entered at choice prompts, including commands.
+    choice_history: VecDeque<String>,
+    /// Lines entered at free-text variable prompts, kept separately so each kind of prompt only recalls its own.
+    text_history: VecDeque<String>,
+    text_history_size: usize,
+    /// Whether the debug overlay is shown.
+    overlay: Arc<AtomicBool>,
+    /// Whether the pause key ended the line being read.
+    pause: Arc<AtomicBool>,
+    /// Whether the fast-forward key ended the line being read.
+    fast_forward: Arc<AtomicBool>,
+    /// Whether choices can be clicked; see [`Settings::mouse`].
+    mouse: bool,
+    /// Whether lines are drawn without moving the cursor back; see [`Settings::accessibility`].
+    accessible: bool,
+    #[cfg(feature = "gamepad")]
+    gamepad: RefCell<Gamepad>,
+}
+
+/// Offers tab-completion of free-text input against a known set of answers.
+///
+/// Pressing tab cycles through the answers matching the line so far, ignoring case.
+/// While several answers match, they're listed after the cursor as a hint.
+#[derive(Default)]
+struct CompletionHelper {
+    completions: Vec<String>,
+}
+
+impl CompletionHelper {
+    fn matches(&self, line: &str) -> Vec<String> {
+        let line = line.to_lowercase();
+        self.completions
+            .iter()
+            .filter(|completion| completion.to_lowercase().starts_with(&line))
+            .cloned()
+            .collect()
+    }
+}
+
+impl Helper for CompletionHelper {}
+impl Validator for CompletionHelper {}
+
+impl Completer for CompletionHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &LineContext,
+    ) -> LineResult<(usize, Vec<String>)> {
+        Ok((0, self.matches(&line[..pos])))
+    }
+}
+
+impl Hinter for CompletionHelper {
+    type Hint = String;
+
+    fn hint(&self, line: &str, pos: usize, _: &LineContext) -> Option<String> {
+        if line.is_empty() || pos < line.len() {
+            return None;
+        }
+        match self.matches(line).as_slice() {
+            [] => None,
+            [single] => single.get(line.len()..).map(ToOwned::to_owned),
+            several => Some(format!("  [{}]", several.join(", "))),
+        }
+    }
+}
+
+impl Highlighter for CompletionHelper {
+    fn highlight_hint<'h>(&self, hint: &'h str) -> Cow<'h, str> {
+        Cow::Owned(hint.dark_grey().to_string())
+    }
+}
+
+/// Toggles the debug overlay from within the line editor without changing the line.
+struct OverlayToggle(Arc<AtomicBool>);
+
+impl ConditionalEventHandler for OverlayToggle {
+    fn handle(&self, _: &LineEvent, _: RepeatCount, _: bool, _: &EventContext) -> Option<Cmd> {
+        self.0.fetch_xor(true, Ordering::Relaxed);
+        Some(Cmd::Noop)
+    }
+}
+
+/// Ends the line being read from within the line editor and raises a flag, such as so that the pause menu can be shown.
+struct LineInterrupt(Arc<AtomicBool>);
+
+impl ConditionalEventHandler for LineInterrupt {
+    fn handle(&self, _: &LineEvent, _: RepeatCount, _: bool, _: &EventContext) -> Option<Cmd> {
+        self.0.store(true, Ordering::Relaxed);
+        Some(Cmd::AcceptLine)
+    }
 }
 
 pub enum InputContext {
-    Choices(usize),
-    Variable(String, Option<String>),
+    /// The shortcut key of each usable choice, if it has one.
+    Choices(Vec<Option<char>>),
+    /// A variable name, an optional custom prompt, answers that the input can be tab-completed to,
+    /// the bounds of the number to enter, if only numbers are accepted, and whether the input is masked.
+    Variable(String, Option<String>, Vec<String>, Option<NumberBounds>, bool),
+    /// The number of options that can be toggled at a select prompt.
+    Selection(usize),
 }
 
 impl InputContext {
     const PROMPT: &'static str = "> ";
 
+    /// What's recorded in place of masked input, which never reveals its length.
+    pub const MASKED: &'static str = "*****";
+
+    /// Whether the input is [masked](crate::core::state::variables::VariableInput::masked), in which case it should be read
+    /// with [`take_timed`](InputController::take_timed) so that it's never echoed.
+    pub fn is_masked(&self) -> bool {
+        matches!(self, InputContext::Variable(.., true))
+    }
+
+    /// The line as it's drawn while typed, which is all asterisks if the input is masked.
+    ///
+    /// Commands are never masked so that the player can see what they're typing.
+    fn displayed<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        match self.is_masked() && !line.starts_with('.') {
+            true => Cow::Owned("*".repeat(line.chars().count())),
+            false => Cow::Borrowed(line),
+        }
+    }
+
     pub fn prompt(&self) -> String {
         use InputContext::*;
         match self {
-            Choices(_) => Self::PROMPT.to_owned(),
-            Variable(_, prompt) => prompt
+            Choices(_) | Selection(_) => Self::PROMPT.to_owned(),
+            Variable(_, prompt, ..) => prompt
                 .clone()
                 .map(|s| format!("{s}: "))
                 .unwrap_or(Self::PROMPT.to_owned()),
@@ -30,21 +187,228 @@ impl InputContext {
     }
 }
 
+/// The outcome of reading a line with [`InputController::take_timed`].
+enum TimedRead {
+    Line(String),
+    Quit,
+    Pause,
+    FastForward,
+    Timeout,
+    Idle,
+    /// A choice was clicked.
+    Choice(usize),
+}
+
 pub enum InputResult {
     Quit(bool),
+    /// The pause key was pressed.
+    Pause,
+    /// The fast-forward key was pressed.
+    FastForward,
     Choice(usize),
-    Variable { name: String, value: String },
+    /// The countdown given to [`take_timed`](InputController::take_timed) ran out.
+    Timeout,
+    /// No key was pressed for the idle period given to [`take_timed`](InputController::take_timed).
+    Idle,
+    Variable {
+        name: String,
+        value: String,
+    },
+    /// The numbers of the options to toggle at a select prompt.
+    Toggle(Vec<usize>),
+    /// The options picked at a select prompt were confirmed.
+    Confirm,
     Command(Result<RuntimeCommand>),
 }
 
 impl InputController {
-    pub fn new() -> Result<Self> {
+    /// How often [`take`](Self::take) runs its tick callback while waiting for a line.
+    const TICK_INTERVAL: Duration = Duration::from_millis(20);
+
+    /// How many lines entered at choice prompts are kept for recall.
+    const CHOICE_HISTORY_SIZE: usize = 100;
+
+    /// Creates an input controller from the manifest [`Settings`].
+    ///
+    /// The overlay binding is only kept in `debug` mode.
+    pub fn new(settings: &Settings) -> Result<Self> {
+        let mut bindings = settings.keybindings.clone();
+        if !settings.debug {
+            bindings.overlay = None;
+        }
+        let config = Config::builder()
+            .completion_type(CompletionType::Circular)
+            .build();
+        let mut rl = Editor::with_config(config)?;
+        rl.set_helper(Some(CompletionHelper::default()));
+        let overlay = Arc::new(AtomicBool::new(false));
+        if let Some(key) = bindings.quit {
+            rl.bind_sequence(KeyEvent::ctrl(key), Cmd::Interrupt);
+        }
+        let pause = Arc::new(AtomicBool::new(false));
+        if let Some(key) = bindings.pause {
+            rl.bind_sequence(
+                KeyEvent::ctrl(key),
+                EventHandler::Conditional(Box::new(LineInterrupt(pause.clone()))),
+            );
+        }
+        let fast_forward = Arc::new(AtomicBool::new(false));
+        if let Some(key) = bindings.fast_forward {
+            rl.bind_sequence(
+                KeyEvent::ctrl(key),
+                EventHandler::Conditional(Box::new(LineInterrupt(fast_forward.clone()))),
+            );
+        }
+        if let Some(key) = bindings.overlay {
+            rl.bind_sequence(
+                KeyEvent::ctrl(key),
+                EventHandler::Conditional(Box::new(OverlayToggle(overlay.clone()))),
+            );
+        }
         Ok(Self {
-            rl: DefaultEditor::new()?,
+            rl,
             quit: false,
+            bindings,
+            choice_history: VecDeque::new(),
+            text_history: VecDeque::new(),
+            text_history_size: settings.input_history.size,
+            overlay,
+            pause,
+            fast_forward,
+            mouse: settings.mouse && io::stdout().is_terminal(),
+            accessible: settings.accessibility,
+            #[cfg(feature = "gamepad")]
+            gamepad: RefCell::new(Gamepad::new(&settings.keybindings.gamepad)),
         })
     }
 
+    /// Replaces the free-text input history, such as with entries from a previous session.
+    pub fn restore_text_history(&mut self, entries: &[String]) {
+        self.text_history = entries.iter().cloned().collect();
+        Self::bound(&mut self.text_history, self.text_history_size);
+    }
+
+    /// The free-text input history, from oldest to newest.
+    pub fn text_history(&self) -> Vec<String> {
+        self.text_history.iter().cloned().collect()
+    }
+
+    fn bound(history: &mut VecDeque<String>, size: usize) {
+        while history.len() > size {
+            history.pop_front();
+        }
+    }
+
+    /// Records a successfully handled line in the history matching its [`InputContext`].
+    ///
+    /// A line repeating the previous entry is not recorded again, and masked lines are never recorded.
+    fn record(&mut self, line: String, context: &InputContext) {
+        if context.is_masked() {
+            return;
+        }
+        let (history, size) = match context {
+            InputContext::Choices(_) | InputContext::Selection(_) => {
+                (&mut self.choice_history, Self::CHOICE_HISTORY_SIZE)
+            }
+            InputContext::Variable(..) => (&mut self.text_history, self.text_history_size),
+        };
+        if history.back() != Some(&line) {
+            history.push_back(line);
+            Self::bound(history, size);
+        }
+    }
+
+    /// Loads the history matching an [`InputContext`] into the line editor so that only it can be recalled,
+    /// along with any tab-completions.
+    fn load_history(&mut self, context: &InputContext) -> Result<()> {
+        let history = match context {
+            InputContext::Choices(_) | InputContext::Selection(_) => &self.choice_history,
+            InputContext::Variable(..) => &self.text_history,
+        };
+        if let Some(helper) = self.rl.helper_mut() {
+            helper.completions = match context {
+                InputContext::Choices(_) | InputContext::Selection(_) => Vec::new(),
+                InputContext::Variable(_, _, completions, ..) => completions.clone(),
+            };
+        }
+        self.rl.clear_history()?;
+        for line in history {
+            self.rl.add_history_entry(line.as_str())?;
+        }
+        Ok(())
+    }
+
+    /// Asks a yes-or-no question, reading lines until it's answered.
+    ///
+    /// Signaling to quit or pause, or fast-forwarding, counts as answering no. Nothing is recorded in the input history.
+    pub fn confirm(&mut self, question: &str) -> Result<bool> {
+        self.rl.clear_history()?;
+        if let Some(helper) = self.rl.helper_mut() {
+            helper.completions = Vec::new();
+        }
+        let prompt = format!("{question} (y/n) ");
+        loop {
+            let line = self.rl.readline(&prompt);
+            let interrupted = self.pause.swap(false, Ordering::Relaxed)
+                | self.fast_forward.swap(false, Ordering::Relaxed);
+            let Ok(line) = line else {
+                return Ok(false);
+            };
+            if interrupted {
+                return Ok(false);
+            }
+            match line.trim().to_lowercase().as_str() {
+                "y" | "yes" => return Ok(true),
+                "n" | "no" => return Ok(false),
+                _ => println!("Answer 'y' or 'n'"),
+            }
+        }
+    }
+
+    /// Sets whether lines read by [`take_timed`](Self::take_timed) are drawn screen-reader-friendly,
+    /// following the player's accessibility preference.
+    pub fn set_accessible(&mut self, accessible: bool) {
+        self.accessible = accessible;
+    }
+
+    /// Whether choices can be clicked, in which case they should be read with [`take_timed`](Self::take_timed).
+    pub fn is_mouse_enabled(&self) -> bool {
+        self.mouse
+    }
+
+    /// Whether input in a context should be read with [`take_timed`](Self::take_timed) so that a gamepad
+    /// can be polled alongside the keyboard. This is only the case for choices when built with the `gamepad` feature.
+    pub fn uses_gamepad(&self, context: &InputContext) -> bool {
+        cfg!(feature = "gamepad") && matches!(context, InputContext::Choices(_))
+    }
+
+    /// Handles a [`GamepadAction`], moving the selected choice number in `line` between 1 and `choices`.
+    #[cfg(feature = "gamepad")]
+    fn use_gamepad_action(
+        action: GamepadAction,
+        line: &mut String,
+        choices: usize,
+    ) -> Option<TimedRead> {
+        let selected = line
+            .parse::<usize>()
+            .ok()
+            .filter(|n| (1..=choices).contains(n));
+        let next = match action {
+            GamepadAction::Up => selected.map_or(choices, |n| (n + choices - 2) % choices + 1),
+            GamepadAction::Down => selected.map_or(1, |n| n % choices + 1),
+            GamepadAction::Confirm => return selected.map(|n| TimedRead::Line(n.to_string())),
+            GamepadAction::Back => return Some(TimedRead::Line(".back".to_owned())),
+            GamepadAction::Pause => return Some(TimedRead::Pause),
+        };
+        *line = next.to_string();
+        None
+    }
+
+    /// A shared flag for whether the debug overlay is currently toggled on.
+    pub fn overlay_flag(&self) -> Arc<AtomicBool> {
+        self.overlay.clone()
+    }
+
     fn parse_command(line: String) -> Result<RuntimeCommand> {
         // Split line into command + arguments after '.' starting character
         let args: Vec<String> = line
@@ -56,48 +420,300 @@ impl InputController {
         RuntimeCommand::try_parse_from(args).map_err(|e| anyhow!(e))
     }
 
-    pub fn handle_line(line: String, context: &InputContext) -> Result<InputResult> {
-        if line.is_empty() {
-            return Err(anyhow!("Input cannot be empty"));
-        }
+    /// Handles a line of input within the given [`InputContext`].
+    ///
+    /// Empty lines are rejected unless a number with a default is being entered, or options are being confirmed.
+    pub fn handle_line(&self, line: String, context: &InputContext) -> Result<InputResult> {
         if line.starts_with(".") {
             return Ok(InputResult::Command(Self::parse_command(line)));
         }
+        let numeric = matches!(context, InputContext::Variable(.., Some(_), _));
+        let selection = matches!(context, InputContext::Selection(_));
+        if line.is_empty() && !numeric && !selection {
+            return Err(anyhow!("Input cannot be empty"));
+        }
         match context {
-            &InputContext::Choices(choices) => {
-                let choice = line
-                    .parse::<usize>()
-                    .map_err(|_| anyhow!("Input must be a number"))?;
-                if choice < 1 || choice > choices {
+            InputContext::Choices(keys) => {
+                let choice = match self.bindings.choice_action(&line) {
+                    Some(ChoiceAction::Back) => {
+                        return Ok(InputResult::Command(Ok(RuntimeCommand::Back)))
+                    }
+                    Some(ChoiceAction::Select(choice)) => choice,
+                    None => match line.parse::<usize>() {
+                        Ok(choice) => choice,
+                        Err(_) => Self::shortcut(keys, &line)
+                            .ok_or(anyhow!("Input must be a number or a choice's key"))?,
+                    },
+                };
+                if choice < 1 || choice > keys.len() {
                     return Err(anyhow!("Input out of range"));
                 }
                 Ok(InputResult::Choice(choice))
             }
-            InputContext::Variable(name, _) => Ok(InputResult::Variable {
+            InputContext::Variable(name, .., bounds, _) => Ok(InputResult::Variable {
                 name: name.clone(),
-                value: line,
+                value: match bounds {
+                    Some(bounds) => bounds.accept(&line)?,
+                    None => line,
+                },
             }),
+            InputContext::Selection(count) => Self::handle_selection(&line, *count),
         }
     }
 
-    pub fn take(&mut self, context: &InputContext) -> Result<InputResult> {
-        use InputResult::*;
-        match self.rl.readline(&context.prompt()) {
-            Ok(line) => {
-                if self.quit {
-                    self.quit = false;
+    /// Handles a line entered at a select prompt, which is either empty to confirm or option numbers to toggle,
+    /// separated by spaces or commas.
+    fn handle_selection(line: &str, count: usize) -> Result<InputResult> {
+        if line.is_empty() {
+            return Ok(InputResult::Confirm);
+        }
+        let numbers = line
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|part| !part.is_empty())
+            .map(|part| {
+                part.parse::<usize>()
+                    .ok()
+                    .filter(|number| (1..=count).contains(number))
+                    .ok_or(anyhow!("Input must be option numbers in range"))
+            })
+            .collect::<Result<_>>()?;
+        Ok(InputResult::Toggle(numbers))
+    }
+
+    /// The number of the choice whose shortcut key is the whole `line`, ignoring case.
+    fn shortcut(keys: &[Option<char>], line: &str) -> Option<usize> {
+        let mut chars = line.chars();
+        let (Some(c), None) = (chars.next(), chars.next()) else {
+            return None;
+        };
+        keys.iter()
+            .position(|key| key.is_some_and(|key| key.to_lowercase().eq(c.to_lowercase())))
+            .map(|index| index + 1)
+    }
+
+    /// Handles the result of reading a line, tracking whether the player has signaled to quit.
+    fn handle_read(&mut self, line: Option<String>, context: &InputContext) -> Result<InputResult> {
+        let Some(line) = line else {
+            let result = InputResult::Quit(self.quit);
+            self.quit = true;
+            return Ok(result);
+        };
+        self.quit = false;
+        let result = self.handle_line(line.trim().to_owned(), context)?;
+        self.record(line, context);
+        Ok(result)
+    }
+
+    /// Redraws the line being read by [`take_timed`](Self::take_timed) along with the seconds left to read it, if any.
+    ///
+    /// If [accessible](Self::set_accessible), the cursor is never moved back and there's no countdown. Instead, only the
+    /// characters added since the line was `drawn` are written, or the whole line again on a new line if any were removed.
+    fn draw_countdown(
+        &self,
+        prompt: &str,
+        line: &str,
+        remaining: Option<Duration>,
+        drawn: &mut Option<String>,
+    ) -> Result<()> {
+        let mut stdout = io::stdout();
+        if self.accessible {
+            match drawn.as_deref() {
+                Some(previous) if previous == line => return Ok(()),
+                Some(previous) if line.starts_with(previous) => {
+                    write!(stdout, "{}", &line[previous.len()..])?
                 }
-                let result = Self::handle_line(line.trim().to_owned(), context)?;
-                self.rl.add_history_entry(line)?;
-                Ok(result)
+                Some(_) => write!(stdout, "\r\n{prompt}{line}")?,
+                None => write!(stdout, "{prompt}{line}")?,
             }
-            Err(_) => {
-                let result = Quit(self.quit);
-                if !self.quit {
-                    self.quit = true;
+            stdout.flush()?;
+            *drawn = Some(line.to_owned());
+            return Ok(());
+        }
+        queue!(stdout, MoveToColumn(0), Clear(ClearType::CurrentLine))?;
+        match remaining {
+            Some(remaining) => {
+                let seconds = remaining.as_millis().div_ceil(1000);
+                write!(stdout, "[{seconds}] {prompt}{line}")?
+            }
+            None => write!(stdout, "{prompt}{line}")?,
+        }
+        stdout.flush()?;
+        Ok(())
+    }
+
+    /// Reads a line of input like [`take`](Self::take), but gives up once `remaining` runs out
+    /// or once no key has been pressed for the `idle` period.
+    ///
+    /// The line is read key by key so that a countdown can be drawn in front of it, and `remaining` is
+    /// left with however much time was unused. The countdown is frozen while a command is being typed.
+    ///
+    /// If a choice `layout` is given, mouse reporting is turned on so that clicking a choice selects it.
+    /// Terminals that don't support it are read from as usual.
+    pub fn take_timed(
+        &mut self,
+        context: &InputContext,
+        remaining: Option<&mut Duration>,
+        idle: Option<Duration>,
+        layout: Option<&ChoiceLayout>,
+        mut tick: impl FnMut(),
+    ) -> Result<InputResult> {
+        let mut line = String::new();
+        if let (true, Some(remaining)) = (self.accessible, remaining.as_deref()) {
+            println!("You have {} seconds to answer.", remaining.as_millis().div_ceil(1000));
+        }
+        terminal::enable_raw_mode()?;
+        let layout = layout.filter(|_| execute!(io::stdout(), EnableMouseCapture).is_ok());
+        let read = self.read_timed(context, &mut line, remaining, idle, layout, &mut tick);
+        if layout.is_some() {
+            let _ = execute!(io::stdout(), DisableMouseCapture);
+        }
+        terminal::disable_raw_mode()?;
+        println!();
+        match read? {
+            TimedRead::Line(line) => self.handle_read(Some(line), context),
+            TimedRead::Quit => self.handle_read(None, context),
+            TimedRead::Pause => Ok(InputResult::Pause),
+            TimedRead::FastForward => Ok(InputResult::FastForward),
+            TimedRead::Timeout => Ok(InputResult::Timeout),
+            TimedRead::Idle => Ok(InputResult::Idle),
+            TimedRead::Choice(choice) => {
+                self.quit = false;
+                Ok(InputResult::Choice(choice))
+            }
+        }
+    }
+
+    /// Reads keys into `line` until it is submitted, the player signals to quit, `remaining` runs out,
+    /// the player goes `idle`, or a choice in the `layout` is clicked.
+    ///
+    /// At a choice prompt, a connected gamepad can also select and submit a choice.
+    fn read_timed(
+        &self,
+        context: &InputContext,
+        line: &mut String,
+        mut remaining: Option<&mut Duration>,
+        idle: Option<Duration>,
+        layout: Option<&ChoiceLayout>,
+        tick: &mut impl FnMut(),
+    ) -> Result<TimedRead> {
+        let prompt = &context.prompt();
+        let input_row = layout
+            .and_then(|_| cursor::position().ok())
+            .map(|(_, row)| row);
+        let mut last = Instant::now();
+        let mut last_key = Instant::now();
+        let mut drawn = None;
+        loop {
+            if let Some(remaining) = remaining.as_deref_mut() {
+                if !line.starts_with('.') {
+                    *remaining = remaining.saturating_sub(last.elapsed());
+                }
+                if remaining.is_zero() {
+                    return Ok(TimedRead::Timeout);
+                }
+            }
+            last = Instant::now();
+            if idle.is_some_and(|idle| last_key.elapsed() >= idle) {
+                return Ok(TimedRead::Idle);
+            }
+            #[cfg(feature = "gamepad")]
+            if let InputContext::Choices(keys) = context {
+                let action = (!keys.is_empty())
+                    .then(|| self.gamepad.borrow_mut().poll())
+                    .flatten();
+                if let Some(action) = action {
+                    last_key = Instant::now();
+                    if let Some(read) = Self::use_gamepad_action(action, line, keys.len()) {
+                        return Ok(read);
+                    }
                 }
-                return Ok(result);
             }
+            self.draw_countdown(
+                prompt,
+                &context.displayed(line),
+                remaining.as_deref().copied(),
+                &mut drawn,
+            )?;
+            tick();
+            if !event::poll(Self::TICK_INTERVAL)? {
+                continue;
+            }
+            let key = match event::read()? {
+                Event::Key(key) => key,
+                Event::Mouse(MouseEvent {
+                    kind: MouseEventKind::Down(MouseButton::Left),
+                    row,
+                    ..
+                }) => {
+                    last_key = Instant::now();
+                    let clicked = layout
+                        .zip(input_row)
+                        .and_then(|(layout, input_row)| layout.choice_at(row, input_row));
+                    if let Some(choice) = clicked {
+                        *line = choice.to_string();
+                        self.draw_countdown(
+                            prompt,
+                            line,
+                            remaining.as_deref().copied(),
+                            &mut drawn,
+                        )?;
+                        return Ok(TimedRead::Choice(choice));
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            last_key = Instant::now();
+            let control = key.modifiers.contains(KeyModifiers::CONTROL);
+            match key.code {
+                KeyCode::Char(c)
+                    if control && ("cd".contains(c) || self.bindings.quit == Some(c)) =>
+                {
+                    return Ok(TimedRead::Quit)
+                }
+                KeyCode::Char(c) if control && self.bindings.pause == Some(c) => {
+                    return Ok(TimedRead::Pause)
+                }
+                KeyCode::Char(c) if control && self.bindings.fast_forward == Some(c) => {
+                    return Ok(TimedRead::FastForward)
+                }
+                KeyCode::Char(c) if control && self.bindings.overlay == Some(c) => {
+                    self.overlay.fetch_xor(true, Ordering::Relaxed);
+                }
+                KeyCode::Char(c) => line.push(c),
+                KeyCode::Backspace => {
+                    line.pop();
+                }
+                KeyCode::Enter => return Ok(TimedRead::Line(line.clone())),
+                _ => {}
+            }
+        }
+    }
+
+    /// Reads a line of input and handles it within the given [`InputContext`].
+    ///
+    /// The line is read on a separate thread so that `tick` can keep running on this one until it arrives.
+    pub fn take(&mut self, context: &InputContext, mut tick: impl FnMut()) -> Result<InputResult> {
+        let prompt = context.prompt();
+        self.load_history(context)?;
+        let rl = &mut self.rl;
+        let line = thread::scope(|scope| {
+            let reader = scope.spawn(|| rl.readline(&prompt));
+            while !reader.is_finished() {
+                tick();
+                thread::sleep(Self::TICK_INTERVAL);
+            }
+            reader.join().map_err(|_| anyhow!("Input thread panicked"))
+        })?;
+        if self.pause.swap(false, Ordering::Relaxed) {
+            return Ok(InputResult::Pause);
+        }
+        if self.fast_forward.swap(false, Ordering::Relaxed) {
+            return Ok(InputResult::FastForward);
         }
+        self.handle_read(line.ok(), context)
     }
 }
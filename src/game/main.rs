@@ -1,24 +1,98 @@
+use std::{io, time::Instant};
+
 use anyhow::{anyhow, Result};
+use crossterm::{
+    cursor::MoveTo,
+    execute,
+    style::Stylize,
+    terminal::{self, Clear, ClearType},
+};
+use result::OptionResultExt;
 
 use crate::{
     core::{
         choice::Choice,
         context::{StaticContext, TextContext},
         discord::RichPresence,
-        manifest::Manifest,
+        manifest::{AutosaveMode, Manifest},
         player::Player,
-        prompt::{Prompt, PromptModel},
+        prompt::{Chapter, Prompt, PromptModel},
         text::display::Text,
     },
-    loading::saves::SaveManager,
+    loading::{saves::SaveManager, watcher::ContentWatcher},
     text_context,
 };
 
 use super::{
-    gloop::{next_input_context, take_input, GameLoopResult},
+    gloop::{
+        apply_save_requests, run_hook, run_sequence, take_input, GameLoopResult, HookResult,
+        InputState,
+    },
     input::InputController,
 };
 
+/// Clears the terminal or prints a separator before a prompt from `file` is shown, based on the [`ScreenMode`](crate::core::manifest::ScreenMode)
+/// and whether the prompt starts a new `chapter`.
+///
+/// In accessibility mode, the terminal is never cleared, and there's only a blank line between prompts.
+/// The debug overlay redraws itself once input is read, so clearing doesn't lose it.
+fn prepare_screen(
+    previous: Option<&str>,
+    file: &str,
+    chapter: bool,
+    text_context: &TextContext,
+) -> Result<()> {
+    if previous.is_none() {
+        return Ok(());
+    }
+    if text_context.is_accessible() {
+        println!();
+        return Ok(());
+    }
+    if text_context
+        .config()
+        .settings
+        .screen_mode
+        .clears(previous, file, chapter)
+    {
+        execute!(io::stdout(), Clear(ClearType::All), MoveTo(0, 0))?;
+        return Ok(());
+    }
+    let width = terminal::size().map_or(40, |(width, _)| width.min(40) as usize);
+    println!("{}\n", "─".repeat(width).dark_grey());
+    Ok(())
+}
+
+/// Prints the title card of a [`Chapter`] with its filled `title`, and records it in the transcript.
+fn print_chapter(chapter: &Chapter, title: &str, text_context: &TextContext) -> Result<()> {
+    let subtitle = chapter
+        .subtitle
+        .as_ref()
+        .map(|subtitle| subtitle.fill(text_context))
+        .invert()?;
+    if text_context.is_accessible() {
+        println!("Chapter: {title}");
+        if let Some(subtitle) = &subtitle {
+            println!("{subtitle}");
+        }
+    } else {
+        let width = terminal::size().map_or(40, |(width, _)| width.min(40) as usize);
+        let rule = "═".repeat(width).dark_grey();
+        println!("{rule}\n{}", format!("{title:^width$}").bold());
+        if let Some(subtitle) = &subtitle {
+            println!("{}", format!("{subtitle:^width$}").italic());
+        }
+        println!("{rule}");
+    }
+    println!();
+    let transcript = &text_context.resources().transcript;
+    transcript.record_text(&format!("## {title}"));
+    if let Some(subtitle) = &subtitle {
+        transcript.record_text(&format!("*{subtitle}*"));
+    }
+    Ok(())
+}
+
 pub fn first_play_init(stc: &StaticContext, player: &mut Player) -> Result<()> {
     let text_context = text_context!(stc, player);
     if let Some(background) = &stc.config.entry.background {
@@ -29,13 +103,24 @@ pub fn first_play_init(stc: &StaticContext, player: &mut Player) -> Result<()> {
     Ok(())
 }
 
+/// The number of prompts in a row that [`on_enter`](Prompt::on_enter) hooks can move the player away from
+/// before the game loop assumes they're stuck in a cycle.
+const MAX_HOOK_JUMPS: usize = 100;
+
+/// Runs the game loop until the player quits or reaches an ending, returning [`GameLoopResult::Shutdown`].
+///
+/// If a [`ContentWatcher`] is provided and reports changes, returns [`GameLoopResult::Reload`] instead
+/// so that the resources can be reloaded before calling this again.
 pub fn begin(
     stc: &StaticContext,
     player: &mut Player,
     saves: &SaveManager,
     drpc: &mut Option<RichPresence>,
     input: &mut InputController,
-) -> Result<bool> {
+    watcher: &mut Option<ContentWatcher>,
+) -> Result<GameLoopResult> {
+    stc.resources.apply_preferences(player, stc.config)?;
+    stc.resources.resume_audio(player)?;
     if !player.began {
         first_play_init(stc, player)?;
     }
@@ -43,59 +128,326 @@ pub fn begin(
     stc.config
         .set_rich_presence(drpc, &player.latest_entry()?.path.to_string())?;
 
-    let silent = 'outer: loop {
+    let mut changed = || watcher.as_mut().is_some_and(ContentWatcher::changed);
+
+    // The file of the last prompt shown, for the screen mode
+    let mut previous_file: Option<String> = None;
+    // The number of prompts in a row left by their `on_enter` hooks
+    let mut hook_jumps = 0;
+    // Playtime is counted from here up to each input, including any text, sequences, and sounds shown before it
+    let mut counted_since = Instant::now();
+    stc.resources.pause.take_paused();
+    let result = 'outer: loop {
+        if changed() {
+            break 'outer GameLoopResult::Reload;
+        }
+        stc.resources.record_audio(player);
+        let path = player.latest_entry()?.path.clone();
+        log::info!("Entered prompt '{path}'");
+        let next_prompt = Prompt::get(&stc.resources.prompts, &path)?;
+        saves.autosave(player, AutosaveMode::EveryPrompt)?;
+        // Passes that stay on the same prompt, such as after a save request or a reload, keep the checkpoint as it was
+        let checkpoint = path.to_string();
+        if next_prompt.checkpoint && player.checkpoint.as_deref() != Some(checkpoint.as_str()) {
+            log::info!("Reached checkpoint '{path}'");
+            player.checkpoint = Some(checkpoint);
+            saves.write_checkpoint(player)?;
+        }
+        // Run before the text context is created so that the text sees any variables the hook sets
+        let mut arrangement = None;
+        if let Some(hook) = &next_prompt.on_enter {
+            match run_hook(hook, &path, Some(next_prompt), player, saves, stc)? {
+                HookResult::Moved => {
+                    hook_jumps += 1;
+                    if hook_jumps > MAX_HOOK_JUMPS {
+                        return Err(anyhow!(
+                            "`on_enter` hooks jumped {MAX_HOOK_JUMPS} times in a row, last from '{path}'"
+                        ));
+                    }
+                    continue 'outer;
+                }
+                HookResult::Stayed(arranged) => arrangement = arranged,
+            }
+        }
+        hook_jumps = 0;
         // Text context owns variables to avoid immutable and mutable borrow overlap
         let text_context = text_context!(stc, player);
         let entry = player.latest_entry()?;
-        let next_prompt = Prompt::get(&stc.resources.prompts, &entry.path)?;
         let model = next_prompt.model(&text_context)?;
-        let choices = next_prompt.usable_choices(&player.notes, &text_context)?;
+        let used = player.used_choices_at(&entry.path);
+        if next_prompt.shuffle_choices {
+            // Forked by the history length so that each visit can differ but redisplaying or going back doesn't
+            let random = player.random.fork(player.history.len() as u64);
+            arrangement = Some(next_prompt.shuffle_arrangement(
+                arrangement,
+                &used,
+                &random,
+                &text_context,
+            )?);
+        }
+        let choices = next_prompt.usable_choices(
+            &player.notes,
+            &used,
+            arrangement.as_ref(),
+            &text_context,
+        )?;
 
         if choices.is_empty() {
-            return Err(anyhow!("No usable choices"));
+            // Running out of one-time choices is expected in dialogue trees, so return to the previous prompt
+            let previous = player
+                .history
+                .iter()
+                .rev()
+                .nth(1)
+                .map(|previous| previous.path.clone());
+            match previous {
+                Some(previous) if !used.is_empty() && previous != path => {
+                    log::warn!(
+                        "Every choice at '{}' has been used; returning to '{previous}'",
+                        entry.path
+                    );
+                    player.jump(&previous, stc.config);
+                    continue;
+                }
+                // With nowhere else to return to, such as at the entry prompt, offer the used choices again rather than none
+                _ if !used.is_empty() => {
+                    log::warn!(
+                        "Every choice at '{path}' has been used and there's no prompt to return to; offering them again"
+                    );
+                    player.reset_used_choices(&path);
+                    continue;
+                }
+                _ => return Err(anyhow!("No usable choices")),
+            }
         }
 
-        let raw_choices: Vec<&Choice> = choices.iter().map(|(choice, _)| *choice).collect();
-        next_prompt.print(player, &model, entry.display, &raw_choices, &text_context)?;
+        let raw_choices: Vec<&Choice> = choices.iter().map(|(choice, ..)| *choice).collect();
+        // A chapter is only introduced when the player enters it from another
+        let chapter = next_prompt
+            .chapter
+            .as_ref()
+            .map(|chapter| {
+                chapter
+                    .title
+                    .fill(&text_context)
+                    .map(|title| (chapter, title))
+            })
+            .invert()?
+            .filter(|(_, title)| player.chapter.as_ref() != Some(title));
+        let display = entry.display;
+        if display {
+            let file = &entry.path.file;
+            prepare_screen(previous_file.as_deref(), file, chapter.is_some(), &text_context)?;
+            previous_file = Some(file.clone());
+        }
+        if !saves.see(&entry.path) {
+            stc.resources.fast_forward.stop();
+        }
+        stc.resources
+            .transcript
+            .enter(next_prompt.transcript.unwrap_or(true));
+        if let Some((chapter, title)) = chapter {
+            log::info!("Started chapter '{title}'");
+            if display {
+                print_chapter(chapter, &title, &text_context)?;
+            }
+            if let Some(sounds) = &chapter.sounds {
+                stc.resources.submit_audio(player, sounds, &text_context)?;
+            }
+            player.chapter = Some(title);
+        }
+        if let Some(music) = &next_prompt.music {
+            stc.resources.submit_music(player, music, &text_context)?;
+        }
+        stc.resources.pause.set_chapter(player.chapter.clone());
+        stc.resources.start_auto_advance();
+        next_prompt.print(
+            player,
+            &model,
+            display,
+            &raw_choices,
+            arrangement.as_ref(),
+            &text_context,
+        )?;
+        player.apply_script_changes(stc, &text_context)?;
+        if apply_save_requests(player, saves, stc)? {
+            continue 'outer;
+        }
+        if let (Some(sequence), true) = (&next_prompt.sequence, display) {
+            run_sequence(sequence, player, stc, &text_context)?;
+            player.apply_script_changes(stc, &text_context)?;
+            if apply_save_requests(player, saves, stc)? {
+                continue 'outer;
+            }
+        }
 
         match model {
             PromptModel::Redirect(choice) => {
-                player.choose_full(choice, &None, None, drpc, &model, stc, &text_context)?
+                player.choose_full(choice, &None, None, drpc, &model, stc, &text_context)?;
+                if let Some(hook) = &next_prompt.on_exit {
+                    run_hook(hook, &path, None, player, saves, stc)?;
+                }
             }
             PromptModel::Ending(lines) => {
                 Text::print_lines(lines, player, &text_context)?;
-                break 'outer true;
+                if let Some(checkpoint) = restart_from_checkpoint(player, saves, stc.config)? {
+                    stc.resources
+                        .replace_player(player, checkpoint, stc.config)?;
+                    previous_file = None;
+                    continue 'outer;
+                }
+                break 'outer GameLoopResult::Shutdown(true);
             }
-            _ => loop {
-                let context = next_input_context(&model, &choices, &text_context)?
-                    .ok_or(anyhow!("Could not resolve input context"))?;
-
-                match take_input(
-                    input,
-                    &context,
-                    player,
-                    saves,
-                    drpc,
-                    &model,
-                    &choices,
-                    stc,
-                    &text_context,
-                )? {
-                    GameLoopResult::Retry(flush) => {
-                        if flush {
-                            println!()
-                        }
+            _ => {
+                let mut state = InputState {
+                    model: &model,
+                    choices: &choices,
+                    timed: match model {
+                        PromptModel::Response => Choice::find_timed(&choices, &text_context)?,
+                        _ => None,
+                    },
+                    idle: next_prompt
+                        .idle
+                        .as_ref()
+                        .map(|handler| handler.after(&text_context).map(|after| (handler, after)))
+                        .invert()?,
+                };
+                loop {
+                    let result =
+                        take_input(input, &mut state, player, saves, drpc, stc, &text_context)?;
+                    count_playtime(&mut counted_since, player, stc);
+                    player.apply_script_changes(stc, &text_context)?;
+                    if apply_save_requests(player, saves, stc)? {
+                        continue 'outer;
                     }
-                    GameLoopResult::Continue => {
-                        println!();
-                        break;
+
+                    match result {
+                        GameLoopResult::Retry(flush) => {
+                            if flush {
+                                println!()
+                            }
+                            if changed() {
+                                break 'outer GameLoopResult::Reload;
+                            }
+                        }
+                        GameLoopResult::Continue => {
+                            if let Some(hook) = &next_prompt.on_exit {
+                                run_hook(hook, &path, None, player, saves, stc)?;
+                            }
+                            stc.resources.record_audio(player);
+                            saves.autosave(player, AutosaveMode::EveryChoice)?;
+                            println!();
+                            break;
+                        }
+                        // Silent shutdowns are endings
+                        GameLoopResult::Shutdown(true) => {
+                            if let Some(checkpoint) =
+                                restart_from_checkpoint(player, saves, stc.config)?
+                            {
+                                stc.resources
+                                    .replace_player(player, checkpoint, stc.config)?;
+                                previous_file = None;
+                                continue 'outer;
+                            }
+                            break 'outer GameLoopResult::Shutdown(true);
+                        }
+                        GameLoopResult::Shutdown(silent) => {
+                            break 'outer GameLoopResult::Shutdown(silent)
+                        }
+                        GameLoopResult::Reload => break 'outer GameLoopResult::Reload,
+                        GameLoopResult::ReconnectAudio => {
+                            break 'outer GameLoopResult::ReconnectAudio
+                        }
                     }
-                    GameLoopResult::Shutdown(silent) => break 'outer silent,
                 }
-            },
+            }
         }
     };
-    Ok(silent)
+    count_playtime(&mut counted_since, player, stc);
+    Ok(result)
+}
+
+/// Adds the time since `since`, minus the time spent [paused](crate::core::pause::PauseMenu::take_paused), to the
+/// player's playtime and restarts the count.
+fn count_playtime(since: &mut Instant, player: &mut Player, stc: &StaticContext) {
+    let paused = stc.resources.pause.take_paused();
+    player.add_playtime(since.elapsed().saturating_sub(paused), stc.config);
+    *since = Instant::now();
+}
+
+/// Offers to restart from the checkpoint save once the player reaches an ending, such as a death.
+///
+/// Only offered if the `player` has entered a [checkpoint](Prompt::checkpoint) prompt.
+/// Returns the checkpoint player if the player accepts.
+fn restart_from_checkpoint(
+    player: &Player,
+    saves: &SaveManager,
+    config: &Manifest,
+) -> Result<Option<Player>> {
+    let (Some(checkpoint), Some(slot)) = (&player.checkpoint, saves.last_checkpoint()) else {
+        return Ok(None);
+    };
+    println!();
+    let question = requestty::Question::confirm("checkpoint")
+        .message(format!("Restart from checkpoint '{checkpoint}' ({})?", slot.summary()))
+        .default(true)
+        .build();
+    if !requestty::prompt_one(question)?.as_bool().unwrap_or(false) {
+        return Ok(None);
+    }
+    println!();
+    saves.load_checkpoint(config).map(Some)
+}
+
+/// What the player picks to recover with after the game loop has crashed.
+enum Recovery {
+    Autosave,
+    Checkpoint,
+    Quit,
+}
+
+/// Offers to relaunch from the most recent autosave, or to restart from the checkpoint save if the `player` has entered
+/// a [checkpoint](Prompt::checkpoint) prompt, after the game loop has crashed.
+///
+/// Returns the picked player if either exists and the player picks one, or [`None`] if the crash should be reported.
+pub fn recover(config: &Manifest, saves: &SaveManager, player: &Player) -> Result<Option<Player>> {
+    let autosave = saves.last_autosave();
+    let checkpoint = player
+        .checkpoint
+        .as_ref()
+        .and_then(|checkpoint| Some((checkpoint, saves.last_checkpoint()?)));
+    let mut choices = Vec::new();
+    if let Some(autosave) = &autosave {
+        choices.push((
+            Recovery::Autosave,
+            format!("Relaunch from your last autosave, {}", autosave.label()),
+        ));
+    }
+    if let Some((checkpoint, slot)) = &checkpoint {
+        choices.push((
+            Recovery::Checkpoint,
+            format!("Restart from checkpoint '{checkpoint}' ({})", slot.summary()),
+        ));
+    }
+    if choices.is_empty() {
+        return Ok(None);
+    }
+    choices.push((Recovery::Quit, "Quit".to_owned()));
+    println!("\n{}\n", crash_context(config));
+    let question = requestty::Question::select("Recover from the crash?")
+        .choices(choices.iter().map(|(_, label)| label.clone()))
+        .build();
+    let index = requestty::prompt_one(question)?
+        .as_list_item()
+        .unwrap()
+        .index;
+    let result = match choices.swap_remove(index).0 {
+        Recovery::Autosave => saves.load_autosave(config)?,
+        Recovery::Checkpoint => saves.load_checkpoint(config)?,
+        Recovery::Quit => return Ok(None),
+    };
+    println!();
+    Ok(Some(result))
 }
 
 pub fn crash_context(config: &Manifest) -> String {
@@ -0,0 +1,129 @@
+use std::{
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use crossterm::{
+    cursor::{MoveTo, RestorePosition, SavePosition},
+    queue,
+    style::Stylize,
+    terminal,
+};
+use unicode_truncate::{Alignment, UnicodeTruncateStr};
+
+use crate::core::{context::StaticContext, player::Player};
+
+/// A debug panel drawn in the top right corner of the terminal while input is being read.
+///
+/// The panel lists the current prompt, the player's variables, their enabled channels, and the
+/// [state](crate::core::audio::ChannelState) of each channel. It's redrawn periodically so that playback positions stay current.
+pub struct DebugOverlay {
+    /// How many rows were drawn last time.
+    drawn: u16,
+    last_draw: Option<Instant>,
+}
+
+impl DebugOverlay {
+    /// The width of the panel in columns.
+    const WIDTH: usize = 40;
+    /// How often the panel is redrawn while shown.
+    const REDRAW_INTERVAL: Duration = Duration::from_millis(250);
+
+    pub fn new() -> Self {
+        Self {
+            drawn: 0,
+            last_draw: None,
+        }
+    }
+
+    fn lines(player: &Player, stc: &StaticContext) -> Vec<String> {
+        let mut lines = vec!["Debug".bold().to_string()];
+        if let Ok(entry) = player.latest_entry() {
+            lines.push(format!("prompt: {}", entry.path));
+        }
+        if let Some(checkpoint) = &player.checkpoint {
+            lines.push(format!("checkpoint: {checkpoint}"));
+        }
+        let mut variables: Vec<String> = player
+            .variables
+            .iter()
+            .map(|(name, value)| format!("  {name}: {value}"))
+            .collect();
+        variables.sort();
+        lines.push(format!("variables: {}", variables.len()));
+        lines.extend(variables);
+        let mut channels: Vec<&String> = player.channels.iter().collect();
+        channels.sort();
+        lines.push(format!("channels: {}", itertools::join(channels, ", ")));
+        if let Some(audio) = &stc.resources.audio {
+            let audio = audio.borrow();
+            let mut states = audio.channel_states();
+            states.sort_by_key(|(name, _)| *name);
+            for (name, state) in states {
+                let status = if state.is_playing {
+                    "playing"
+                } else {
+                    "paused"
+                };
+                let position = state
+                    .position
+                    .map(|(pos, duration)| {
+                        format!(" {:.1}s/{:.1}s", pos.as_secs_f32(), duration.as_secs_f32())
+                    })
+                    .unwrap_or_default();
+                lines.push(format!("  {name}: {status}{position}"));
+            }
+        }
+        lines
+    }
+
+    /// Clears the rows drawn last time.
+    fn clear(&mut self, stdout: &mut impl Write, column: u16) -> Result<()> {
+        for row in 0..self.drawn {
+            queue!(stdout, MoveTo(column, row))?;
+            write!(stdout, "{}", " ".repeat(Self::WIDTH))?;
+        }
+        self.drawn = 0;
+        Ok(())
+    }
+
+    /// Draws the panel if `shown`, or clears it if it was previously drawn.
+    ///
+    /// While shown, the panel is only redrawn once every [`REDRAW_INTERVAL`](Self::REDRAW_INTERVAL).
+    /// The cursor is returned to where it was afterwards so that the line being read is undisturbed.
+    pub fn update(&mut self, shown: bool, player: &Player, stc: &StaticContext) -> Result<()> {
+        if shown
+            && self
+                .last_draw
+                .is_some_and(|last| last.elapsed() < Self::REDRAW_INTERVAL)
+        {
+            return Ok(());
+        }
+        if !shown && self.drawn == 0 {
+            return Ok(());
+        }
+        let (columns, rows) = terminal::size()?;
+        let column = columns.saturating_sub(Self::WIDTH as u16);
+        let mut stdout = io::stdout();
+        queue!(stdout, SavePosition)?;
+        self.clear(&mut stdout, column)?;
+        if shown {
+            for (row, line) in Self::lines(player, stc)
+                .iter()
+                .take(rows as usize)
+                .enumerate()
+            {
+                queue!(stdout, MoveTo(column, row as u16))?;
+                write!(stdout, "{}", line.unicode_pad(Self::WIDTH, Alignment::Left, true))?;
+                self.drawn += 1;
+            }
+            self.last_draw = Some(Instant::now());
+        } else {
+            self.last_draw = None;
+        }
+        queue!(stdout, RestorePosition)?;
+        stdout.flush()?;
+        Ok(())
+    }
+}
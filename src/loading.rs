@@ -1,2 +1,7 @@
+pub mod exports;
 pub mod loader;
+pub mod logger;
+pub mod migrations;
+pub mod protection;
 pub mod saves;
+pub mod watcher;
@@ -0,0 +1,181 @@
+use anyhow::{anyhow, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+
+use crate::core::{manifest::Manifest, player::Player, prompt::Prompt, resources::Resources};
+
+use super::{loader::Loader, migrations, protection::SaveCodec, saves::SaveManager};
+
+#[derive(Serialize, Deserialize, Debug)]
+/// A player save written to a self-contained file so that it can be moved between machines or shared.
+///
+/// Exports are always [signed](SaveCodec::signed), even if the game's saves aren't protected, so that they can't be
+/// edited by hand and imported back. They record the game and game version they were exported from so that they
+/// can be checked before being imported.
+struct PortableSave<P> {
+    /// The [ID](crate::core::manifest::Metadata::game_id) of the game this was exported from.
+    game: String,
+    /// The version of the game this was exported from.
+    game_version: Version,
+    /// The exported player, including its save format version.
+    player: P,
+}
+
+/// The directory that exports are written to, which is also checked when importing a bare file name.
+pub fn dir(config: &Manifest) -> Result<Utf8PathBuf> {
+    Ok(SaveManager::generic_dir()?
+        .join(config.metadata.game_id())
+        .join("exports"))
+}
+
+/// Serializes and signs the player as the content of an export file.
+fn encode(player: &Player, config: &Manifest) -> Result<String> {
+    let save = PortableSave {
+        game: config.metadata.game_id().to_owned(),
+        game_version: config.metadata.version.clone(),
+        player,
+    };
+    Ok(SaveCodec::signed(config).encode(serde_yaml::to_string(&save)?))
+}
+
+/// Writes the player to a new file in the game's exports directory, returning its path.
+pub fn export(player: &Player, config: &Manifest) -> Result<Utf8PathBuf> {
+    let dir = dir(config)?;
+    std::fs::create_dir_all(&dir)?;
+    let file = dir.join(format!("{}.yml", chrono::Local::now().format("%Y-%m-%d_%H-%M-%S")));
+    std::fs::write(&file, encode(player, config)?)?;
+    Ok(file)
+}
+
+/// Resolves the `path` of an export, falling back to a file within the exports directory.
+fn resolve(path: &str, config: &Manifest) -> Result<Utf8PathBuf> {
+    let path = Utf8Path::new(path);
+    if path.exists() {
+        return Ok(path.to_path_buf());
+    }
+    let file = dir(config)?.join(path);
+    if !file.exists() {
+        return Err(anyhow!("Export file '{path}' doesn't exist"));
+    }
+    Ok(file)
+}
+
+/// Checks the signature of an export file's `content` and parses the player in it, upgrading it to the current save format.
+///
+/// The export must come from this game and from a version of it no newer than the current one.
+fn decode(content: String, config: &Manifest) -> Result<Player> {
+    let content = SaveCodec::signed(config).decode(content)?;
+    let mut save: PortableSave<Value> =
+        Loader::parse(content).with_context(|| "Failed to parse export file")?;
+    let game = config.metadata.game_id();
+    if save.game != game {
+        return Err(anyhow!(
+            "Incompatible export: it's from a different game ('{}', but this is '{game}')",
+            save.game
+        ));
+    }
+    let version = &config.metadata.version;
+    if save.game_version > *version {
+        return Err(anyhow!(
+            "Incompatible export: it's from a newer version of the game ({}, but this is {version})",
+            save.game_version
+        ));
+    }
+    migrations::migrate(&mut save.player)?;
+    serde_yaml::from_value(save.player).with_context(|| "Failed to parse the exported player")
+}
+
+/// Reads an exported player from `path` with [`decode`].
+///
+/// Every prompt in the player's history must still exist. Nothing is written, so a failed import never touches the existing save.
+pub fn import(path: &str, config: &Manifest, resources: &Resources) -> Result<Player> {
+    let file = resolve(path, config)?;
+    let mut player = decode(std::fs::read_to_string(&file)?, config)?;
+    if player.history.is_empty() {
+        return Err(anyhow!("Incompatible export: its history is empty"));
+    }
+    for entry in &player.history {
+        Prompt::get(&resources.prompts, &entry.path).with_context(|| {
+            anyhow!("Incompatible export: prompt {} no longer exists", entry.path)
+        })?;
+    }
+    player.sync_channels(config);
+    Ok(player)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(id: &str, version: &str) -> Manifest {
+        let mut config = Resources::test_manifest(id, "");
+        config.metadata.version = version.parse().unwrap();
+        config
+    }
+
+    /// Replaces a line of an export by re-signing it, as if the game had written it that way.
+    fn resign(content: &str, from: &str, to: &str, config: &Manifest) -> String {
+        let codec = SaveCodec::signed(config);
+        codec.encode(codec.decode(content.to_owned()).unwrap().replace(from, to))
+    }
+
+    #[test]
+    fn round_trip() {
+        let config = manifest("test", "1.0.0");
+        let mut player = Player::new(&config);
+        player.playtime = 42;
+        let imported = decode(encode(&player, &config).unwrap(), &config).unwrap();
+        assert_eq!(imported.playtime, 42);
+        assert_eq!(imported.version, migrations::CURRENT);
+    }
+
+    #[test]
+    fn rejects_edited_exports() {
+        let config = manifest("test", "1.0.0");
+        let content = encode(&Player::new(&config), &config).unwrap();
+        let edited = content.replace("playtime: 0", "playtime: 99");
+        assert_ne!(content, edited);
+        assert!(decode(edited, &config).is_err());
+    }
+
+    #[test]
+    fn rejects_other_games() {
+        let config = manifest("test", "1.0.0");
+        let content = encode(&Player::new(&config), &config).unwrap();
+        let err = decode(content, &manifest("other", "1.0.0")).unwrap_err();
+        assert!(format!("{err:#}").contains("edited"), "{err:#}");
+        let content = resign(
+            &encode(&Player::new(&config), &config).unwrap(),
+            "game: test",
+            "game: other",
+            &config,
+        );
+        let err = decode(content, &config).unwrap_err();
+        assert!(err.to_string().contains("different game"), "{err}");
+    }
+
+    #[test]
+    fn rejects_newer_game_versions() {
+        let config = manifest("test", "1.0.0");
+        let content = encode(&Player::new(&manifest("test", "2.0.0")), &manifest("test", "2.0.0"));
+        let err = decode(content.unwrap(), &config).unwrap_err();
+        assert!(err.to_string().contains("newer version of the game"), "{err}");
+        let older = encode(&Player::new(&config), &config).unwrap();
+        assert!(decode(older, &manifest("test", "1.1.0")).is_ok());
+    }
+
+    #[test]
+    fn rejects_newer_save_formats() {
+        let config = manifest("test", "1.0.0");
+        let content = resign(
+            &encode(&Player::new(&config), &config).unwrap(),
+            &format!("  version: {}", migrations::CURRENT),
+            &format!("  version: {}", migrations::CURRENT + 1),
+            &config,
+        );
+        let err = decode(content, &config).unwrap_err();
+        assert!(err.to_string().contains("Incompatible save"), "{err}");
+    }
+}
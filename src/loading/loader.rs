@@ -1,7 +1,8 @@
 use std::{
     collections::BTreeMap,
     fs::File,
-    io::{self, Cursor, Read, Seek},
+    io::{self, Read},
+    sync::Arc,
 };
 
 use anyhow::{anyhow, Context, Result};
@@ -13,12 +14,14 @@ use piz::{
     read::{as_tree, DirectoryContents, FileTree},
     ZipArchive,
 };
-use playback_rs::{Hint, Song};
 use result::OptionResultExt;
 use serde::de::DeserializeOwned;
 use walkdir::WalkDir;
 
-use crate::core::audio::Sounds;
+use crate::core::{
+    audio::{sound::SoundSource, SoundSources},
+    manifest::Manifest,
+};
 
 /// An ordered map of content container names to values within a single file.
 pub type ContentFile<T> = BTreeMap<String, T>;
@@ -36,6 +39,10 @@ pub enum Backend<'a> {
 pub struct Loader<'a> {
     dir: Utf8PathBuf,
     backend: Backend<'a>,
+    /// The directory within a [`Zip`](Backend::Zip) archive that contains the manifest.
+    root: Utf8PathBuf,
+    /// Mod directories whose content files shadow the game's, in the order they're applied.
+    mods: Vec<Utf8PathBuf>,
 }
 
 pub struct KeyedPath(String, Utf8PathBuf);
@@ -56,9 +63,27 @@ impl KeyedPath {
 }
 
 impl<'a> Loader<'a> {
+    /// The file extensions of game archives.
+    pub const ARCHIVE_EXTENSIONS: [&'static str; 2] = ["nage", "zip"];
+
+    /// Resolves the game at some target path.
+    ///
+    /// If nothing exists at the path, an archive with the same name and one of the
+    /// [`ARCHIVE_EXTENSIONS`](Self::ARCHIVE_EXTENSIONS) is used instead, if one exists.
+    pub fn resolve(target: Utf8PathBuf) -> Utf8PathBuf {
+        if target.exists() {
+            return target;
+        }
+        Self::ARCHIVE_EXTENSIONS
+            .iter()
+            .map(|ext| target.with_extension(ext))
+            .find(|archive| archive.is_file())
+            .unwrap_or(target)
+    }
+
     pub fn mapping(target: &Utf8PathBuf) -> Result<Option<Vec<u8>>> {
         if let Some(ext) = target.extension() {
-            if ext == "zip" {
+            if Self::ARCHIVE_EXTENSIONS.contains(&ext) {
                 let file = File::open(target)?;
                 let mapping = unsafe { Mmap::map(&file)? }.to_owned();
                 return Ok(Some(mapping));
@@ -92,21 +117,79 @@ impl<'a> Loader<'a> {
         Err(anyhow!("Unrecognized game type"))
     }
 
-    /// Constructs a loader from a base directory.
-    /// Any input paths will be inside this directory.
+    /// Finds the directory within an archive that contains the manifest.
+    ///
+    /// Archives made by compressing a game folder place everything within that folder,
+    /// so the shallowest manifest file is used. If there is none, the archive root is used.
+    fn archive_root(tree: &DirectoryContents) -> Utf8PathBuf {
+        tree.files()
+            .map(|file| file.path.to_path_buf())
+            .filter(|path| path.file_name() == Some(Manifest::FILE))
+            .min_by_key(|path| path.components().count())
+            .and_then(|path| path.parent().map(Utf8Path::to_path_buf))
+            .unwrap_or_default()
+    }
+
+    /// Constructs a loader from a base directory or archive.
+    /// Any input paths will be inside this directory, or the archive's [root](Self::archive_root).
     pub fn new(
         target: Utf8PathBuf,
         archive: &'a Option<ZipArchive<'a>>,
         tree: &'a Option<DirectoryContents<'a>>,
     ) -> Result<Self> {
         let backend = Self::backend(&target, archive, tree)?;
+        let root = match &backend {
+            Backend::Folder => Utf8PathBuf::new(),
+            Backend::Zip(_, tree) => Self::archive_root(tree),
+        };
         let result = Self {
             dir: target,
             backend,
+            root,
+            mods: Vec::new(),
         };
         Ok(result)
     }
 
+    /// Adds mod directories that override or add to the game's content, later mods taking precedence.
+    ///
+    /// Relative paths are resolved against the directory containing the game.
+    /// Only content directories are affected; the manifest is always the game's own.
+    pub fn add_mods<P>(&mut self, mods: &[P]) -> Result<()>
+    where
+        P: AsRef<Utf8Path>,
+    {
+        let base = match self.backend {
+            Backend::Folder => self.dir.clone(),
+            Backend::Zip(_, _) => self
+                .dir
+                .parent()
+                .unwrap_or(Utf8Path::new("."))
+                .to_path_buf(),
+        };
+        for dir in mods {
+            let dir = base.join(dir);
+            if !dir.is_dir() {
+                return Err(anyhow!("Mod directory '{dir}' doesn't exist"));
+            }
+            self.mods.push(dir);
+        }
+        Ok(())
+    }
+
+    /// Whether a content file path belongs to a mod rather than the game.
+    fn is_mod_path(&self, path: &Utf8Path) -> bool {
+        self.mods.iter().any(|dir| path.starts_with(dir))
+    }
+
+    /// The game directory, if content is loaded from a folder rather than an archive.
+    pub fn folder(&self) -> Option<&Utf8Path> {
+        match self.backend {
+            Backend::Folder => Some(&self.dir),
+            Backend::Zip(_, _) => None,
+        }
+    }
+
     pub fn dir_or_current(dir: Option<Utf8PathBuf>) -> Utf8PathBuf {
         dir.unwrap_or(Utf8PathBuf::from("."))
     }
@@ -115,6 +198,8 @@ impl<'a> Loader<'a> {
         Self {
             dir,
             backend: Backend::Folder,
+            root: Utf8PathBuf::new(),
+            mods: Vec::new(),
         }
     }
 
@@ -138,7 +223,7 @@ impl<'a> Loader<'a> {
         use Backend::*;
         match &self.backend {
             Folder => self.dir.join(path),
-            Zip(_, _) => path.as_ref().to_path_buf(),
+            Zip(_, _) => self.root.join(path),
         }
     }
 
@@ -184,6 +269,7 @@ impl<'a> Loader<'a> {
         };
         let result = match &self.backend {
             Folder => std::fs::read_to_string(full)?,
+            Zip(_, _) if self.is_mod_path(&full) => std::fs::read_to_string(full)?,
             Zip(archive, tree) => {
                 let reader = Self::create_reader(archive, tree, full)?;
                 io::read_to_string(reader)?
@@ -210,36 +296,55 @@ impl<'a> Loader<'a> {
         Self::parse(content).with_context(|| format!("Failed to parse {}", path.as_ref()))
     }
 
+    /// Lists the files within a directory on disk.
+    fn folder_files(full: &Utf8Path) -> Vec<KeyedPath> {
+        WalkDir::new(full)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_file())
+            .filter_map(move |e| {
+                let file_path = Utf8PathBuf::from_path_buf(e.path().to_path_buf()).ok()?;
+                Some(KeyedPath::new(file_path, full)?)
+            })
+            .collect()
+    }
+
     /// Iterates over content files, performs the specified operation on the file path,
     /// and combines the results into an ordered [`BTreeMap`].
+    ///
+    /// Files within [mods](Self::add_mods) replace any game file with the same key, but two game files
+    /// with the same key, such as `main.yml` and `main.yaml`, are an error.
     pub fn map_content<P, T, F>(&self, path: P, mapper: F) -> Result<BTreeMap<String, T>>
     where
         P: AsRef<Utf8Path>,
         F: Fn(Utf8PathBuf) -> Result<T>,
     {
         use Backend::*;
-        match &self.backend {
-            Folder => {
-                let full = self.get_path(&path);
-                WalkDir::new(&full)
-                    .into_iter()
-                    .filter_map(|e| e.ok())
-                    .filter(|e| e.path().is_file())
-                    .filter_map(move |e| {
-                        let file_path = Utf8PathBuf::from_path_buf(e.path().to_path_buf()).ok()?;
-                        Some(KeyedPath::new(file_path, &full)?)
-                    })
-                    .map(|KeyedPath(key, path)| Ok((key, mapper(path)?)))
-                    .collect()
-            }
-            Zip(_, tree) => {
-                let full = self.get_path(&path);
-                tree.files()
-                    .filter_map(|file| KeyedPath::new(file.path.to_owned().into_owned(), &full))
-                    .map(|KeyedPath(key, path)| Ok((key, mapper(path.to_path_buf())?)))
-                    .collect()
+        let full = self.get_path(&path);
+        let files = match &self.backend {
+            Folder => Self::folder_files(&full),
+            Zip(_, tree) => tree
+                .files()
+                .filter_map(|file| KeyedPath::new(file.path.to_owned().into_owned(), &full))
+                .collect(),
+        };
+        let mod_files = self
+            .mods
+            .iter()
+            .flat_map(|dir| Self::folder_files(&dir.join(&path)));
+        let mut keyed = BTreeMap::new();
+        for KeyedPath(key, path) in files {
+            if let Some(other) = keyed.insert(key.clone(), path.clone()) {
+                return Err(anyhow!("Content files {other} and {path} both define '{key}'"));
             }
         }
+        for KeyedPath(key, path) in mod_files {
+            keyed.insert(key, path);
+        }
+        keyed
+            .into_iter()
+            .map(|(key, path)| Ok((key, mapper(path)?)))
+            .collect()
     }
 
     /// Iterates over content files, reads them, and combines their content into a [`String`] map.
@@ -259,33 +364,52 @@ impl<'a> Loader<'a> {
         self.map_content(path, |local| Ok(self.load(local, false)?))
     }
 
-    fn load_sound_file<P>(&self, path: P) -> Result<Song>
+    /// Deserializes the definition files at a path, which is either a single file or a directory of them.
+    ///
+    /// Each result is paired with the path of the file it came from so that conflicts can point at it.
+    pub fn load_definitions<P, T>(&self, path: P) -> Result<Vec<(Utf8PathBuf, T)>>
+    where
+        P: AsRef<Utf8Path>,
+        T: DeserializeOwned,
+    {
+        let definitions: Vec<_> = self
+            .map_content(&path, |local| Ok((local.clone(), self.load(local, false)?)))?
+            .into_values()
+            .collect();
+        if definitions.is_empty() {
+            return Err(anyhow!("Included path {} doesn't exist", path.as_ref()));
+        }
+        Ok(definitions)
+    }
+
+    /// Creates a [`SoundSource`] for a sound file without decoding it.
+    ///
+    /// - For a [`Folder`](Backend::Folder) backend, the file is only read once the sound is decoded.
+    /// - For a [`Zip`](Backend::Zip) backend, the archived file is read into memory immediately.
+    fn load_sound_source<P>(&self, path: P) -> Result<SoundSource>
     where
         P: AsRef<Utf8Path>,
     {
         use Backend::*;
+        let path = path.as_ref().to_path_buf();
         let result = match self.backend {
-            Folder => Song::from_file(path.as_ref(), None),
+            Folder => SoundSource::File(path),
+            Zip(_, _) if self.is_mod_path(&path) => SoundSource::File(path),
             Zip(archive, tree) => {
-                let mut hint = Hint::new();
-                if let Some(extension) = path.as_ref().extension() {
-                    hint.with_extension(extension);
-                }
-                let mut reader = Self::create_reader(archive, tree, path)?;
-                let mut cursor = Cursor::new(Vec::new());
-                io::copy(&mut reader, &mut cursor)?;
-                cursor.seek(io::SeekFrom::Start(0))?;
-                Song::new(Box::new(cursor), &hint, None)
+                let mut reader = Self::create_reader(archive, tree, &path)?;
+                let mut content = Vec::new();
+                reader.read_to_end(&mut content)?;
+                SoundSource::Memory(path, Arc::new(content))
             }
         };
-        result.map_err(|err| anyhow!(err))
+        Ok(result)
     }
 
-    /// Loads and parses sounds using [`load_sound_file`].
-    pub fn load_sounds<P>(&self, path: P) -> Result<Sounds>
+    /// Collects sound sources using [`load_sound_source`](Self::load_sound_source).
+    pub fn load_sound_sources<P>(&self, path: P) -> Result<SoundSources>
     where
         P: AsRef<Utf8Path>,
     {
-        self.map_content(path, |local| Ok(self.load_sound_file(local)?))
+        self.map_content(path, |local| self.load_sound_source(local))
     }
 }
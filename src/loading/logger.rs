@@ -0,0 +1,121 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    sync::Mutex,
+};
+
+use anyhow::Result;
+use camino::{Utf8Path, Utf8PathBuf};
+use log::{LevelFilter, Log, Metadata, Record};
+
+use crate::core::manifest::Manifest;
+
+use super::saves::SaveManager;
+
+/// A [`Log`] implementation writing timestamped records to a rotating file in the game's data directory.
+///
+/// Logging is only set up if the manifest's `log_level` isn't `off`. Otherwise, the maximum level stays at
+/// [`LevelFilter::Off`] and the `log` macros return before formatting anything.
+pub struct GameLogger {
+    dir: Utf8PathBuf,
+    /// The current log file and the number of bytes written to it.
+    file: Mutex<(File, u64)>,
+}
+
+impl GameLogger {
+    /// The size a log file can grow to before it's rotated.
+    const MAX_SIZE: u64 = 1024 * 1024;
+    /// How many rotated log files to keep, not counting the current one.
+    const KEEP: usize = 3;
+
+    /// The directory log files are kept in.
+    pub fn dir(config: &Manifest) -> Result<Utf8PathBuf> {
+        let dir = SaveManager::generic_dir()?
+            .join(config.metadata.game_id())
+            .join("logs");
+        Ok(dir)
+    }
+
+    /// The path of a log file, where `0` is the current one.
+    fn file_path(dir: &Utf8Path, index: usize) -> Utf8PathBuf {
+        match index {
+            0 => dir.join("nage.log"),
+            _ => dir.join(format!("nage.{index}.log")),
+        }
+    }
+
+    /// Shifts every log file back an index, dropping the oldest, and opens a new current one.
+    fn rotate(dir: &Utf8Path) -> Result<File> {
+        for index in (0..Self::KEEP).rev() {
+            let file = Self::file_path(dir, index);
+            if file.exists() {
+                std::fs::rename(&file, Self::file_path(dir, index + 1))?;
+            }
+        }
+        Ok(File::create(Self::file_path(dir, 0))?)
+    }
+
+    /// Opens the current log file, rotating it first if it's already full.
+    fn open(dir: &Utf8Path) -> Result<(File, u64)> {
+        std::fs::create_dir_all(dir)?;
+        let path = Self::file_path(dir, 0);
+        let size = std::fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+        if size >= Self::MAX_SIZE {
+            return Ok((Self::rotate(dir)?, 0));
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok((file, size))
+    }
+
+    /// Installs the logger with the manifest's `log_level`, doing nothing if it's `off`.
+    pub fn init(config: &Manifest) -> Result<()> {
+        let level = config.settings.log_level.filter();
+        if level == LevelFilter::Off {
+            return Ok(());
+        }
+        let dir = Self::dir(config)?;
+        let logger = GameLogger {
+            file: Mutex::new(Self::open(&dir)?),
+            dir,
+        };
+        log::set_boxed_logger(Box::new(logger))?;
+        log::set_max_level(level);
+        Ok(())
+    }
+}
+
+impl Log for GameLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format!(
+            "{} {:<5} [{}] {}\n",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        let Ok(mut file) = self.file.lock() else {
+            return;
+        };
+        if file.1 + line.len() as u64 > Self::MAX_SIZE {
+            if let Ok(rotated) = Self::rotate(&self.dir) {
+                *file = (rotated, 0);
+            }
+        }
+        if file.0.write_all(line.as_bytes()).is_ok() {
+            file.1 += line.len() as u64;
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.0.flush();
+        }
+    }
+}
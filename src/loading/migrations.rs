@@ -0,0 +1,122 @@
+use anyhow::{anyhow, Result};
+use serde_yaml::{Mapping, Value};
+
+/// The version of the save format that [`Player`](crate::core::player::Player)s are currently written in.
+pub const CURRENT: u32 = 1;
+
+/// The key of the save format version within a save file. Saves from before it was added have no version,
+/// which is read as `0`.
+const KEY: &str = "version";
+
+/// A function that upgrades a save file from one version of the save format to the next.
+type Migration = fn(&mut Mapping) -> Result<()>;
+
+/// The migration from each version of the save format to the one after it, keyed by the version it upgrades from.
+const MIGRATIONS: &[(u32, Migration)] = &[(0, from_unversioned)];
+
+/// Saves from before the format was versioned only lack the version itself, so nothing else changes.
+fn from_unversioned(_: &mut Mapping) -> Result<()> {
+    Ok(())
+}
+
+/// The save format version of a save file.
+pub fn version(save: &Value) -> Result<u32> {
+    match save.get(KEY) {
+        None => Ok(0),
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or(anyhow!("Save format version must be a whole number")),
+    }
+}
+
+/// Upgrades a save file to the [current](CURRENT) save format, one version at a time.
+///
+/// Returns whether anything was migrated. If the save is newer than the current format or there's no
+/// migration from one of its versions, it's incompatible and an error is returned instead.
+pub fn migrate(save: &mut Value) -> Result<bool> {
+    let mut version = version(save)?;
+    if version > CURRENT {
+        return Err(anyhow!(
+            "Incompatible save: it was saved by a newer version of nage (save format {version}, but only up to {CURRENT} is supported)"
+        ));
+    }
+    let migrated = version < CURRENT;
+    let mapping = save
+        .as_mapping_mut()
+        .ok_or(anyhow!("Save file must be a map"))?;
+    while version < CURRENT {
+        let (_, migration) =
+            MIGRATIONS
+                .iter()
+                .find(|(from, _)| *from == version)
+                .ok_or(anyhow!(
+                    "Incompatible save: there's no way to upgrade it from save format {version}"
+                ))?;
+        migration(mapping)
+            .map_err(|err| anyhow!("Failed to upgrade save from format {version}: {err}"))?;
+        version += 1;
+        mapping.insert(KEY.into(), version.into());
+    }
+    Ok(migrated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::player::Player;
+
+    /// A save written before the save format was versioned.
+    const UNVERSIONED: &str = "\
+began: true
+lang: en_us
+channels: []
+notes: [met_shopkeeper]
+variables:
+  name: Bob
+info_pages: []
+log: []
+history:
+- path:
+    file: main
+    prompt: start
+  display: true
+  locked: false
+  redirect: false
+  notes: null
+  variables: null
+  log: false
+";
+
+    #[test]
+    fn migrates_unversioned_saves() {
+        let mut save: Value = serde_yaml::from_str(UNVERSIONED).unwrap();
+        assert_eq!(version(&save).unwrap(), 0);
+        assert!(migrate(&mut save).unwrap());
+        assert_eq!(version(&save).unwrap(), CURRENT);
+        let player: Player = serde_yaml::from_value(save).unwrap();
+        assert_eq!(player.version, CURRENT);
+        assert!(player.notes.contains("met_shopkeeper"));
+        assert_eq!(player.history[0].path.prompt, "start");
+    }
+
+    #[test]
+    fn leaves_current_saves_alone() {
+        let mut save: Value = serde_yaml::from_str(&format!("{KEY}: {CURRENT}")).unwrap();
+        assert!(!migrate(&mut save).unwrap());
+        assert_eq!(version(&save).unwrap(), CURRENT);
+    }
+
+    #[test]
+    fn rejects_newer_saves() {
+        let mut save: Value = serde_yaml::from_str(&format!("{KEY}: {}", CURRENT + 1)).unwrap();
+        let err = migrate(&mut save).unwrap_err();
+        assert!(err.to_string().contains("newer version of nage"), "{err}");
+    }
+
+    #[test]
+    fn rejects_invalid_versions() {
+        let mut save: Value = serde_yaml::from_str(&format!("{KEY}: -1")).unwrap();
+        assert!(migrate(&mut save).is_err());
+    }
+}
@@ -0,0 +1,139 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+use crate::core::manifest::Manifest;
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// How save files are protected from being edited by hand.
+///
+/// Protection only deters casual editing; it isn't cryptographically secure.
+pub enum SaveProtection {
+    #[default]
+    /// Saves are written as plain YAML.
+    Off,
+    /// Saves are written as plain YAML with a signature, so edits are detected but the save can still be read.
+    Signed,
+    /// Saves are signed and scrambled so that they can't be read either.
+    Obfuscated,
+}
+
+/// Reads and writes save file content according to the manifest's [`SaveProtection`].
+///
+/// Protected saves start with a [header](Self::HEADER) naming their protection and signature. The signature
+/// is keyed by the game's ID, so a save signed by one game can't be passed off as another's.
+#[derive(Debug, Clone)]
+pub struct SaveCodec {
+    mode: SaveProtection,
+    key: u64,
+}
+
+impl SaveCodec {
+    /// The start of the first line of every protected save.
+    const HEADER: &'static str = "# nage protected save; editing this file will make it unloadable";
+
+    const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    pub fn new(config: &Manifest) -> Self {
+        Self {
+            mode: config.settings.save_protection,
+            key: Self::hash(Self::FNV_OFFSET, config.metadata.game_id().as_bytes()),
+        }
+    }
+
+    /// Constructs a codec like [`new`](Self::new), but one that at least signs content even if the game's saves
+    /// aren't protected, such as for [exports](crate::loading::exports) that could otherwise be edited and imported back.
+    pub fn signed(config: &Manifest) -> Self {
+        let mut codec = Self::new(config);
+        if codec.mode == SaveProtection::Off {
+            codec.mode = SaveProtection::Signed;
+        }
+        codec
+    }
+
+    /// Hashes some bytes with FNV-1a, starting from a previous hash.
+    fn hash(start: u64, bytes: &[u8]) -> u64 {
+        bytes
+            .iter()
+            .fold(start, |hash, byte| (hash ^ *byte as u64).wrapping_mul(Self::FNV_PRIME))
+    }
+
+    /// The signature of some content, which changes if either the content or the game does.
+    fn sign(&self, content: &str) -> u64 {
+        let inner = Self::hash(self.key, content.as_bytes());
+        Self::hash(self.key.rotate_left(32), &inner.to_le_bytes())
+    }
+
+    /// XORs bytes with a keystream seeded by the signature, so that scrambling twice restores them.
+    fn scramble(&self, signature: u64, bytes: &mut [u8]) {
+        let mut state = (self.key ^ signature) | 1;
+        for byte in bytes {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            *byte ^= state as u8;
+        }
+    }
+
+    /// Protects the content of a save before it's written.
+    pub fn encode(&self, content: String) -> String {
+        let signature = self.sign(&content);
+        let body = match self.mode {
+            SaveProtection::Off => return content,
+            SaveProtection::Signed => content,
+            SaveProtection::Obfuscated => {
+                let mut bytes = content.into_bytes();
+                self.scramble(signature, &mut bytes);
+                bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+            }
+        };
+        let mode = match self.mode {
+            SaveProtection::Obfuscated => "obfuscated",
+            _ => "signed",
+        };
+        format!("{} ({mode} {signature:016x})\n{body}", Self::HEADER)
+    }
+
+    /// Checks and restores the content of a save after it's read.
+    ///
+    /// Protected saves are always checked, even if protection has since been turned off.
+    /// If protection is on, plain saves are rejected, since they could have been written by hand.
+    pub fn decode(&self, content: String) -> Result<String> {
+        let Some(rest) = content.strip_prefix(Self::HEADER) else {
+            if self.mode != SaveProtection::Off {
+                return Err(anyhow!(
+                    "This save isn't protected, but the game only loads protected saves"
+                ));
+            }
+            return Ok(content);
+        };
+        let (header, body) = rest.split_once('\n').unwrap_or((rest, ""));
+        let (mode, signature) = header
+            .trim()
+            .trim_start_matches('(')
+            .trim_end_matches(')')
+            .split_once(' ')
+            .ok_or(anyhow!("This save's protection header is damaged"))?;
+        let signature = u64::from_str_radix(signature, 16)
+            .map_err(|_| anyhow!("This save's protection header is damaged"))?;
+        let content = match mode {
+            "signed" => body.to_owned(),
+            "obfuscated" => {
+                let mut bytes = (0..body.trim_end().len())
+                    .step_by(2)
+                    .map(|index| u8::from_str_radix(body.get(index..index + 2)?, 16).ok())
+                    .collect::<Option<Vec<u8>>>()
+                    .ok_or(anyhow!("This save has been edited outside of the game"))?;
+                self.scramble(signature, &mut bytes);
+                String::from_utf8(bytes)
+                    .map_err(|_| anyhow!("This save has been edited outside of the game"))?
+            }
+            _ => return Err(anyhow!("This save uses an unknown protection '{mode}'")),
+        };
+        if self.sign(&content) != signature {
+            return Err(anyhow!("This save has been edited outside of the game"));
+        }
+        Ok(content)
+    }
+}
@@ -1,13 +1,96 @@
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    thread::{self, JoinHandle},
+    time::{Duration, SystemTime},
+};
+
 use anyhow::{anyhow, Context, Result};
 use camino::{Utf8Path, Utf8PathBuf};
 
-use crate::core::{manifest::Manifest, player::Player};
+use crate::core::{
+    manifest::{AutosaveMode, Manifest},
+    path::PathData,
+    player::Player,
+    text::display::format_duration,
+};
+
+use super::{loader::Loader, protection::SaveCodec};
+
+/// Details about a readable save file, shown when choosing between saves.
+pub struct SaveDetails {
+    /// The prompt the player was last at.
+    pub prompt: String,
+    /// The title of the chapter the player was last in, if any.
+    pub chapter: Option<String>,
+    /// The player's total playtime, in seconds.
+    pub playtime: u64,
+    /// When the save file was last written, if known.
+    pub modified: Option<SystemTime>,
+}
+
+/// A save file along with its [`SaveDetails`], or the error that prevented it from being read.
+pub struct SaveSlot {
+    pub file: Utf8PathBuf,
+    pub details: Result<SaveDetails>,
+}
+
+impl SaveSlot {
+    /// The label to display for this slot when choosing between saves.
+    ///
+    /// ### Examples
+    ///
+    /// - `my_save (The Harbor, chapter_1/start, 2h 13m played, saved 1h 5m ago)`
+    /// - `old_save (damaged)`
+    pub fn label(&self) -> String {
+        let name = self.file.file_stem().unwrap_or(self.file.as_str());
+        format!("{name} ({})", self.summary())
+    }
 
-use super::loader::Loader;
+    /// Where the save is and how long it's been played, or `damaged` if it can't be read.
+    pub fn summary(&self) -> String {
+        match &self.details {
+            Ok(details) => {
+                let saved = details
+                    .modified
+                    .and_then(|modified| modified.elapsed().ok())
+                    .map(|elapsed| format!(", saved {} ago", format_duration(elapsed)))
+                    .unwrap_or_default();
+                let playtime = format_duration(Duration::from_secs(details.playtime));
+                let chapter = details
+                    .chapter
+                    .as_ref()
+                    .map(|chapter| format!("{chapter}, "))
+                    .unwrap_or_default();
+                format!("{chapter}{}, {playtime} played{saved}", details.prompt)
+            }
+            Err(_) => "damaged".to_owned(),
+        }
+    }
+}
+
+/// What the player picks at launch when there's a [continue save](SaveManager::write_continue).
+enum LaunchChoice {
+    Continue,
+    Pick,
+    New,
+}
 
 pub struct SaveManager {
     dir: Utf8PathBuf,
     pub save_file: Option<Utf8PathBuf>,
+    /// Whether the player is picking up from the [continue save](Self::write_continue) rather than the save file.
+    continuing: bool,
+    /// How often to autosave and how many autosaves to keep.
+    autosave: (AutosaveMode, usize),
+    /// The thread writing the most recent autosave, if any.
+    autosaving: RefCell<Option<JoinHandle<()>>>,
+    /// The file that the prompts seen across every save are kept in.
+    seen_file: Utf8PathBuf,
+    /// The paths of the prompts the player has seen in any save, used for [fast-forwarding](crate::core::fast_forward::FastForward).
+    seen: RefCell<HashSet<String>>,
+    /// How save files are [protected](crate::loading::protection::SaveProtection) when written and read.
+    codec: SaveCodec,
 }
 
 impl SaveManager {
@@ -22,6 +105,14 @@ impl SaveManager {
         Ok(dir)
     }
 
+    /// The file that the prompts seen across every save are kept in, apart from the saves themselves.
+    fn seen_file(config: &Manifest) -> Result<Utf8PathBuf> {
+        let file = Self::generic_dir()?
+            .join(config.metadata.game_id())
+            .join("seen.yml");
+        Ok(file)
+    }
+
     fn dir(config: &Manifest) -> Result<Utf8PathBuf> {
         let dir = Self::game_dir(config)?;
         if !dir.exists() {
@@ -30,17 +121,59 @@ impl SaveManager {
         Ok(dir)
     }
 
+    /// Creates a save manager, picking the save file to load.
+    ///
+    /// If `pick` is set, or the last save file used is damaged, the player chooses between the saves.
+    /// If the manifest's [`save_on_quit`](crate::core::manifest::Settings::save_on_quit) setting is on and there's
+    /// a continue save, the player is first offered to continue from it, which keeps the last save file for manual saves.
     pub fn new(config: &Manifest, pick: bool, new: bool) -> Result<Self> {
         let dir = Self::dir(config)?;
+        let codec = SaveCodec::new(config);
         let saves = Self::saves(&dir)?;
+        let launch = match config.settings.save_on_quit && !new {
+            true => Self::offer_continue(&dir, !saves.is_empty(), &codec)?,
+            false => None,
+        };
+        let (continuing, pick, new) = match launch {
+            Some(LaunchChoice::Continue) => (true, false, false),
+            Some(LaunchChoice::Pick) => (false, true, false),
+            Some(LaunchChoice::New) => (false, false, true),
+            None => (false, pick, new),
+        };
         let save_file = if new || saves.is_empty() {
             None
+        } else if continuing {
+            Self::last_save_file(&dir).ok()
         } else if pick {
-            Some(Self::choose_save(&saves)?)
+            Self::choose_save(&dir, &saves, &codec)?
         } else {
-            Self::last_save_file(&dir).ok()
+            match Self::last_save_file(&dir) {
+                Ok(last) => match Self::read_player(&dir, &last, &codec) {
+                    Ok(_) => Some(last),
+                    Err(err) => {
+                        println!("Your last save file '{last}' is damaged: {err:#}\n");
+                        Self::choose_save(&dir, &saves, &codec)?
+                    }
+                },
+                Err(_) => None,
+            }
         };
-        Ok(Self { dir, save_file })
+        let autosave = (config.settings.autosave.mode, config.settings.autosave.slots);
+        let seen_file = Self::seen_file(config)?;
+        let seen = std::fs::read_to_string(&seen_file)
+            .ok()
+            .and_then(|content| serde_yaml::from_str(&content).ok())
+            .unwrap_or_default();
+        Ok(Self {
+            dir,
+            save_file,
+            continuing,
+            autosave,
+            autosaving: RefCell::new(None),
+            seen_file,
+            seen: RefCell::new(seen),
+            codec,
+        })
     }
 
     fn save_name_storage<P>(path: P) -> Utf8PathBuf
@@ -58,15 +191,40 @@ impl SaveManager {
         Ok(Utf8PathBuf::from(string))
     }
 
-    fn load_player<P>(&self, file: P) -> Result<Player>
+    fn read_player<P>(dir: &Utf8Path, file: P, codec: &SaveCodec) -> Result<Player>
     where
         P: AsRef<Utf8Path>,
     {
-        let content = std::fs::read_to_string(self.dir.join(&file))?;
-        Loader::parse(content)
+        let content = codec.decode(std::fs::read_to_string(dir.join(&file))?)?;
+        Player::load(content)
             .with_context(|| anyhow!("Failed to parse save file '{}'", file.as_ref()))
     }
 
+    fn load_player<P>(&self, file: P) -> Result<Player>
+    where
+        P: AsRef<Utf8Path>,
+    {
+        Self::read_player(&self.dir, file, &self.codec)
+    }
+
+    /// Reads a save file into a [`SaveSlot`], marking it as damaged rather than failing if it can't be read.
+    fn slot(dir: &Utf8Path, file: &Utf8Path, codec: &SaveCodec) -> SaveSlot {
+        let details = Self::read_player(dir, file, codec).and_then(|player| {
+            Ok(SaveDetails {
+                prompt: player.latest_entry()?.path.to_string(),
+                chapter: player.chapter.clone(),
+                playtime: player.playtime,
+                modified: std::fs::metadata(dir.join(file))
+                    .and_then(|metadata| metadata.modified())
+                    .ok(),
+            })
+        });
+        SaveSlot {
+            file: file.to_path_buf(),
+            details,
+        }
+    }
+
     fn saves<P>(dir: P) -> Result<Vec<Utf8PathBuf>>
     where
         P: AsRef<Utf8Path>,
@@ -79,31 +237,86 @@ impl SaveManager {
         Ok(result)
     }
 
-    fn choose_save<P>(saves: &Vec<P>) -> Result<Utf8PathBuf>
-    where
-        P: AsRef<Utf8Path>,
-    {
-        let save_names: Vec<String> = saves
+    /// Prompts the player to choose a save file, returning [`None`] if they choose to start a new one.
+    ///
+    /// Damaged save files are listed but can't be chosen.
+    fn choose_save(
+        dir: &Utf8Path,
+        saves: &[Utf8PathBuf],
+        codec: &SaveCodec,
+    ) -> Result<Option<Utf8PathBuf>> {
+        let slots: Vec<SaveSlot> = saves
             .iter()
-            .map(|save| save.as_ref().file_stem().map(ToString::to_string).unwrap())
+            .map(|save| Self::slot(dir, save, codec))
             .collect();
-        let prompt = requestty::Question::select("Choose a save file")
-            .choices(save_names)
-            .build();
-        let choice = requestty::prompt_one(prompt)?.as_list_item().unwrap().index;
+        let mut labels: Vec<String> = slots.iter().map(SaveSlot::label).collect();
+        labels.push("Start a new save".to_owned());
+        loop {
+            let prompt = requestty::Question::select("Choose a save file")
+                .choices(labels.clone())
+                .build();
+            let choice = requestty::prompt_one(prompt)?.as_list_item().unwrap().index;
 
-        println!();
+            println!();
 
-        Ok(saves[choice].as_ref().to_path_buf())
+            let Some(slot) = slots.get(choice) else {
+                return Ok(None);
+            };
+            match &slot.details {
+                Ok(_) => return Ok(Some(slot.file.clone())),
+                Err(err) => println!("This save file is damaged: {err:#}\n"),
+            }
+        }
     }
 
     pub fn load(&self, config: &Manifest) -> Result<Player> {
+        if self.continuing {
+            let dir = Self::continue_dir(&self.dir);
+            let mut player = Self::read_player(&dir, Self::CONTINUE_FILE, &self.codec)?;
+            player.sync_channels(config);
+            return Ok(player);
+        }
         match &self.save_file {
-            Some(save) => self.load_player(save),
+            Some(save) => {
+                let mut player = self.load_player(save)?;
+                player.sync_channels(config);
+                Ok(player)
+            }
             None => Ok(Player::new(config)),
         }
     }
 
+    /// The save file for a named slot, such as one requested by a script.
+    pub fn slot_file(slot: &str) -> Result<Utf8PathBuf> {
+        if slot.is_empty() || !sanitize_filename::is_sanitized(slot) {
+            return Err(anyhow!("Invalid save slot '{slot}'"));
+        }
+        Ok(Utf8PathBuf::from(format!("{slot}.yml")))
+    }
+
+    /// Ensures that a named slot exists in the saves directory and can be read.
+    pub fn check_slot(dir: &Utf8Path, slot: &str, codec: &SaveCodec) -> Result<()> {
+        let file = Self::slot_file(slot)?;
+        if !dir.join(&file).exists() {
+            return Err(anyhow!("Save slot '{slot}' doesn't exist"));
+        }
+        Self::read_player(dir, file, codec).map(|_| ())
+    }
+
+    /// Writes the player to a named slot without changing the current save file.
+    pub fn write_slot(&self, slot: &str, player: &Player) -> Result<()> {
+        let content = self.codec.encode(serde_yaml::to_string(player)?);
+        std::fs::write(self.dir.join(Self::slot_file(slot)?), content)?;
+        Ok(())
+    }
+
+    /// Loads the player from a named slot.
+    pub fn load_slot(&self, slot: &str, config: &Manifest) -> Result<Player> {
+        let mut player = self.load_player(Self::slot_file(slot)?)?;
+        player.sync_channels(config);
+        Ok(player)
+    }
+
     fn prompt_new_save_file() -> Result<String> {
         println!();
         let prompt = requestty::Question::input("Save file name")
@@ -122,18 +335,222 @@ impl SaveManager {
     where
         P: AsRef<Utf8Path>,
     {
-        if let Ok(content) = serde_yaml::to_string(player) {
+        if let Ok(content) = serde_yaml::to_string(player).map(|content| self.codec.encode(content))
+        {
             let _ = std::fs::write(self.dir.join(&save_file), content);
         }
     }
 
+    /// The path of an autosave slot, where slot `0` is the most recent.
+    fn autosave_file(dir: &Utf8Path, slot: usize) -> Utf8PathBuf {
+        dir.join(format!("autosave-{slot}.yml"))
+    }
+
+    /// Shifts every autosave back a slot, dropping the oldest, and writes the new one to the first slot.
+    fn rotate_autosaves(dir: &Utf8Path, slots: usize, content: &str) -> Result<()> {
+        std::fs::create_dir_all(dir)?;
+        for slot in (0..slots - 1).rev() {
+            let file = Self::autosave_file(dir, slot);
+            if file.exists() {
+                std::fs::rename(&file, Self::autosave_file(dir, slot + 1))?;
+            }
+        }
+        std::fs::write(Self::autosave_file(dir, 0), content)?;
+        Ok(())
+    }
+
+    /// Autosaves the player if the manifest's `autosave` mode matches.
+    ///
+    /// Autosaves are kept in their own directory apart from manual saves. The player is serialized immediately,
+    /// but the file is written on a separate thread so that the game doesn't stutter.
+    pub fn autosave(&self, player: &Player, mode: AutosaveMode) -> Result<()> {
+        let (configured, slots) = self.autosave;
+        if configured != mode || mode == AutosaveMode::Off {
+            return Ok(());
+        }
+        let content = self.codec.encode(serde_yaml::to_string(player)?);
+        let dir = self.autosave_dir();
+        // Wait for any previous autosave so that the rotations don't overlap
+        self.finish_autosave();
+        let handle = thread::spawn(move || {
+            let _ = Self::rotate_autosaves(&dir, slots, &content);
+        });
+        *self.autosaving.borrow_mut() = Some(handle);
+        Ok(())
+    }
+
+    /// The directory autosaves are kept in.
+    fn autosave_dir(&self) -> Utf8PathBuf {
+        self.dir.join("autosaves")
+    }
+
+    /// The most recent autosave as a [`SaveSlot`], if one exists and can be read.
+    ///
+    /// Waits for any autosave being written first.
+    pub fn last_autosave(&self) -> Option<SaveSlot> {
+        self.finish_autosave();
+        let dir = self.autosave_dir();
+        let file = Self::autosave_file(&dir, 0);
+        if !file.exists() {
+            return None;
+        }
+        let slot = Self::slot(&dir, file.strip_prefix(&dir).ok()?, &self.codec);
+        slot.details.is_ok().then_some(slot)
+    }
+
+    /// Loads the player from the most recent autosave.
+    pub fn load_autosave(&self, config: &Manifest) -> Result<Player> {
+        let dir = self.autosave_dir();
+        let mut player = Self::read_player(&dir, Self::autosave_file(&dir, 0), &self.codec)?;
+        player.sync_channels(config);
+        Ok(player)
+    }
+
+    /// Blocks until the autosave being written, if any, is finished.
+    pub fn finish_autosave(&self) {
+        if let Some(handle) = self.autosaving.borrow_mut().take() {
+            let _ = handle.join();
+        }
+    }
+
+    /// The name of the continue save within its [directory](Self::continue_dir).
+    const CONTINUE_FILE: &'static str = "continue.yml";
+
+    /// The directory the continue save is kept in, apart from manual saves.
+    fn continue_dir(dir: &Utf8Path) -> Utf8PathBuf {
+        dir.join("continue")
+    }
+
+    /// Asks the player whether to pick up from the continue save, if one exists and can be read.
+    fn offer_continue(
+        dir: &Utf8Path,
+        has_saves: bool,
+        codec: &SaveCodec,
+    ) -> Result<Option<LaunchChoice>> {
+        let continue_dir = Self::continue_dir(dir);
+        if !continue_dir.join(Self::CONTINUE_FILE).exists() {
+            return Ok(None);
+        }
+        let slot = Self::slot(&continue_dir, Utf8Path::new(Self::CONTINUE_FILE), codec);
+        if let Err(err) = &slot.details {
+            println!("Your continue save is damaged: {err:#}\n");
+            return Ok(None);
+        }
+        let mut choices = vec![
+            (LaunchChoice::Continue, format!("Continue ({})", slot.summary())),
+            (LaunchChoice::New, "Start a new save".to_owned()),
+        ];
+        if has_saves {
+            choices.insert(1, (LaunchChoice::Pick, "Choose a save file".to_owned()));
+        }
+        let question = requestty::Question::select("Continue where you left off?")
+            .choices(choices.iter().map(|(_, label)| label.clone()))
+            .build();
+        let index = requestty::prompt_one(question)?
+            .as_list_item()
+            .unwrap()
+            .index;
+        println!();
+        Ok(Some(choices.swap_remove(index).0))
+    }
+
+    /// Writes the player to the continue save when they quit.
+    ///
+    /// The save is written to a temporary file first and then moved into place,
+    /// so that a good continue save is never left half-overwritten.
+    pub fn write_continue(&self, player: &Player) -> Result<()> {
+        let content = self.codec.encode(serde_yaml::to_string(player)?);
+        Self::replace_file(&Self::continue_dir(&self.dir), Self::CONTINUE_FILE, &content)?;
+        self.write_seen();
+        Ok(())
+    }
+
+    /// Writes a save to a temporary file in `dir` and then moves it into place as `file`.
+    fn replace_file(dir: &Utf8Path, file: &str, content: &str) -> Result<()> {
+        std::fs::create_dir_all(dir)?;
+        let file = dir.join(file);
+        let temp = file.with_extension("yml.tmp");
+        std::fs::write(&temp, content)?;
+        std::fs::rename(&temp, &file)?;
+        Ok(())
+    }
+
+    /// The name of the checkpoint save within its [directory](Self::checkpoint_dir).
+    const CHECKPOINT_FILE: &'static str = "checkpoint.yml";
+
+    /// The directory the checkpoint save is kept in, apart from manual saves.
+    fn checkpoint_dir(&self) -> Utf8PathBuf {
+        self.dir.join("checkpoint")
+    }
+
+    /// Writes the player to the checkpoint save, replacing the last one, when they enter a [checkpoint](crate::core::prompt::Prompt::checkpoint) prompt.
+    ///
+    /// Like the [continue save](Self::write_continue), it's never left half-overwritten.
+    pub fn write_checkpoint(&self, player: &Player) -> Result<()> {
+        let content = self.codec.encode(serde_yaml::to_string(player)?);
+        Self::replace_file(&self.checkpoint_dir(), Self::CHECKPOINT_FILE, &content)
+    }
+
+    /// The checkpoint save as a [`SaveSlot`], if one exists and can be read.
+    pub fn last_checkpoint(&self) -> Option<SaveSlot> {
+        let dir = self.checkpoint_dir();
+        if !dir.join(Self::CHECKPOINT_FILE).exists() {
+            return None;
+        }
+        let slot = Self::slot(&dir, Utf8Path::new(Self::CHECKPOINT_FILE), &self.codec);
+        slot.details.is_ok().then_some(slot)
+    }
+
+    /// Loads the player from the checkpoint save.
+    pub fn load_checkpoint(&self, config: &Manifest) -> Result<Player> {
+        let mut player =
+            Self::read_player(&self.checkpoint_dir(), Self::CHECKPOINT_FILE, &self.codec)?;
+        player.sync_channels(config);
+        Ok(player)
+    }
+
+    /// Removes the continue save, such as once the player reaches an ending and there's nothing left to continue.
+    pub fn clear_continue(&self) {
+        let _ = std::fs::remove_file(Self::continue_dir(&self.dir).join(Self::CONTINUE_FILE));
+    }
+
+    /// Records that the player has seen a prompt, returning whether they had already seen it in any save.
+    pub fn see(&self, path: &PathData) -> bool {
+        !self.seen.borrow_mut().insert(path.to_string())
+    }
+
+    /// How many prompts the player has seen across every save.
+    pub fn seen_count(&self) -> usize {
+        self.seen.borrow().len()
+    }
+
+    /// Forgets every prompt the player has seen, so that fast-forwarding stops at all of them again.
+    pub fn forget_seen(&self) {
+        self.seen.borrow_mut().clear();
+        self.write_seen();
+    }
+
+    fn write_seen(&self) {
+        if let Ok(content) = serde_yaml::to_string(&*self.seen.borrow()) {
+            let _ = std::fs::write(&self.seen_file, content);
+        }
+    }
+
     pub fn write(&self, player: &Player) -> Result<()> {
         let save = match &self.save_file {
             Some(value) => value.clone(),
             None => Utf8PathBuf::from(Self::prompt_new_save_file()?),
         };
         self.write_player(&save, player);
+        self.write_seen();
         let _ = std::fs::write(Self::save_name_storage(&self.dir), save.to_string());
         Ok(())
     }
 }
+
+impl Drop for SaveManager {
+    fn drop(&mut self) {
+        self.finish_autosave();
+        self.write_seen();
+    }
+}
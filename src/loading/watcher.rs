@@ -0,0 +1,68 @@
+use std::{
+    collections::BTreeMap,
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use walkdir::WalkDir;
+
+/// The modification times of every file within the watched directories.
+type Snapshot = BTreeMap<Utf8PathBuf, SystemTime>;
+
+/// Polls the content directories read by [`Resources`](crate::core::resources::Resources) for file changes.
+pub struct ContentWatcher {
+    dir: Utf8PathBuf,
+    snapshot: Snapshot,
+}
+
+impl ContentWatcher {
+    /// The content directories that are watched, relative to the game directory.
+    const DIRS: [&'static str; 4] = ["prompts", "lang", "info", "scripts"];
+    /// How long files must go unchanged before a change is reported, guarding against partial writes.
+    const DEBOUNCE: Duration = Duration::from_millis(300);
+    /// How often files are checked while waiting for them to settle.
+    const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+    pub fn new(dir: &Utf8Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            snapshot: Self::snapshot(dir),
+        }
+    }
+
+    fn snapshot(dir: &Utf8Path) -> Snapshot {
+        Self::DIRS
+            .iter()
+            .flat_map(|content| WalkDir::new(dir.join(content)))
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_file())
+            .filter_map(|entry| {
+                let modified = entry.metadata().ok()?.modified().ok()?;
+                let path = Utf8PathBuf::from_path_buf(entry.into_path()).ok()?;
+                Some((path, modified))
+            })
+            .collect()
+    }
+
+    /// Whether any content files have been added, removed, or modified since the last call.
+    ///
+    /// If so, blocks until the files have stopped changing for [`DEBOUNCE`](Self::DEBOUNCE).
+    pub fn changed(&mut self) -> bool {
+        let mut current = Self::snapshot(&self.dir);
+        if current == self.snapshot {
+            return false;
+        }
+        let mut settled = Instant::now();
+        while settled.elapsed() < Self::DEBOUNCE {
+            thread::sleep(Self::POLL_INTERVAL);
+            let next = Self::snapshot(&self.dir);
+            if next != current {
+                current = next;
+                settled = Instant::now();
+            }
+        }
+        self.snapshot = current;
+        true
+    }
+}
@@ -1,17 +1,24 @@
 #![feature(result_flattening)]
 #![feature(iterator_try_collect)]
 
-use crate::core::{context::StaticContext, manifest::Manifest, resources::Resources};
+use crate::core::{
+    context::StaticContext, manifest::Manifest, reachability::PromptGraph, resources::Resources,
+    state::random::SeededRandom,
+};
+
+use std::time::Duration;
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Result};
 use camino::Utf8PathBuf;
 use clap::Parser;
 use cmd::cli::CliCommand;
+use crossterm::style::Stylize;
 use game::{
+    gloop::GameLoopResult,
     input::InputController,
-    main::{begin, crash_context},
+    main::{begin, crash_context, recover},
 };
-use loading::{loader::Loader, saves::SaveManager};
+use loading::{loader::Loader, logger::GameLogger, saves::SaveManager, watcher::ContentWatcher};
 
 mod cmd;
 mod core;
@@ -20,37 +27,151 @@ mod loading;
 
 pub const NAGE_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-fn run(path: Utf8PathBuf, pick: bool, new: bool) -> Result<()> {
+fn run(
+    path: Utf8PathBuf,
+    pick: bool,
+    new: bool,
+    mods: &[Utf8PathBuf],
+    seed: Option<u64>,
+    check: bool,
+) -> Result<()> {
     // Create content loader
     let mapping = Loader::mapping(&path)?;
     let archive = Loader::archive(&mapping)?;
     let tree = Loader::tree(&archive)?;
-    let loader = Loader::new(path, &archive, &tree)?;
+    let mut loader = Loader::new(path, &archive, &tree)?;
     // Load content and data
     let config = Manifest::load(&loader)?;
-    let resources = Resources::load(&loader, &config)?;
+    GameLogger::init(&config)?;
+    loader.add_mods(&config.settings.mods)?;
+    loader.add_mods(mods)?;
+    if check {
+        return check_game(&loader, &config);
+    }
+    let mut resources = Resources::load(&loader, &config)?;
     // Load player
     let saves = SaveManager::new(&config, pick, new)?;
     let mut player = saves.load(&config)?;
+    if let Some(seed) = seed {
+        player.random = SeededRandom::new(seed);
+    }
     // Validate loaded resources
-    let stc = StaticContext::new(&config, &resources);
-    resources.validate(&stc)?;
+    resources.validate(&StaticContext::new(&config, &resources))?;
+    // Watch content files for changes if enabled
+    let mut watcher = config
+        .settings
+        .hot_reload
+        .then(|| loader.folder().map(ContentWatcher::new))
+        .flatten();
     // Load rich presence
     let mut drpc = config.connect_rich_presence();
     // Create input controller
-    let mut input = InputController::new()?;
-    // Begin game loop
-    let silent = begin(&stc, &mut player, &saves, &mut drpc, &mut input)
-        .with_context(|| crash_context(&config))?;
+    let mut input = InputController::new(&config.settings)?;
+    if config.settings.input_history.save {
+        input.restore_text_history(&player.input_history);
+    }
+    // Begin game loop, reloading resources when content changes
+    let silent = loop {
+        let stc = StaticContext::new(&config, &resources);
+        let result = match begin(&stc, &mut player, &saves, &mut drpc, &mut input, &mut watcher) {
+            Ok(result) => result,
+            // Quits are clean results, so any error here is a crash
+            Err(err) => {
+                log::error!("Crashed: {err:?}");
+                match recover(&config, &saves, &player)? {
+                    Some(autosave) => {
+                        player = autosave;
+                        continue;
+                    }
+                    None => return Err(err.context(crash_context(&config))),
+                }
+            }
+        };
+        match result {
+            GameLoopResult::Shutdown(silent) => break silent,
+            GameLoopResult::ReconnectAudio => match resources.reconnect_audio(&loader, &config) {
+                Ok(_) => println!("Connected to the sound device\n"),
+                Err(err) => println!("Failed to connect to the sound device: {err:#}\n"),
+            },
+            _ => match resources.reload(&loader, &config, &player) {
+                Ok(_) => println!("Reloaded content\n"),
+                Err(err) => println!("Failed to reload content: {err:?}\n"),
+            },
+        }
+    };
     // Shut down game with silence based on game loop result
     if !silent {
         println!("Exiting...");
     }
+    // Keep the player's place for next launch, unless there's nothing left to continue
+    if config.settings.save_on_quit {
+        match silent {
+            false => {
+                resources.record_audio(&mut player);
+                saves.write_continue(&player)?;
+            }
+            true => saves.clear_continue(),
+        }
+    }
     // Save player data
     if config.settings.save {
+        resources.record_audio(&mut player);
         saves.write(&player)?;
     }
+    // Fade out any audio after it's been recorded in the save
+    if let (Some(audio), false) = (&resources.audio, silent) {
+        let fade = Duration::from_millis(config.settings.shutdown_fade);
+        audio.borrow_mut().fade_out_all(fade)?;
+    }
+
+    Ok(())
+}
+
+/// Loads and validates the game without running it or connecting to sound devices, printing every problem found.
+///
+/// Returns an [`Err`] if there are any problems so that the process exits with a nonzero code.
+fn check_game(loader: &Loader, config: &Manifest) -> Result<()> {
+    let resources = Resources::load_headless(loader, config)?;
+    let stc = StaticContext::new(config, &resources);
+    for warning in resources.warnings(&stc) {
+        println!("{} {warning}", "Warning:".yellow());
+    }
+    let problems = resources.problems(&stc);
+    if problems.is_empty() {
+        println!("No problems found");
+        return Ok(());
+    }
+    for problem in &problems {
+        println!("{} {problem:?}\n", "Error:".red());
+    }
+    Err(anyhow!("Found {} problem(s)", problems.len()))
+}
 
+/// Loads the game without running it and writes its [`PromptGraph`] as DOT to `output`, or to stdout if there is none.
+///
+/// Jumps that can't be resolved or followed are left out of the graph and printed as warnings.
+fn export_graph(
+    path: Utf8PathBuf,
+    mods: &[Utf8PathBuf],
+    output: Option<Utf8PathBuf>,
+) -> Result<()> {
+    let mapping = Loader::mapping(&path)?;
+    let archive = Loader::archive(&mapping)?;
+    let tree = Loader::tree(&archive)?;
+    let mut loader = Loader::new(path, &archive, &tree)?;
+    let config = Manifest::load(&loader)?;
+    loader.add_mods(&config.settings.mods)?;
+    loader.add_mods(mods)?;
+    let resources = Resources::load_headless(&loader, &config)?;
+    let graph = PromptGraph::build(&resources.prompts);
+    for problem in graph.errors.iter().chain(&graph.warnings) {
+        eprintln!("{} {problem}", "Warning:".yellow());
+    }
+    let dot = graph.to_dot(&config.metadata.name, &config.entry.path);
+    match output {
+        Some(file) => std::fs::write(file, dot)?,
+        None => print!("{dot}"),
+    }
     Ok(())
 }
 
@@ -58,8 +179,19 @@ fn main() -> Result<()> {
     // Parse CLI command - if 'run', use logic above
     // otherwise, uses its own method
     let command = CliCommand::parse();
-    if let CliCommand::Run { path, pick, new } = command {
-        return run(Loader::dir_or_current(path), pick, new);
+    if let CliCommand::Run {
+        path,
+        pick,
+        new,
+        mods,
+        seed,
+        check,
+    } = command
+    {
+        return run(Loader::resolve(Loader::dir_or_current(path)), pick, new, &mods, seed, check);
+    }
+    if let CliCommand::Graph { path, mods, output } = command {
+        return export_graph(Loader::resolve(Loader::dir_or_current(path)), &mods, output);
     }
     command.run()
 }